@@ -1,16 +1,13 @@
 use log::{LevelFilter, error, info};
-use serialport::{DataBits, FlowControl, Parity, StopBits};
-use std::collections::HashSet;
 use std::time::Duration;
 use tokio::time::sleep;
-use tokio_serial::SerialPortBuilderExt;
 
 #[path = "../examples/lib/common.rs"]
 mod common;
 use crate::common::{AppError, get_args};
 use common::logger_builder;
-use r200_uhf::Rfid;
-use r200_uhf::connector::{AsyncIO, Connector};
+use r200_uhf::TagSet;
+use r200_uhf::connector::{AsyncConnector, AsyncIO};
 
 #[allow(unreachable_code)]
 #[tokio::main]
@@ -20,27 +17,21 @@ async fn main() -> Result<(), AppError> {
     let (port_name, baud, power) = get_args()?;
 
     info!("Opening port {} at {} baud...", port_name, baud);
-    let port = tokio_serial::new(&port_name, baud)
-        .data_bits(DataBits::Eight)
-        .parity(Parity::None)
-        .stop_bits(StopBits::One)
-        .flow_control(FlowControl::None)
-        .open_native_async()?;
-
-    let mut connector = Connector::new(port);
+    let mut connector = AsyncConnector::open_async(&port_name, baud)
+        .map_err(|e| AppError::Connector(e.to_string()))?;
 
     // It's possible that the device was not correct terminated and the multiple polling instruction
     // is enabled. Send a stop.
     loop {
-        if connector
-            .stop_multiple_polling_instructions()
-            .await
-            .is_err()
-        {
-            error!("FAIL: Connector stop multiple polling");
-            sleep(Duration::from_millis(500)).await;
-        } else {
-            break;
+        match connector.stop_multiple_polling_instructions().await {
+            Ok(drained) => {
+                info!("Multiple polling stopped, {} frames discarded", drained);
+                break;
+            }
+            Err(_) => {
+                error!("FAIL: Connector stop multiple polling");
+                sleep(Duration::from_millis(500)).await;
+            }
         }
     }
 
@@ -71,8 +62,8 @@ async fn main() -> Result<(), AppError> {
         .get_transmit_power()
         .await
         .map_err(|e| AppError::Connector(e.to_string()))?;
-    info!("Transmission power {:?}", transmission_power);
-    if transmission_power != power {
+    info!("Transmission power {}", transmission_power);
+    if transmission_power.dbm != power {
         info!(
             "Set transmission power {:?}",
             connector
@@ -94,22 +85,22 @@ async fn main() -> Result<(), AppError> {
         sleep(Duration::from_millis(150));
     }*/
 
-    let mut unique_rfids: HashSet<Rfid> = HashSet::new();
+    // One entry per EPC, with the read count
+    let mut unique_rfids = TagSet::new();
 
     // Loop for 10 times with multiple polling instruction
     for sequence in 0..10 {
-        for i in connector
-            .multi_polling_instruction()
-            .await
-            .map_err(|e| AppError::Connector(e.to_string()))?
-        {
-            unique_rfids.insert(i.clone());
-        }
+        unique_rfids.extend(
+            connector
+                .multi_polling_instruction()
+                .await
+                .map_err(|e| AppError::Connector(e.to_string()))?,
+        );
 
         println!("|     SEQUENCE: {sequence}   |");
         println!("|     RFID_UNICI     |");
         for rfid in unique_rfids.iter() {
-            println!("| {} |", rfid);
+            println!("| {} x{} |", rfid.epc, rfid.reads);
         }
         println!("|  TOTAL: {}     |", unique_rfids.len());
     }
@@ -4,7 +4,7 @@ use std::{env, fmt};
 
 /*
 TRANSMISSION POWER: 23.6 W
-The working range could be between 15 and 26 for this chip.
+The working range is 15 to 26 dBm for this chip, see `get_power_range`.
 Having not found a national update that allows a power greater than that provided by
 ETSI for 867.9 MHz in that portion (0.5 W ERP),
 the safe assumption is that in Europe the limit for 867.9 MHz remains 0.5 W ERP,
@@ -31,6 +31,12 @@ impl fmt::Display for AppError {
 
 impl std::error::Error for AppError {}
 
+impl From<r200_uhf::connector::ConnectorError> for AppError {
+    fn from(err: r200_uhf::connector::ConnectorError) -> Self {
+        AppError::Connector(err.to_string())
+    }
+}
+
 impl From<serialport::Error> for AppError {
     fn from(err: serialport::Error) -> Self {
         AppError::Serial(err.to_string())
@@ -1,6 +1,4 @@
 use log::{LevelFilter, error, info};
-use r200_uhf::Rfid;
-use std::collections::HashSet;
 use std::thread::sleep;
 use std::time::Duration;
 
@@ -8,31 +6,39 @@ use std::time::Duration;
 mod common;
 use crate::common::{AppError, get_args};
 use common::logger_builder;
-use r200_uhf::connector::Connector;
 use r200_uhf::connector::sync::SyncIO;
+use r200_uhf::connector::{Connector, ConnectorBuilder};
 
 fn main() -> Result<(), AppError> {
     logger_builder(LevelFilter::Info);
 
     let (port_name, baud, power) = get_args().unwrap();
 
+    if let Ok(ports) = Connector::available_ports() {
+        for port in ports.iter().filter(|p| p.is_probably_r200()) {
+            info!("Possible R200 reader on {}", port.name);
+        }
+    }
+
     info!("Opening port {} at {} baud...", port_name, baud);
-    let port = serialport::new(&port_name, baud)
-        //  .parity(serialport::Parity::Even)
+    let mut connector = ConnectorBuilder::new()
+        .port(&port_name)
+        .baud(baud)
         .timeout(Duration::from_millis(500))
-        .open()
-        .map_err(|e| AppError::Serial(format!("Failed to open {}: {}", port_name, e)))?;
-
-    let mut connector = Connector::new(port);
+        .build()?;
 
     // It's possible that the device was not correct terminated and the multiple polling instruction
-    // is enabled. Send a stop.
+    // is enabled (e.g. the previous run was ended with Ctrl-C, Drop does not run). Send a stop.
     loop {
-        if connector.stop_multiple_polling_instructions().is_err() {
-            error!("FAIL: Connector stop multiple polling");
-            sleep(Duration::from_millis(500));
-        } else {
-            break;
+        match connector.stop_multiple_polling_instructions() {
+            Ok(drained) => {
+                info!("Multiple polling stopped, {} frames discarded", drained);
+                break;
+            }
+            Err(_) => {
+                error!("FAIL: Connector stop multiple polling");
+                sleep(Duration::from_millis(500));
+            }
         }
     }
 
@@ -59,8 +65,8 @@ fn main() -> Result<(), AppError> {
     let trasmission_power = connector
         .get_transmit_power()
         .map_err(|e| AppError::Connector(e.to_string()))?;
-    info!("Trasmissione power {:?}", trasmission_power);
-    if trasmission_power != power {
+    info!("Trasmissione power {}", trasmission_power);
+    if trasmission_power.dbm != power {
         info!(
             "Set trasmission power {:?}",
             connector
@@ -81,23 +87,21 @@ fn main() -> Result<(), AppError> {
         sleep(Duration::from_millis(150));
     }*/
 
-    let mut unique_rfids: HashSet<Rfid> = HashSet::new();
-
     // Loop for 10 times with multiple polling instruction
     for sequence in 0..10 {
-        for i in connector
-            .multi_polling_instruction()
-            .map_err(|e| AppError::Connector(e.to_string()))?
-        {
-            unique_rfids.insert(i.clone());
-        }
+        let observations = connector
+            .inventory_unique(100)
+            .map_err(|e| AppError::Connector(e.to_string()))?;
 
         println!("|     SEQUENCE: {sequence}   |");
         println!("|     RFID_UNICI     |");
-        for rfid in unique_rfids.iter() {
-            println!("| {} |", rfid);
+        for o in observations.iter() {
+            println!(
+                "| {} reads: {} RSSI min/mean/max: {}/{:.1}/{} |",
+                o.epc, o.reads, o.rssi_min, o.rssi_mean, o.rssi_max
+            );
         }
-        println!("|  TOTAL: {}     |", unique_rfids.len());
+        println!("|  TOTAL: {}     |", observations.len());
     }
 
     Ok(())
@@ -1,32 +1,145 @@
 use crate::connector::{
-    Connector, ConnectorError, WorkingArea, calculate_transmit_power, clear_non_ascii, hexdump_line,
+    Connector, ConnectorError, FASTID_SELECT_MASK, FASTID_SELECT_POINTER, GPIO_PIN_COUNT,
+    GpioState, InventoryMode, InventoryResult, LockMask, MemoryBank, ModuleInfo, PowerMode,
+    QueryParameters, ReaderEvent, Sel, SelectMask, SelectParameters, Session, Target,
+    TransmitPower, WAKE_UP_BYTE, WorkingArea, calculate_transmit_power, hexdump_line,
 };
-use crate::frame::{Command, Frame, R200_FRAME_END, R200_FRAME_HEADER};
+use crate::frame::{Command, Frame};
+use crate::logging::{debug, warn};
 use crate::packet::Packet;
-use crate::rfid::Rfid;
+use crate::rfid::{Rfid, TagObservation, TimedRfid};
 use async_trait::async_trait;
-use log::{debug, info};
+use std::collections::BTreeSet;
+use std::ops::ControlFlow;
 use std::time::Duration;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::time::Instant;
+
+/// What a frame of a multiple polling inventory carried, see `collect_inventory_async`
+enum InventoryFrame {
+    Tag(Rfid),
+    /// No tag answered during a round
+    EmptyRound,
+}
 
 #[async_trait]
 pub trait AsyncIO {
     type Socket: AsyncRead + AsyncWrite + Unpin + Send;
     async fn setup_reader(&mut self) -> Result<(), ConnectorError>;
     async fn get_module_info(&mut self) -> Result<String, ConnectorError>;
+    async fn get_module_info_struct(&mut self) -> Result<ModuleInfo, ConnectorError>;
     async fn send_packet(&mut self, command: Command) -> Result<(), ConnectorError>;
     async fn single_read_from_serial(&mut self) -> Result<Option<Packet>, ConnectorError>;
+    async fn send_and_receive(
+        &mut self,
+        command: Command,
+    ) -> Result<Option<Packet>, ConnectorError>;
     async fn read_from_serial(
         &mut self,
         num_expected_responses: Option<u32>,
     ) -> Result<Option<Vec<Packet>>, ConnectorError>;
     async fn get_working_area(&mut self) -> Result<WorkingArea, ConnectorError>;
+    async fn get_working_area_raw(&mut self) -> Result<u8, ConnectorError>;
+    async fn set_working_area(&mut self, area: WorkingArea) -> Result<(), ConnectorError>;
     async fn get_working_channel(&mut self) -> Result<f64, ConnectorError>;
-    async fn get_transmit_power(&mut self) -> Result<f64, ConnectorError>;
+    async fn channel_plan(&mut self) -> Result<Vec<(u8, f64)>, ConnectorError>;
+    async fn get_working_channel_index(&mut self) -> Result<u8, ConnectorError>;
+    async fn set_working_channel(&mut self, channel_index: u8) -> Result<(), ConnectorError>;
+    async fn set_working_channel_mhz(&mut self, mhz: f64) -> Result<(), ConnectorError>;
+    async fn get_transmit_power(&mut self) -> Result<TransmitPower, ConnectorError>;
+    #[deprecated(note = "use get_transmit_power, which returns a TransmitPower")]
+    async fn get_transmit_power_dbm(&mut self) -> Result<f64, ConnectorError>;
+    async fn get_power_range(&mut self) -> Result<(f64, f64), ConnectorError>;
     async fn set_transmission_power(&mut self, power: f64) -> Result<(), ConnectorError>;
+    async fn set_transmit_power_checked(&mut self, power: f64) -> Result<f64, ConnectorError>;
     async fn single_polling_instruction(&mut self) -> Result<Vec<Rfid>, ConnectorError>;
+    async fn single_polling_instruction_raw(
+        &mut self,
+    ) -> Result<Vec<(Rfid, Vec<u8>)>, ConnectorError>;
+    async fn single_polling_instruction_timed(&mut self) -> Result<Vec<TimedRfid>, ConnectorError>;
+    async fn single_polling_instruction_with_timeout(
+        &mut self,
+        deadline: Duration,
+    ) -> Result<Vec<Rfid>, ConnectorError>;
+    async fn wait_for_tag(&mut self, timeout: Duration) -> Result<Option<Rfid>, ConnectorError>;
     async fn multi_polling_instruction(&mut self) -> Result<Vec<Rfid>, ConnectorError>;
-    async fn stop_multiple_polling_instructions(&mut self) -> Result<(), ConnectorError>;
+    async fn multi_polling_with<F>(&mut self, max: u16, on_tag: F) -> Result<(), ConnectorError>
+    where
+        F: FnMut(Rfid) -> ControlFlow<()> + Send;
+    async fn multi_polling_detailed(&mut self, max: u16)
+    -> Result<InventoryResult, ConnectorError>;
+    async fn inventory_for(&mut self, dwell: Duration) -> Result<Vec<Rfid>, ConnectorError>;
+    async fn inventory_resilient(&mut self, total_rounds: u32)
+    -> Result<Vec<Rfid>, ConnectorError>;
+    async fn inventory_unique(
+        &mut self,
+        rounds: u16,
+    ) -> Result<Vec<TagObservation>, ConnectorError>;
+    async fn inventory_with_tid(&mut self, max: u16) -> Result<Vec<Rfid>, ConnectorError>;
+    async fn stop_multiple_polling_instructions(&mut self) -> Result<usize, ConnectorError>;
+    async fn get_query_parameters(&mut self) -> Result<QueryParameters, ConnectorError>;
+    async fn set_inventory_mode(&mut self, mode: InventoryMode) -> Result<(), ConnectorError>;
+    async fn get_select(&mut self) -> Result<SelectParameters, ConnectorError>;
+    async fn set_select(
+        &mut self,
+        mask_bank: MemoryBank,
+        bit_pointer: u32,
+        mask: &[u8],
+        mask_bit_len: u16,
+    ) -> Result<(), ConnectorError>;
+    async fn set_select_mask(&mut self, select: &SelectMask) -> Result<(), ConnectorError>;
+    async fn set_select_mode(&mut self, enabled: bool) -> Result<(), ConnectorError>;
+    async fn set_auto_frequency_hopping(&mut self, enabled: bool) -> Result<(), ConnectorError>;
+    async fn set_hopping_channels(&mut self, channels: &[u8]) -> Result<(), ConnectorError>;
+    async fn kill_tag(&mut self, kill_password: u32) -> Result<(), ConnectorError>;
+    async fn reset_module(&mut self) -> Result<(), ConnectorError>;
+    async fn set_power_mode(&mut self, mode: PowerMode) -> Result<(), ConnectorError>;
+    async fn set_rf_carrier(&mut self, on: bool) -> Result<(), ConnectorError>;
+    async fn set_gpio_direction(&mut self, pin: u8, output: bool) -> Result<(), ConnectorError>;
+    async fn set_gpio(&mut self, pin: u8, level: bool) -> Result<(), ConnectorError>;
+    async fn get_gpio(&mut self) -> Result<GpioState, ConnectorError>;
+    async fn lock_tag(
+        &mut self,
+        access_password: Option<u32>,
+        lock_mask: LockMask,
+    ) -> Result<(), ConnectorError>;
+    async fn send_raw(&mut self, command_code: u8, params: &[u8])
+    -> Result<Packet, ConnectorError>;
+    async fn resync(&mut self) -> Result<usize, ConnectorError>;
+    async fn set_query_parameters(
+        &mut self,
+        q: u8,
+        session: Session,
+        target: Target,
+        sel: Sel,
+    ) -> Result<(), ConnectorError>;
+    async fn read_tag_memory(
+        &mut self,
+        bank: MemoryBank,
+        word_addr: u16,
+        word_count: u16,
+        access_password: Option<u32>,
+    ) -> Result<Vec<u8>, ConnectorError>;
+    async fn access_and_read(
+        &mut self,
+        select: SelectMask,
+        access_password: u32,
+        bank: MemoryBank,
+        addr: u16,
+        words: u16,
+    ) -> Result<Vec<u8>, ConnectorError>;
+    async fn write_tag_memory(
+        &mut self,
+        bank: MemoryBank,
+        word_addr: u16,
+        words: &[u16],
+        access_password: Option<u32>,
+    ) -> Result<(), ConnectorError>;
+    async fn write_epc(
+        &mut self,
+        new_epc: &[u8],
+        access_password: u32,
+    ) -> Result<(), ConnectorError>;
 }
 
 #[async_trait]
@@ -42,25 +155,19 @@ where
     }
 
     async fn get_module_info(&mut self) -> Result<String, ConnectorError> {
-        self.send_packet(Command::HardwareVersion).await?;
-        let hardware = self.single_read_from_serial().await?;
-        self.send_packet(Command::SoftwareVersion).await?;
-        let software = self.single_read_from_serial().await?;
-        self.send_packet(Command::Manufacturer).await?;
-        let manufacture = self.single_read_from_serial().await?;
-
-        let hw_str = hardware.map(|p| p.to_string()).unwrap_or_default();
-        let sw_str = software.map(|p| p.to_string()).unwrap_or_default();
-        let mf_str = manufacture.map(|p| p.to_string()).unwrap_or_default();
-
-        let out = format!(
-            "Hardware: {} - Software: {} - Manufacturer: {}",
-            clear_non_ascii(&hw_str),
-            clear_non_ascii(&sw_str),
-            clear_non_ascii(&mf_str)
-        );
+        Ok(self.get_module_info_struct().await?.to_string())
+    }
 
-        Ok(out)
+    async fn get_module_info_struct(&mut self) -> Result<ModuleInfo, ConnectorError> {
+        let hardware = self.send_and_receive(Command::HardwareVersion).await?;
+        let software = self.send_and_receive(Command::SoftwareVersion).await?;
+        let manufacturer = self.send_and_receive(Command::Manufacturer).await?;
+
+        Ok(ModuleInfo {
+            hardware: Connector::<S>::parse_module_info(hardware),
+            software: Connector::<S>::parse_module_info(software),
+            manufacturer: Connector::<S>::parse_module_info(manufacturer),
+        })
     }
 
     async fn send_packet(&mut self, command: Command) -> Result<(), ConnectorError> {
@@ -73,6 +180,8 @@ where
         debug!("[TX] {out} - [{command}]");
 
         self.port.write_all(&frame).await?;
+        self.record_tx(&frame);
+        self.emit(|| ReaderEvent::FrameSent(frame.clone()));
         self.port.flush().await?;
         Ok(())
     }
@@ -82,130 +191,896 @@ where
         Ok(out.unwrap_or(vec![]).pop())
     }
 
-    async fn read_from_serial(
+    async fn send_and_receive(
         &mut self,
-        num_expected_responses: Option<u32>,
-    ) -> Result<Option<Vec<Packet>>, ConnectorError> {
-        let mut read_buf: [u8; 1024] = [0u8; 1024];
-        let mut rolling: Vec<u8> = Vec::with_capacity(4096);
-        let mut output: Vec<Packet> = Vec::new();
-
+        command: Command,
+    ) -> Result<Option<Packet>, ConnectorError> {
+        let mut attempt = 0;
         loop {
-            let read_future = self.port.read(&mut read_buf);
-
-            // In a real async scenario with timeout, we might use tokio::time::timeout
-            let raw_data_size =
-                match tokio::time::timeout(Duration::from_millis(500), read_future).await {
-                    Ok(res) => res,
-                    Err(_) => {
-                        if output.is_empty() {
-                            return Err(ConnectorError::Timeout);
-                        }
-                        break;
+            self.send_packet(command.clone()).await?;
+            match self.single_read_from_serial().await {
+                Ok(None) | Err(ConnectorError::Timeout) if attempt < self.retries => {
+                    attempt += 1;
+                    warn!(
+                        "No response to [{command}], retry {attempt}/{}",
+                        self.retries
+                    );
+                }
+                Ok(Some(p)) => {
+                    if let Some(code) = p.as_error() {
+                        return Err(ConnectorError::Device(code));
                     }
-                };
-
-            match raw_data_size {
-                Ok(n) if n > 0 => {
-                    rolling.extend_from_slice(&read_buf[..n]);
-                    hexdump_line("[RAW] ", &rolling);
-
-                    while let Some(header_pos) =
-                        rolling.iter().position(|&x| x == R200_FRAME_HEADER)
-                    {
-                        if let Some(end_pos) = rolling.iter().position(|&x| x == R200_FRAME_END) {
-                            if end_pos > header_pos {
-                                let chunk = &rolling[header_pos..=end_pos];
-                                if chunk.len() > 4 {
-                                    let p = Packet::new(Vec::from(chunk));
-                                    if p.is_valid() {
-                                        debug!("{}", p.debug());
-                                        output.push(p);
-                                        if output.len()
-                                            >= num_expected_responses.unwrap_or(100000) as usize
-                                        {
-                                            return Ok(Some(output));
-                                        }
-                                    }
-                                }
-                                rolling.drain(..=end_pos);
-                            } else {
-                                // End before header, discard everything before header
-                                rolling.drain(..header_pos);
-                                break;
-                            }
-                        } else {
-                            // Header but no end yet
-                            break;
-                        }
-                    }
-
-                    if rolling.len() > 8192 {
-                        rolling.drain(..rolling.len() - 4096);
+                    if p.command_code() != command.code() {
+                        // Best effort, the mismatch is the error to report
+                        let _ = self.resync().await;
+                        return Err(ConnectorError::UnexpectedResponse {
+                            expected: command.code(),
+                            got: p.command_code(),
+                        });
                     }
+                    return Ok(Some(p));
                 }
-                Ok(_) => return Ok(None),
-                Err(e) => return Err(ConnectorError::SerialRead(e.to_string())),
+                other => return other,
             }
         }
-        Ok(Some(output))
+    }
+
+    async fn read_from_serial(
+        &mut self,
+        num_expected_responses: Option<u32>,
+    ) -> Result<Option<Vec<Packet>>, ConnectorError> {
+        self.read_packets_async(num_expected_responses, self.route_notifications, None)
+            .await
     }
 
     async fn get_working_area(&mut self) -> Result<WorkingArea, ConnectorError> {
-        self.send_packet(Command::GetWorkingArea).await?;
-        if let Some(p) = self.single_read_from_serial().await? {
-            return Connector::<S>::parse_to_working_area(p);
-        }
-        Err(ConnectorError::NoPacketReceived)
+        let code = self.get_working_area_raw().await?;
+        WorkingArea::from_code(code).ok_or(ConnectorError::InvalidWorkingArea)
+    }
+
+    async fn get_working_area_raw(&mut self) -> Result<u8, ConnectorError> {
+        let p = self
+            .send_and_receive(Command::GetWorkingArea)
+            .await?
+            .ok_or(ConnectorError::NoPacketReceived)?;
+        let code = Connector::<S>::parse_working_area_code(p)?;
+        self.working_area = WorkingArea::from_code(code);
+        Ok(code)
+    }
+
+    async fn set_working_area(&mut self, area: WorkingArea) -> Result<(), ConnectorError> {
+        let p = self.send_and_receive(Command::SetWorkingArea(area)).await?;
+        Connector::<S>::_set_working_area(p, area)?;
+        self.working_area = Some(area);
+        Ok(())
     }
 
     async fn get_working_channel(&mut self) -> Result<f64, ConnectorError> {
-        self.send_packet(Command::GetWorkingChannel).await?;
-        if let Some(p) = self.single_read_from_serial().await? {
-            return Ok(self.get_working_area().await?.packet_to_64(p));
+        // The area is known before the channel is asked, so the response pairs with it
+        let area = match self.working_area {
+            Some(area) => area,
+            None => self.get_working_area().await?,
+        };
+        Ok(area.channel_mhz(self.get_working_channel_index().await?))
+    }
+
+    async fn channel_plan(&mut self) -> Result<Vec<(u8, f64)>, ConnectorError> {
+        let plan = self.get_working_area().await?.frequency_plan();
+        Ok(plan
+            .channels()
+            .enumerate()
+            .map(|(i, mhz)| (i as u8, mhz))
+            .collect())
+    }
+
+    async fn get_working_channel_index(&mut self) -> Result<u8, ConnectorError> {
+        match self.send_and_receive(Command::GetWorkingChannel).await? {
+            Some(p) => Connector::<S>::parse_working_channel_index(p),
+            None => Err(ConnectorError::NoPacketReceived),
         }
-        Err(ConnectorError::NoPacketReceived)
     }
 
-    async fn get_transmit_power(&mut self) -> Result<f64, ConnectorError> {
-        self.send_packet(Command::AcquireTransmitPower).await?;
-        if let Some(p) = self.single_read_from_serial().await? {
+    async fn set_working_channel(&mut self, channel_index: u8) -> Result<(), ConnectorError> {
+        if self.frequency_hopping == Some(true) {
+            warn!("Automatic frequency hopping is enabled, the working channel will not stick");
+        }
+        let area = match self.working_area {
+            Some(area) => area,
+            None => self.get_working_area().await?,
+        };
+        Connector::<S>::check_channel_index(area, channel_index)?;
+        let p = self
+            .send_and_receive(Command::SetWorkingChannel(channel_index))
+            .await?;
+        Connector::<S>::_set_working_channel(p, channel_index)
+    }
+
+    async fn set_working_channel_mhz(&mut self, mhz: f64) -> Result<(), ConnectorError> {
+        let area = match self.working_area {
+            Some(area) => area,
+            None => self.get_working_area().await?,
+        };
+        let channel_index = area.channel_index(mhz).ok_or_else(|| {
+            ConnectorError::InvalidParameter(format!("{} MHz is not a channel of {:?}", mhz, area))
+        })?;
+        self.set_working_channel(channel_index).await
+    }
+
+    async fn get_transmit_power(&mut self) -> Result<TransmitPower, ConnectorError> {
+        if let Some(p) = self.send_and_receive(Command::AcquireTransmitPower).await? {
             return calculate_transmit_power(p);
         }
         Err(ConnectorError::NoPacketReceived)
     }
 
+    async fn get_transmit_power_dbm(&mut self) -> Result<f64, ConnectorError> {
+        Ok(self.get_transmit_power().await?.dbm)
+    }
+
+    async fn get_power_range(&mut self) -> Result<(f64, f64), ConnectorError> {
+        if let Some(range) = self.power_range {
+            return Ok(range);
+        }
+        let p = self.send_and_receive(Command::HardwareVersion).await?;
+        let range = Connector::<S>::parse_power_range(p);
+        self.power_range = Some(range);
+        Ok(range)
+    }
+
     async fn set_transmission_power(&mut self, power: f64) -> Result<(), ConnectorError> {
-        self.send_packet(Command::SetTransmissionPower(power))
+        let range = self.get_power_range().await?;
+        Connector::<S>::check_power(range, power)?;
+        let p = self
+            .send_and_receive(Command::SetTransmissionPower(power))
             .await?;
-        Connector::<S>::_set_transmission_power(self.single_read_from_serial().await?, power)
+        Connector::<S>::_set_transmission_power(p, power)
+    }
+
+    async fn set_transmit_power_checked(&mut self, power: f64) -> Result<f64, ConnectorError> {
+        self.set_transmission_power(power).await?;
+        let applied = self.get_transmit_power().await?.dbm;
+        Connector::<S>::check_applied_power(power, applied);
+        Ok(applied)
     }
 
     async fn single_polling_instruction(&mut self) -> Result<Vec<Rfid>, ConnectorError> {
         self.send_packet(Command::SinglePollingInstruction).await?;
-        let response = self.read_from_serial(None).await?;
+        let response = self.read_packets_async(None, false, None).await?;
         self.parse_rfid_packets(response)
     }
 
-    async fn multi_polling_instruction(&mut self) -> Result<Vec<Rfid>, ConnectorError> {
-        self.send_packet(Command::MultiplePollingInstruction(100))
+    async fn single_polling_instruction_raw(
+        &mut self,
+    ) -> Result<Vec<(Rfid, Vec<u8>)>, ConnectorError> {
+        self.send_packet(Command::SinglePollingInstruction).await?;
+        let response = self.read_packets_async(None, false, None).await?;
+        let tags = self.parse_rfid_frames(response)?;
+        Ok(tags
+            .into_iter()
+            .map(|(rfid, p)| (rfid, p.as_bytes().to_vec()))
+            .collect())
+    }
+
+    async fn single_polling_instruction_timed(&mut self) -> Result<Vec<TimedRfid>, ConnectorError> {
+        self.send_packet(Command::SinglePollingInstruction).await?;
+        let response = self
+            .read_stamped_packets_async(None, false, None, |c| c.now())
             .await?;
-        let response = self.read_from_serial(Some(100)).await?;
+        self.parse_timed_frames(response)
+    }
+
+    async fn single_polling_instruction_with_timeout(
+        &mut self,
+        deadline: Duration,
+    ) -> Result<Vec<Rfid>, ConnectorError> {
+        let deadline = Instant::now() + deadline;
+        self.send_packet(Command::SinglePollingInstruction).await?;
+        let response = self.read_packets_async(None, false, Some(deadline)).await?;
         self.parse_rfid_packets(response)
     }
 
-    async fn stop_multiple_polling_instructions(&mut self) -> Result<(), ConnectorError> {
-        self.send_packet(Command::StopMultiplePollingInstruction)
+    async fn wait_for_tag(&mut self, timeout: Duration) -> Result<Option<Rfid>, ConnectorError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            match self
+                .single_polling_instruction_with_timeout(remaining)
+                .await
+            {
+                Ok(tags) => {
+                    if let Some(tag) = tags.into_iter().next() {
+                        return Ok(Some(tag));
+                    }
+                }
+                Err(ConnectorError::Timeout) => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    async fn multi_polling_instruction(&mut self) -> Result<Vec<Rfid>, ConnectorError> {
+        let mut tags = Vec::new();
+        self.multi_polling_with(100, |tag| {
+            tags.push(tag);
+            ControlFlow::Continue(())
+        })
+        .await?;
+        Ok(tags)
+    }
+
+    async fn multi_polling_with<F>(&mut self, max: u16, mut on_tag: F) -> Result<(), ConnectorError>
+    where
+        F: FnMut(Rfid) -> ControlFlow<()> + Send,
+    {
+        self.send_packet(Command::MultiplePollingInstruction(max))
+            .await?;
+        let result = self
+            .collect_inventory_async(None, |frame| match frame {
+                InventoryFrame::Tag(rfid) => on_tag(rfid),
+                _ => ControlFlow::Continue(()),
+            })
+            .await;
+
+        // Stopped before the end: stop the device and discard the tags still in flight
+        if matches!(
+            result,
+            Ok(ControlFlow::Break(())) | Err(ConnectorError::Device(_))
+        ) && let Err(e) = self.stop_multiple_polling_instructions().await
+        {
+            warn!("Unable to stop multiple polling: {e}");
+        }
+        result.map(|_| ())
+    }
+
+    async fn multi_polling_detailed(
+        &mut self,
+        max: u16,
+    ) -> Result<InventoryResult, ConnectorError> {
+        self.send_packet(Command::MultiplePollingInstruction(max))
+            .await?;
+        let mut reads = Vec::new();
+        let mut empty_rounds: u16 = 0;
+        let result = self
+            .collect_inventory_async(None, |frame| {
+                match frame {
+                    InventoryFrame::Tag(rfid) => reads.push(rfid),
+                    InventoryFrame::EmptyRound => empty_rounds = empty_rounds.saturating_add(1),
+                }
+                ControlFlow::Continue(())
+            })
+            .await;
+
+        if let Err(e) = result {
+            // Stopped on a device error: stop the device and discard the tags still in flight
+            if let ConnectorError::Device(_) = e
+                && let Err(e) = self.stop_multiple_polling_instructions().await
+            {
+                warn!("Unable to stop multiple polling: {e}");
+            }
+            return Err(e);
+        }
+        let unique_count = reads
+            .iter()
+            .map(|r: &Rfid| r.epc.as_str())
+            .collect::<BTreeSet<_>>()
+            .len();
+        Ok(InventoryResult {
+            reads,
+            rounds_completed: None,
+            empty_rounds,
+            unique_count,
+        })
+    }
+
+    async fn inventory_for(&mut self, dwell: Duration) -> Result<Vec<Rfid>, ConnectorError> {
+        let deadline = Instant::now() + dwell;
+        self.send_packet(Command::MultiplePollingInstruction(u16::MAX))
             .await?;
-        if let Some(p) = self.single_read_from_serial().await? {
-            if matches!(p.command(), Ok(Command::StopMultiplePollingInstruction)) {
-                return Ok(());
+        let mut tags = Vec::new();
+        let collected = self
+            .collect_inventory_async(Some(deadline), |frame| {
+                if let InventoryFrame::Tag(rfid) = frame {
+                    tags.push(rfid);
+                }
+                ControlFlow::Continue(())
+            })
+            .await
+            .map(|_| ());
+
+        let stopped = self.stop_multiple_polling_instructions().await;
+        collected?;
+        stopped?;
+        Ok(tags)
+    }
+
+    async fn inventory_resilient(
+        &mut self,
+        total_rounds: u32,
+    ) -> Result<Vec<Rfid>, ConnectorError> {
+        let mut tags = Vec::new();
+        let mut remaining = total_rounds;
+        while remaining > 0 {
+            let max = remaining.min(u16::MAX as u32) as u16;
+            let result = self.multi_polling_detailed(max).await?;
+            tags.extend(result.reads);
+            remaining -= max as u32;
+        }
+        Ok(tags)
+    }
+
+    async fn inventory_unique(
+        &mut self,
+        rounds: u16,
+    ) -> Result<Vec<TagObservation>, ConnectorError> {
+        let mut tags = Vec::new();
+        self.multi_polling_with(rounds, |tag| {
+            tags.push(tag);
+            ControlFlow::Continue(())
+        })
+        .await?;
+        Ok(TagObservation::aggregate(&tags))
+    }
+
+    async fn inventory_with_tid(&mut self, max: u16) -> Result<Vec<Rfid>, ConnectorError> {
+        let previous = self.get_select().await?;
+        let previous_mode = self.select_mode.unwrap_or(false);
+        self.set_select(
+            MemoryBank::Tid,
+            FASTID_SELECT_POINTER,
+            &FASTID_SELECT_MASK,
+            8,
+        )
+        .await?;
+        self.set_select_mode(true).await?;
+        let mut tags = Vec::new();
+        let result = self
+            .multi_polling_with(max, |tag| {
+                tags.push(tag);
+                ControlFlow::Continue(())
+            })
+            .await;
+        let mut restored = self
+            .set_select(
+                previous.mask_bank,
+                previous.bit_pointer,
+                &previous.mask,
+                previous.mask_bit_len,
+            )
+            .await;
+        if restored.is_ok() {
+            restored = self.set_select_mode(previous_mode).await;
+        }
+        result?;
+        restored?;
+        Ok(tags)
+    }
+
+    async fn stop_multiple_polling_instructions(&mut self) -> Result<usize, ConnectorError> {
+        let mut read_buf = vec![0u8; self.read_buf_size];
+        let mut drained = 0;
+        for attempt in 0..=self.retries {
+            if attempt > 0 {
+                warn!("No stop confirmation, retry {attempt}/{}", self.retries);
+            }
+            self.send_packet(Command::StopMultiplePollingInstruction)
+                .await?;
+            let mut decoder = self.frame_decoder();
+            loop {
+                while let Some(p) = self.take_frame(&mut decoder) {
+                    if matches!(p.command(), Ok(Command::StopMultiplePollingInstruction)) {
+                        return Ok(drained);
+                    }
+                    debug!("Discarding in-flight frame: {}", p.debug());
+                    drained += 1;
+                }
+                match self
+                    .read_port_async(&mut read_buf, Duration::from_millis(500))
+                    .await
+                {
+                    Ok(Ok(n)) if n > 0 => {
+                        decoder.push(&read_buf[..n]);
+                    }
+                    Err(_) | Ok(Ok(_)) => break,
+                    Ok(Err(e)) => return Err(ConnectorError::SerialRead(e.to_string())),
+                }
             }
         }
         Err(ConnectorError::ErrorStopMultiPolling(
-            "Failed to stop multi polling".into(),
+            "No stop confirmation from device".into(),
         ))
     }
+
+    async fn get_query_parameters(&mut self) -> Result<QueryParameters, ConnectorError> {
+        if let Some(p) = self.send_and_receive(Command::GetQueryParameters).await? {
+            return Connector::<S>::parse_query_parameters(p);
+        }
+        Err(ConnectorError::NoPacketReceived)
+    }
+
+    async fn set_query_parameters(
+        &mut self,
+        q: u8,
+        session: Session,
+        target: Target,
+        sel: Sel,
+    ) -> Result<(), ConnectorError> {
+        Connector::<S>::check_query_parameters(q)?;
+        let params = QueryParameters {
+            sel,
+            session,
+            target,
+            q,
+        };
+        let p = self
+            .send_and_receive(Command::SetQueryParameters(params))
+            .await?;
+        Connector::<S>::_set_query_parameters(p, params)
+    }
+
+    async fn set_inventory_mode(&mut self, mode: InventoryMode) -> Result<(), ConnectorError> {
+        let params = mode.query_parameters();
+        self.set_query_parameters(params.q, params.session, params.target, params.sel)
+            .await
+    }
+
+    async fn get_select(&mut self) -> Result<SelectParameters, ConnectorError> {
+        if let Some(p) = self.send_and_receive(Command::GetSelect).await? {
+            return Connector::<S>::parse_select_parameters(p);
+        }
+        Err(ConnectorError::NoPacketReceived)
+    }
+
+    async fn set_select(
+        &mut self,
+        mask_bank: MemoryBank,
+        bit_pointer: u32,
+        mask: &[u8],
+        mask_bit_len: u16,
+    ) -> Result<(), ConnectorError> {
+        Connector::<S>::check_select_mask(mask, mask_bit_len)?;
+        let p = self
+            .send_and_receive(Command::SetSelect {
+                bank: mask_bank,
+                bit_pointer,
+                mask: mask.to_vec(),
+                mask_bit_len: mask_bit_len as u8,
+            })
+            .await?;
+        Connector::<S>::_set_select(p, mask_bank)
+    }
+
+    async fn set_select_mask(&mut self, select: &SelectMask) -> Result<(), ConnectorError> {
+        Connector::<S>::check_select_mask_bytes(select)?;
+        self.set_select(
+            select.bank,
+            select.bit_pointer,
+            &select.mask,
+            select.bit_len,
+        )
+        .await
+    }
+
+    async fn set_select_mode(&mut self, enabled: bool) -> Result<(), ConnectorError> {
+        let p = self
+            .send_and_receive(Command::SetSelectMode(enabled))
+            .await?;
+        Connector::<S>::_set_select_mode(p, enabled)?;
+        self.select_mode = Some(enabled);
+        Ok(())
+    }
+
+    async fn set_auto_frequency_hopping(&mut self, enabled: bool) -> Result<(), ConnectorError> {
+        let p = self
+            .send_and_receive(Command::SetAutoFrequencyHopping(enabled))
+            .await?;
+        Connector::<S>::_set_auto_frequency_hopping(p, enabled)?;
+        self.frequency_hopping = Some(enabled);
+        Ok(())
+    }
+
+    async fn set_hopping_channels(&mut self, channels: &[u8]) -> Result<(), ConnectorError> {
+        let area = match self.working_area {
+            Some(area) => area,
+            None => self.get_working_area().await?,
+        };
+        Connector::<S>::check_hopping_channels(area, channels)?;
+        let p = self
+            .send_and_receive(Command::InsertWorkingChannel(channels.to_vec()))
+            .await?;
+        Connector::<S>::_set_hopping_channels(p, channels)?;
+        self.hopping_channels = (!channels.is_empty()).then(|| channels.to_vec());
+        Ok(())
+    }
+
+    async fn send_raw(
+        &mut self,
+        command_code: u8,
+        params: &[u8],
+    ) -> Result<Packet, ConnectorError> {
+        self.send_packet(Command::Raw {
+            code: command_code,
+            params: params.to_vec(),
+        })
+        .await?;
+        self.single_read_from_serial()
+            .await?
+            .ok_or(ConnectorError::NoPacketReceived)
+    }
+
+    async fn resync(&mut self) -> Result<usize, ConnectorError> {
+        let mut read_buf = vec![0u8; self.read_buf_size];
+        let mut decoder = self.frame_decoder();
+        let mut discarded = 0;
+        loop {
+            match self
+                .read_port_async(&mut read_buf, Duration::from_millis(500))
+                .await
+            {
+                Err(_) | Ok(Ok(0)) => break,
+                Ok(Ok(n)) => {
+                    discarded += n;
+                    decoder.push(&read_buf[..n]);
+                    let mut frames = 0;
+                    while decoder.next_packet().is_some() {
+                        frames += 1;
+                    }
+                    if frames > 0 && decoder.pending().is_empty() {
+                        break;
+                    }
+                    if discarded >= self.rolling_cap {
+                        warn!("Still no frame boundary after {} bytes", discarded);
+                        break;
+                    }
+                }
+                Ok(Err(e)) => return Err(ConnectorError::SerialRead(e.to_string())),
+            }
+        }
+        if discarded > 0 {
+            warn!("Resync discarded {} bytes", discarded);
+        }
+        Ok(discarded)
+    }
+
+    async fn kill_tag(&mut self, kill_password: u32) -> Result<(), ConnectorError> {
+        if let Some(p) = self
+            .send_and_receive(Command::KillTag(kill_password))
+            .await
+            .map_err(Connector::<S>::tag_access_error)?
+        {
+            return Connector::<S>::parse_tag_status(p);
+        }
+        Err(ConnectorError::NoPacketReceived)
+    }
+
+    async fn lock_tag(
+        &mut self,
+        access_password: Option<u32>,
+        lock_mask: LockMask,
+    ) -> Result<(), ConnectorError> {
+        let access_password = access_password.unwrap_or(self.access_password);
+        Connector::<S>::check_lock_mask(&lock_mask)?;
+        if let Some(p) = self
+            .send_and_receive(Command::LockTag {
+                access_password,
+                lock_mask,
+            })
+            .await
+            .map_err(Connector::<S>::tag_access_error)?
+        {
+            return Connector::<S>::parse_tag_status(p);
+        }
+        Err(ConnectorError::NoPacketReceived)
+    }
+
+    async fn reset_module(&mut self) -> Result<(), ConnectorError> {
+        self.set_power_mode(PowerMode::Sleep).await?;
+        self.set_power_mode(PowerMode::Active).await
+    }
+
+    async fn set_power_mode(&mut self, mode: PowerMode) -> Result<(), ConnectorError> {
+        match mode {
+            PowerMode::Sleep => {
+                let p = self.send_and_receive(Command::ModuleSleep).await?;
+                Connector::<S>::_set_power_mode(p, mode)?;
+            }
+            PowerMode::Idle => {
+                let p = self.send_and_receive(Command::IdleMode(true)).await?;
+                Connector::<S>::_set_power_mode(p, mode)?;
+            }
+            PowerMode::Active if self.power_mode == Some(PowerMode::Sleep) => {
+                self.port.write_all(&[WAKE_UP_BYTE]).await?;
+                self.port.flush().await?;
+                match self.read_from_serial(None).await {
+                    Ok(_) | Err(ConnectorError::Timeout) => {}
+                    Err(e) => return Err(e),
+                }
+                self.frequency_hopping = None;
+                self.working_area = None;
+                self.hopping_channels = None;
+                self.select_mode = None;
+            }
+            PowerMode::Active => {
+                let p = self.send_and_receive(Command::IdleMode(false)).await?;
+                Connector::<S>::_set_power_mode(p, mode)?;
+            }
+        }
+        self.power_mode = Some(mode);
+        Ok(())
+    }
+
+    async fn set_rf_carrier(&mut self, on: bool) -> Result<(), ConnectorError> {
+        let p = self.send_and_receive(Command::SetRfCarrier(on)).await?;
+        Connector::<S>::_set_rf_carrier(p, on)
+    }
+
+    async fn set_gpio_direction(&mut self, pin: u8, output: bool) -> Result<(), ConnectorError> {
+        Connector::<S>::check_gpio_pin(pin)?;
+        let p = self
+            .send_and_receive(Command::SetGpioDirection { pin, output })
+            .await?;
+        Connector::<S>::_set_gpio(p, pin, "direction")
+    }
+
+    async fn set_gpio(&mut self, pin: u8, level: bool) -> Result<(), ConnectorError> {
+        Connector::<S>::check_gpio_pin(pin)?;
+        let p = self
+            .send_and_receive(Command::SetGpioLevel { pin, high: level })
+            .await?;
+        Connector::<S>::_set_gpio(p, pin, "level")
+    }
+
+    async fn get_gpio(&mut self) -> Result<GpioState, ConnectorError> {
+        let mut levels = [false; GPIO_PIN_COUNT as usize];
+        for (pin, level) in (1..=GPIO_PIN_COUNT).zip(levels.iter_mut()) {
+            let p = self.send_and_receive(Command::ReadGpioLevel(pin)).await?;
+            *level = Connector::<S>::parse_gpio_result(p, pin)?;
+        }
+        Ok(GpioState { levels })
+    }
+
+    async fn read_tag_memory(
+        &mut self,
+        bank: MemoryBank,
+        word_addr: u16,
+        word_count: u16,
+        access_password: Option<u32>,
+    ) -> Result<Vec<u8>, ConnectorError> {
+        Connector::<S>::check_words_to_read(word_count)?;
+        let access_password = access_password.unwrap_or(self.access_password);
+        if let Some(p) = self
+            .send_and_receive(Command::ReadTagMemory {
+                bank,
+                word_addr,
+                word_count,
+                access_password,
+            })
+            .await
+            .map_err(Connector::<S>::tag_access_error)?
+        {
+            return Connector::<S>::parse_tag_memory(p);
+        }
+        Err(ConnectorError::NoPacketReceived)
+    }
+
+    async fn access_and_read(
+        &mut self,
+        select: SelectMask,
+        access_password: u32,
+        bank: MemoryBank,
+        addr: u16,
+        words: u16,
+    ) -> Result<Vec<u8>, ConnectorError> {
+        self.set_select_mask(&select).await?;
+        self.set_select_mode(true).await?;
+        let result = self
+            .read_tag_memory(bank, addr, words, Some(access_password))
+            .await;
+        let restored = self.set_select_mode(false).await;
+        let data = result?;
+        restored?;
+        Ok(data)
+    }
+
+    async fn write_tag_memory(
+        &mut self,
+        bank: MemoryBank,
+        word_addr: u16,
+        words: &[u16],
+        access_password: Option<u32>,
+    ) -> Result<(), ConnectorError> {
+        let access_password = access_password.unwrap_or(self.access_password);
+        Connector::<S>::check_words_to_write(words)?;
+        if let Some(p) = self
+            .send_and_receive(Command::WriteTagMemory {
+                bank,
+                word_addr,
+                words: words.to_vec(),
+                access_password,
+            })
+            .await
+            .map_err(Connector::<S>::tag_access_error)?
+        {
+            return Connector::<S>::parse_tag_status(p);
+        }
+        Err(ConnectorError::NoPacketReceived)
+    }
+
+    async fn write_epc(
+        &mut self,
+        new_epc: &[u8],
+        access_password: u32,
+    ) -> Result<(), ConnectorError> {
+        Connector::<S>::check_epc_to_write(new_epc)?;
+        let pc = self
+            .read_tag_memory(MemoryBank::Epc, 1, 1, Some(access_password))
+            .await?;
+        let words = Connector::<S>::epc_words(&pc, new_epc)?;
+        self.write_tag_memory(MemoryBank::Epc, 1, &words, Some(access_password))
+            .await
+    }
+}
+
+impl<S> Connector<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send,
+{
+    /// Read from the port for at most `wait`, reporting the bytes read or the timeout
+    /// to the event sink
+    async fn read_port_async(
+        &mut self,
+        buf: &mut [u8],
+        wait: Duration,
+    ) -> Result<std::io::Result<usize>, tokio::time::error::Elapsed> {
+        let result = tokio::time::timeout(wait, self.port.read(buf)).await;
+        match result {
+            Ok(Ok(n)) if n > 0 => {
+                self.record_rx(&buf[..n]);
+                self.emit(|| ReaderEvent::RawBytes(buf[..n].to_vec()));
+            }
+            Err(_) => self.emit(|| ReaderEvent::Timeout),
+            _ => {}
+        }
+        result
+    }
+
+    /// Decode the frames of a running multiple polling inventory and pass them to `on_frame`,
+    /// tags below the RSSI filter left out.
+    ///
+    /// Without a `deadline` it ends when nothing arrives for 500 ms, with one it keeps
+    /// listening until the deadline. It also ends when the port is closed.
+    ///
+    /// Returns
+    /// - Ok(ControlFlow::Break) if `on_frame` stopped it, the device is still polling.
+    /// - Ok(ControlFlow::Continue) when the inventory ended.
+    /// - Err(ConnectorError::Device) on an error frame other than "no tag".
+    /// - Err(ConnectorError::SerialRead) on I/O failure.
+    async fn collect_inventory_async(
+        &mut self,
+        deadline: Option<Instant>,
+        mut on_frame: impl FnMut(InventoryFrame) -> ControlFlow<()> + Send,
+    ) -> Result<ControlFlow<()>, ConnectorError> {
+        let mut read_buf = vec![0u8; self.read_buf_size];
+        let mut decoder = self.frame_decoder();
+        loop {
+            while let Some(p) = self.take_frame(&mut decoder) {
+                let frame = match p.as_error() {
+                    // No tag answered during this round
+                    Some(0x15) => InventoryFrame::EmptyRound,
+                    Some(code) => return Err(ConnectorError::Device(code)),
+                    None => match self.parse_tag(p.get_data()) {
+                        Some(rfid) if !self.rssi_accepted(&rfid) => continue,
+                        Some(rfid) => InventoryFrame::Tag(rfid),
+                        None => {
+                            warn!("Skipping malformed packet during inventory: {}", p.debug());
+                            continue;
+                        }
+                    },
+                };
+                if on_frame(frame).is_break() {
+                    return Ok(ControlFlow::Break(()));
+                }
+            }
+            let mut wait = Duration::from_millis(500);
+            if let Some(deadline) = deadline {
+                let left = deadline.saturating_duration_since(Instant::now());
+                if left.is_zero() {
+                    return Ok(ControlFlow::Continue(()));
+                }
+                wait = wait.min(left);
+            }
+            match self.read_port_async(&mut read_buf, wait).await {
+                // No tag in the field right now, keep listening until the deadline
+                Err(_) if deadline.is_some() => {}
+                // Nothing more is coming, the inventory is over
+                Err(_) | Ok(Ok(0)) => return Ok(ControlFlow::Continue(())),
+                Ok(Ok(n)) => {
+                    decoder.push(&read_buf[..n]);
+                    hexdump_line("[RAW] ", decoder.pending());
+                }
+                Ok(Err(e)) => return Err(ConnectorError::SerialRead(e.to_string())),
+            }
+        }
+    }
+
+    /// Read frames until `num_expected_responses` are decoded, nothing arrives for 500 ms,
+    /// or `deadline` is reached. With `route_notifications` notifications go to the event
+    /// sink and do not count as responses.
+    async fn read_packets_async(
+        &mut self,
+        num_expected_responses: Option<u32>,
+        route_notifications: bool,
+        deadline: Option<Instant>,
+    ) -> Result<Option<Vec<Packet>>, ConnectorError> {
+        let output = self
+            .read_stamped_packets_async(
+                num_expected_responses,
+                route_notifications,
+                deadline,
+                |_| (),
+            )
+            .await?;
+        Ok(output.map(|ps| ps.into_iter().map(|(p, ())| p).collect()))
+    }
+
+    /// `read_packets_async`, pairing every frame with the value of `stamp` taken when the
+    /// read returning it completes: frames of the same read share it
+    async fn read_stamped_packets_async<T: Clone>(
+        &mut self,
+        num_expected_responses: Option<u32>,
+        route_notifications: bool,
+        deadline: Option<Instant>,
+        mut stamp: impl FnMut(&Self) -> T,
+    ) -> Result<Option<Vec<(Packet, T)>>, ConnectorError> {
+        let mut read_buf = vec![0u8; self.read_buf_size];
+        let mut decoder = self.frame_decoder();
+        let mut output: Vec<(Packet, T)> = Vec::new();
+
+        loop {
+            let mut wait = Duration::from_millis(500);
+            if let Some(deadline) = deadline {
+                let left = deadline.saturating_duration_since(Instant::now());
+                if left.is_zero() {
+                    break;
+                }
+                wait = wait.min(left);
+            }
+            let raw_data_size = match self.read_port_async(&mut read_buf, wait).await {
+                Ok(res) => res,
+                Err(_) => {
+                    if output.is_empty() && deadline.is_none_or(|d| Instant::now() < d) {
+                        return Err(ConnectorError::Timeout);
+                    }
+                    break;
+                }
+            };
+
+            match raw_data_size {
+                Ok(n) if n > 0 => {
+                    let read_at = stamp(self);
+                    decoder.push(&read_buf[..n]);
+                    hexdump_line("[RAW] ", decoder.pending());
+
+                    while let Some(p) = self.take_frame(&mut decoder) {
+                        debug!("{}", p.debug());
+                        if route_notifications && p.is_notification() {
+                            self.emit(|| ReaderEvent::Notification(p));
+                            continue;
+                        }
+                        output.push((p, read_at.clone()));
+                        if output.len() >= num_expected_responses.unwrap_or(100000) as usize {
+                            return Ok(Some(output));
+                        }
+                    }
+                }
+                Ok(_) => return Ok(None),
+                Err(e) => return Err(ConnectorError::SerialRead(e.to_string())),
+            }
+        }
+        Ok(Some(output))
+    }
 }
 
 #[cfg(test)]
@@ -285,4 +1160,185 @@ mod tests {
         let info = connector.get_module_info().await.unwrap();
         assert!(info.contains("Hardware"));
     }
+
+    #[tokio::test]
+    async fn test_async_duplex_working_area_and_polling() {
+        let (client, mut device) = tokio::io::duplex(256);
+        let device_task = tokio::spawn(async move {
+            // Both requests carry no parameters: 7 bytes each
+            let mut request = [0u8; 7];
+            // Get working area: answer EU
+            device.read_exact(&mut request).await.unwrap();
+            assert_eq!(request[2], 0x08);
+            device
+                .write_all(&[0xAA, 0x01, 0x08, 0x00, 0x01, 0x03, 0x0D, 0xDD])
+                .await
+                .unwrap();
+            // Single polling: one tag, split across two writes
+            device.read_exact(&mut request).await.unwrap();
+            assert_eq!(request[2], 0x22);
+            let tag = [
+                0xAA, 0x02, 0x22, 0x00, 0x11, 0xC9, 0x34, 0x00, 0x30, 0x75, 0x1F, 0xEB, 0x70, 0x5C,
+                0x59, 0x04, 0xE3, 0xD5, 0x0D, 0x70, 0x3A, 0x76, 0xEF, 0xDD,
+            ];
+            device.write_all(&tag[..9]).await.unwrap();
+            device.write_all(&tag[9..]).await.unwrap();
+            device
+        });
+
+        let mut connector = Connector::new(client);
+        assert!(matches!(
+            connector.get_working_area().await.unwrap(),
+            WorkingArea::EU
+        ));
+        let tags = connector.single_polling_instruction().await.unwrap();
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].uid(), "30751FEB705C5904E3D50D70");
+        device_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_async_unexpected_response_code() {
+        // A tag left over by an inventory instead of the power response
+        let read_data = vec![
+            0xAA, 0x02, 0x22, 0x00, 0x11, 0xC9, 0x30, 0x00, 0xE2, 0x80, 0x68, 0x94, 0x00, 0x00,
+            0x50, 0x1D, 0x63, 0xE2, 0x78, 0x01, 0x4F, 0xB0, 0xB6, 0xDD,
+        ];
+        let port = MockAsyncPort {
+            read_data,
+            written_data: Arc::new(Mutex::new(Vec::new())),
+        };
+        let mut connector = Connector::new(port);
+        assert!(matches!(
+            connector.get_transmit_power().await,
+            Err(ConnectorError::UnexpectedResponse {
+                expected: 0xB7,
+                got: 0x22
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_async_resync_gives_up_on_endless_stream() {
+        // A reader still streaming: noise that never lands on a frame boundary
+        let port = MockAsyncPort {
+            read_data: vec![0x13; 10_000],
+            written_data: Arc::new(Mutex::new(Vec::new())),
+        };
+        let mut connector = Connector::new(port);
+        assert_eq!(connector.resync().await.unwrap(), 4096);
+        assert_eq!(connector.port.read_data.len(), 10_000 - 4096);
+    }
+
+    #[tokio::test]
+    async fn test_async_single_polling_with_timeout() {
+        let (client, mut device) = tokio::io::duplex(256);
+        let device_task = tokio::spawn(async move {
+            let mut request = [0u8; 7];
+            device.read_exact(&mut request).await.unwrap();
+            assert_eq!(request[2], 0x22);
+            device
+                .write_all(&[
+                    0xAA, 0x02, 0x22, 0x00, 0x11, 0xC9, 0x34, 0x00, 0x30, 0x75, 0x1F, 0xEB, 0x70,
+                    0x5C, 0x59, 0x04, 0xE3, 0xD5, 0x0D, 0x70, 0x3A, 0x76, 0xEF, 0xDD,
+                ])
+                .await
+                .unwrap();
+            // Keep the link open and silent past the deadline
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        });
+
+        let mut connector = Connector::new(client);
+        let start = Instant::now();
+        let tags = connector
+            .single_polling_instruction_with_timeout(Duration::from_millis(50))
+            .await
+            .unwrap();
+        assert!(start.elapsed() < Duration::from_millis(400));
+        assert_eq!(tags.len(), 1);
+        device_task.abort();
+    }
+
+    #[tokio::test]
+    async fn test_async_multi_polling_with_stops_early() {
+        let tag = |last: u8| {
+            let mut data = vec![
+                0xC9, 0x30, 0x00, 0xE2, 0x80, 0x68, 0x94, 0x00, 0x00, 0x50, 0x1D, 0x63, 0xE2, 0x78,
+            ];
+            data.extend([last, 0x4F, 0xB0]);
+            let mut frame = vec![0xAA, 0x02, 0x22, 0x00, data.len() as u8];
+            frame.extend(data);
+            let sum: u16 = frame[1..].iter().map(|&b| b as u16).sum();
+            frame.extend([(sum & 0xFF) as u8, 0xDD]);
+            frame
+        };
+        let mut read_data = Vec::new();
+        for last in 1..=3 {
+            read_data.extend(tag(last));
+        }
+        read_data.extend([0xAA, 0x01, 0x28, 0x00, 0x01, 0x00, 0x2A, 0xDD]);
+        let written = Arc::new(Mutex::new(Vec::new()));
+        let port = MockAsyncPort {
+            read_data,
+            written_data: written.clone(),
+        };
+        let mut connector = Connector::new(port);
+        let mut seen = Vec::new();
+        connector
+            .multi_polling_with(100, |rfid| {
+                seen.push(rfid.uid());
+                if seen.len() == 2 {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            })
+            .await
+            .unwrap();
+        assert_eq!(seen.len(), 2);
+        assert_eq!(seen[1], "E28068940000501D63E27802");
+        // Start (9 bytes) then stop
+        assert_eq!(
+            written.lock().unwrap()[9..],
+            [0xAA, 0x00, 0x28, 0x00, 0x00, 0x28, 0xDD]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_async_inventory_for_stops_after_dwell() {
+        let (client, mut device) = tokio::io::duplex(256);
+        let device_task = tokio::spawn(async move {
+            // Start without a round limit
+            let mut start = [0u8; 9];
+            device.read_exact(&mut start).await.unwrap();
+            assert_eq!(start[2..7], [0x27, 0x00, 0x02, 0xFF, 0xFF]);
+            device
+                .write_all(&[
+                    0xAA, 0x02, 0x22, 0x00, 0x11, 0xC9, 0x34, 0x00, 0x30, 0x75, 0x1F, 0xEB, 0x70,
+                    0x5C, 0x59, 0x04, 0xE3, 0xD5, 0x0D, 0x70, 0x3A, 0x76, 0xEF, 0xDD,
+                ])
+                .await
+                .unwrap();
+            // Silent until the dwell is over, then the stop arrives
+            let mut stop = [0u8; 7];
+            device.read_exact(&mut stop).await.unwrap();
+            assert_eq!(stop, [0xAA, 0x00, 0x28, 0x00, 0x00, 0x28, 0xDD]);
+            device
+                .write_all(&[0xAA, 0x01, 0x28, 0x00, 0x01, 0x00, 0x2A, 0xDD])
+                .await
+                .unwrap();
+            device
+        });
+
+        let mut connector = Connector::new(client);
+        let start = Instant::now();
+        let tags = connector
+            .inventory_for(Duration::from_millis(100))
+            .await
+            .unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].uid(), "30751FEB705C5904E3D50D70");
+        device_task.await.unwrap();
+    }
 }
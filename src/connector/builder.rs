@@ -0,0 +1,120 @@
+use crate::connector::sync::SyncIO;
+use crate::connector::{Connector, ConnectorError, WorkingArea};
+use std::time::Duration;
+
+const DEFAULT_BAUD: u32 = 115200;
+const DEFAULT_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Configure and open a serial Connector in one place.
+///
+/// ```no_run
+/// use r200_uhf::connector::{ConnectorBuilder, WorkingArea};
+/// use std::time::Duration;
+///
+/// let connector = ConnectorBuilder::new()
+///     .port("/dev/ttyUSB0")
+///     .timeout(Duration::from_millis(300))
+///     .retries(2)
+///     .working_area(WorkingArea::EU)
+///     .build()?;
+/// # Ok::<(), r200_uhf::connector::ConnectorError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConnectorBuilder {
+    port: Option<String>,
+    baud: u32,
+    timeout: Duration,
+    retries: u8,
+    working_area: Option<WorkingArea>,
+}
+
+impl Default for ConnectorBuilder {
+    fn default() -> Self {
+        ConnectorBuilder {
+            port: None,
+            baud: DEFAULT_BAUD,
+            timeout: DEFAULT_TIMEOUT,
+            retries: 0,
+            working_area: None,
+        }
+    }
+}
+
+impl ConnectorBuilder {
+    /// A builder with 115200 baud, a 500 ms read timeout and no retries
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Name of the serial port, e.g. /dev/ttyUSB0 or COM3. Required.
+    pub fn port(mut self, name: &str) -> Self {
+        self.port = Some(name.to_string());
+        self
+    }
+
+    /// Baud rate the device is currently using
+    pub fn baud(mut self, baud: u32) -> Self {
+        self.baud = baud;
+        self
+    }
+
+    /// Read timeout, see `Connector::open`
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// See `Connector::set_command_retries`
+    pub fn retries(mut self, retries: u8) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Working area set on the device by `build`
+    pub fn working_area(mut self, area: WorkingArea) -> Self {
+        self.working_area = Some(area);
+        self
+    }
+
+    /// Open the port and apply the configuration.
+    ///
+    /// Returns
+    /// - Ok(Connector) ready to use.
+    /// - Err(ConnectorError::InvalidParameter) if no port was given.
+    /// - Err(ConnectorError::Serial) if the port cannot be opened.
+    /// - Other ConnectorError variants if the device rejects the working area.
+    pub fn build(self) -> Result<Connector<Box<dyn serialport::SerialPort>>, ConnectorError> {
+        let port = self
+            .port
+            .ok_or_else(|| ConnectorError::InvalidParameter("No serial port given".into()))?;
+        let mut connector = Connector::open(&port, self.baud, self.timeout)?;
+        connector.set_command_retries(self.retries);
+        if let Some(area) = self.working_area {
+            connector.set_working_area(area)?;
+        }
+        Ok(connector)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_requires_a_port() {
+        assert!(matches!(
+            ConnectorBuilder::new().baud(9600).build(),
+            Err(ConnectorError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn build_fails_on_missing_port() {
+        assert!(matches!(
+            ConnectorBuilder::new()
+                .port("/dev/r200-does-not-exist")
+                .build(),
+            Err(ConnectorError::Serial(_))
+        ));
+    }
+}
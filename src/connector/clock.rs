@@ -0,0 +1,38 @@
+use core::time::Duration;
+use std::time::Instant;
+
+/// Time source of the deadlines of `SyncIO` (`inventory_for`, `wait_for_tag`, ...) and of
+/// the `TimedRfid` timestamps.
+///
+/// The connector uses `SystemClock` unless another clock is installed with
+/// `Connector::set_clock`, e.g. a clock advanced by hand to test timeouts without waiting.
+pub trait Clock {
+    /// Time elapsed since an origin of the clock's choice, never going backwards
+    fn now(&self) -> Duration;
+}
+
+/// Monotonic system clock, counting from its creation
+#[derive(Debug, Clone, Copy)]
+pub struct SystemClock {
+    origin: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> SystemClock {
+        SystemClock {
+            origin: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        SystemClock::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+}
@@ -1,4 +1,20 @@
+#[cfg(feature = "std")]
+mod clock;
 pub mod sync;
+mod transport;
+
+#[cfg(feature = "std")]
+pub use clock::{Clock, SystemClock};
+
+pub use transport::Transport;
+#[cfg(feature = "std")]
+pub use transport::{IoPort, ReplayPort};
+
+#[cfg(feature = "serialport")]
+mod builder;
+
+#[cfg(feature = "serialport")]
+pub use builder::ConnectorBuilder;
 
 #[cfg(feature = "async")]
 mod async_impl;
@@ -7,75 +23,899 @@ mod async_impl;
 pub use async_impl::*;
 
 use crate::Rfid;
-use crate::packet::Packet;
-use log::{debug, error, info};
-use std::fmt;
+#[cfg(feature = "std")]
+use crate::TimedRfid;
+#[cfg(any(feature = "serialport", feature = "tokio-serial"))]
+use crate::frame::Command;
+use crate::frame::{FrameType, raw_to_dbm};
+use crate::logging::{debug, error, info, warn};
+use crate::packet::{FrameDecoder, MAX_FRAME_DATA_LEN, Packet};
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use core::time::Duration;
+#[cfg(feature = "std")]
 use std::io;
 
 pub struct Connector<P> {
     port: P,
+    retries: u8,
+    frequency_hopping: Option<bool>,
+    power_mode: Option<PowerMode>,
+    working_area: Option<WorkingArea>,
+    hopping_channels: Option<Vec<u8>>,
+    /// Select mode last set through the connector, the device cannot report it
+    select_mode: Option<bool>,
+    multi_antenna: bool,
+    access_password: u32,
+    route_notifications: bool,
+    min_rssi: Option<u8>,
+    read_buf_size: usize,
+    rolling_cap: usize,
+    event_sink: Option<EventSink>,
+    last_tx: Option<Vec<u8>>,
+    last_rx: Option<Vec<u8>>,
+    /// Set while a multiple polling inventory started through `SyncIO` is not confirmed
+    /// stopped, writes the stop command to the port
+    stop_on_drop: Option<fn(&mut P)>,
+    power_range: Option<(f64, f64)>,
+    #[cfg(feature = "std")]
+    clock: Box<dyn Clock + Send>,
+}
+
+/// Best-effort stop of a running inventory.
+///
+/// A multiple polling inventory started with `SyncIO` and not stopped yet (e.g. on an early
+/// return or a panic) is stopped when the connector is dropped, so the reader does not keep
+/// streaming tags to the next user of the port. Drop cannot report errors: the stop is written
+/// and flushed without waiting for the acknowledge, and failures are ignored. A process killed
+/// without unwinding still leaves the inventory running, call
+/// `stop_multiple_polling_instructions` after opening the port if that matters. Connectors
+/// driven through `AsyncIO` cannot write from Drop and are not covered.
+impl<P> Drop for Connector<P> {
+    fn drop(&mut self) {
+        if let Some(stop) = self.stop_on_drop.take() {
+            stop(&mut self.port);
+        }
+    }
+}
+
+/// Observer of the reader internals, see `Connector::set_event_sink`
+pub type EventSink = Box<dyn FnMut(ReaderEvent) + Send>;
+
+/// What happens on the serial link, reported to the event sink as it happens
+#[derive(Debug, Clone)]
+pub enum ReaderEvent {
+    /// A command frame was written to the port
+    FrameSent(Vec<u8>),
+    /// Bytes returned by a single read of the port
+    RawBytes(Vec<u8>),
+    /// A complete frame with a valid checksum was decoded
+    PacketDecoded(Packet),
+    /// A frame was dropped because of a wrong checksum or end byte
+    ChecksumFailed(Vec<u8>),
+    /// A read of the port timed out
+    Timeout,
+    /// A notification received while waiting for a command response, kept out of the
+    /// response, see `Connector::set_route_notifications`. It was reported as PacketDecoded too.
+    Notification(Packet),
 }
 
 impl<P> Connector<P> {
     /// Create a new Connector from an already opened SerialPort.
     pub fn new(port: P) -> Self {
-        Connector { port }
+        Connector {
+            port,
+            retries: 0,
+            frequency_hopping: None,
+            power_mode: None,
+            working_area: None,
+            hopping_channels: None,
+            select_mode: None,
+            multi_antenna: false,
+            access_password: 0,
+            route_notifications: false,
+            min_rssi: None,
+            read_buf_size: DEFAULT_READ_BUF_SIZE,
+            rolling_cap: DEFAULT_ROLLING_CAP,
+            event_sink: None,
+            last_tx: None,
+            last_rx: None,
+            stop_on_drop: None,
+            power_range: None,
+            #[cfg(feature = "std")]
+            clock: Box::new(SystemClock::new()),
+        }
+    }
+
+    /// Replace the clock used for the deadlines of `SyncIO` and the `TimedRfid` timestamps,
+    /// the system clock by default
+    #[cfg(feature = "std")]
+    pub fn set_clock(&mut self, clock: Box<dyn Clock + Send>) {
+        self.clock = clock;
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn now(&self) -> Duration {
+        self.clock.now()
+    }
+
+    /// Call `sink` with every frame sent, read, decoded or dropped by this connector.
+    ///
+    /// Meant for tracing and metrics, the same information the `debug` log carries.
+    /// The sink runs inside the read loop, keep it short.
+    pub fn set_event_sink(&mut self, sink: EventSink) {
+        self.event_sink = Some(sink);
+    }
+
+    /// Pass an event to the sink, `event` is only built when a sink is set
+    pub(crate) fn emit(&mut self, event: impl FnOnce() -> ReaderEvent) {
+        if let Some(sink) = self.event_sink.as_mut() {
+            sink(event());
+        }
+    }
+
+    /// Pass the notifications received while reading a command response to the event sink
+    /// as `ReaderEvent::Notification`, instead of returning them with the response.
+    ///
+    /// Tags reported by an inventory left running would otherwise be taken for the answer
+    /// to the next command. The inventory commands still return their tags. The default is
+    /// false.
+    pub fn set_route_notifications(&mut self, enabled: bool) {
+        self.route_notifications = enabled;
+    }
+
+    /// Resend a command up to `retries` more times when the device does not answer.
+    ///
+    /// Only timeouts and missing responses are retried, an answer from the device
+    /// (including an error frame) is returned as is. Input still pending on the port is
+    /// discarded before every resend, see `Transport::clear_input`. The default is 0.
+    pub fn set_command_retries(&mut self, retries: u8) {
+        self.retries = retries;
+    }
+
+    /// Automatic frequency hopping state last acknowledged by the device.
+    ///
+    /// The R200 has no command to read it back, so this is None until
+    /// `set_auto_frequency_hopping` succeeds on this connector.
+    pub fn auto_frequency_hopping(&self) -> Option<bool> {
+        self.frequency_hopping
+    }
+
+    /// Working area last read from or set on the device, None until `get_working_area` or
+    /// `set_working_area` succeeds on this connector.
+    ///
+    /// Channel frequencies are computed from it, so `get_working_channel` only reads the
+    /// area from the device when it is not known yet.
+    pub fn working_area(&self) -> Option<WorkingArea> {
+        self.working_area
+    }
+
+    /// Hopping channel list last set with `set_hopping_channels`, None when the device hops
+    /// over every channel of the area.
+    ///
+    /// The protocol has no command to read the list back, so a list set by another
+    /// application on the same reader is not known here.
+    pub fn hopping_channels(&self) -> Option<&[u8]> {
+        self.hopping_channels.as_deref()
+    }
+
+    /// Access password used by `read_tag_memory`, `write_tag_memory` and `lock_tag` when
+    /// they are called without one, for a batch of tags sharing the same password.
+    ///
+    /// The default is 0, the password of a tag that has none. The kill password is
+    /// another one and is always passed to `kill_tag`.
+    pub fn set_access_password(&mut self, password: u32) {
+        self.access_password = password;
+    }
+
+    /// Expect the antenna port byte that multi-antenna readers put before each tag.
+    ///
+    /// When enabled a tag payload one byte longer than the standard layout is read as
+    /// antenna port + tag and the port is stored in `Rfid::antenna`. Leave it disabled
+    /// (the default) on single-antenna readers.
+    pub fn set_multi_antenna(&mut self, enabled: bool) {
+        self.multi_antenna = enabled;
+    }
+
+    /// Drop the tags read with an RSSI below `min_raw_rssi` from every inventory.
+    ///
+    /// The threshold is a raw RSSI byte as in `Rfid::rssi`, compared as the signed dBm value
+    /// it encodes (0xC4 is -60 dBm and lets 0xC9 through). Filtered tags are not counted
+    /// or returned at all, e.g. to ignore the pallet next to a dock door. None (the default)
+    /// keeps every tag.
+    pub fn set_rssi_filter(&mut self, min_raw_rssi: Option<u8>) {
+        self.min_rssi = min_raw_rssi;
+    }
+
+    /// Whether `tag` passes the RSSI filter
+    pub(crate) fn rssi_accepted(&self, tag: &Rfid) -> bool {
+        self.min_rssi
+            .is_none_or(|min| tag.rssi_dbm() >= min as i8 as i16)
+    }
+
+    /// Decode a tag payload according to the antenna setting
+    pub(crate) fn parse_tag(&self, raw: Vec<u8>) -> Option<Rfid> {
+        if self.multi_antenna {
+            Rfid::parse_with_antenna(raw)
+        } else {
+            Rfid::parse(raw)
+        }
+    }
+
+    /// Size the buffers used while reading from the port.
+    ///
+    /// `read_buf` is how many bytes a single read may return, `rolling_cap` how many
+    /// unprocessed bytes are kept once the pending data grows past twice that size.
+    /// Larger buffers cost memory but let a busy reader deliver thousands of tags per
+    /// second with fewer reads and without dropping data. Values below `MIN_READ_BUF_SIZE`
+    /// and `MIN_ROLLING_CAP` are raised to them. The defaults are 1024 and 4096 bytes.
+    pub fn set_buffer_sizes(&mut self, read_buf: usize, rolling_cap: usize) {
+        self.read_buf_size = read_buf.max(MIN_READ_BUF_SIZE);
+        self.rolling_cap = rolling_cap.max(MIN_ROLLING_CAP);
+    }
+
+    /// Frame decoder keeping at most `rolling_cap` pending bytes
+    pub(crate) fn frame_decoder(&self) -> FrameDecoder {
+        FrameDecoder::with_cap(self.rolling_cap)
+    }
+
+    /// Last command frame written to the port, to attach to a bug report
+    pub fn last_tx(&self) -> Option<&[u8]> {
+        self.last_tx.as_deref()
+    }
+
+    /// Bytes read from the port since the last command was written, noise and invalid frames
+    /// included. During an inventory only the most recent `rolling_cap` bytes are kept,
+    /// see `set_buffer_sizes`.
+    pub fn last_rx(&self) -> Option<&[u8]> {
+        self.last_rx.as_deref()
+    }
+
+    pub(crate) fn record_tx(&mut self, frame: &[u8]) {
+        self.last_tx = Some(frame.to_vec());
+        self.last_rx = None;
+    }
+
+    pub(crate) fn record_rx(&mut self, bytes: &[u8]) {
+        let rx = self.last_rx.get_or_insert_with(Vec::new);
+        rx.extend_from_slice(bytes);
+        if rx.len() > self.rolling_cap {
+            rx.drain(..rx.len() - self.rolling_cap);
+        }
+    }
+
+    /// Power mode last set on this connector, None until `set_power_mode` or
+    /// `reset_module` succeeds.
+    ///
+    /// The module leaves Idle on its own at the first tag operation, so Idle may be stale.
+    pub fn power_mode(&self) -> Option<PowerMode> {
+        self.power_mode
+    }
+}
+
+#[cfg(feature = "serialport")]
+impl Connector<Box<dyn serialport::SerialPort>> {
+    /// Open `port_name` as 8N1 at the given baud rate and wrap it in a Connector.
+    ///
+    /// The timeout is applied to every read, so it also decides how long
+    /// read_from_serial waits before considering a response complete. Reads of the
+    /// `SyncIO` methods with a deadline are shortened to the time left.
+    pub fn open(port_name: &str, baud: u32, timeout: Duration) -> Result<Self, ConnectorError> {
+        let port = serialport::new(port_name, baud)
+            .data_bits(serialport::DataBits::Eight)
+            .parity(serialport::Parity::None)
+            .stop_bits(serialport::StopBits::One)
+            .timeout(timeout)
+            .open()?;
+        Ok(Connector::new(port))
+    }
+
+    /// List the serial ports of the system, with the USB IDs of USB adapters.
+    pub fn available_ports() -> Result<Vec<PortInfo>, ConnectorError> {
+        let ports = serialport::available_ports()?;
+        Ok(ports
+            .into_iter()
+            .map(|p| match p.port_type {
+                serialport::SerialPortType::UsbPort(usb) => PortInfo {
+                    name: p.port_name,
+                    vid: Some(usb.vid),
+                    pid: Some(usb.pid),
+                    product: usb.product,
+                    serial_number: usb.serial_number,
+                },
+                _ => PortInfo {
+                    name: p.port_name,
+                    vid: None,
+                    pid: None,
+                    product: None,
+                    serial_number: None,
+                },
+            })
+            .collect())
+    }
+}
+
+/// A serial port found by `Connector::available_ports`
+#[cfg(feature = "serialport")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortInfo {
+    /// Name to pass to `Connector::open`, e.g. /dev/ttyUSB0 or COM3
+    pub name: String,
+    /// USB vendor ID, None for a port that is not a USB adapter
+    pub vid: Option<u16>,
+    /// USB product ID, None for a port that is not a USB adapter
+    pub pid: Option<u16>,
+    /// Product name reported by the USB adapter
+    pub product: Option<String>,
+    /// Serial number of the USB adapter, when it has one (CP210x do, most CH340 do not).
+    ///
+    /// The R200 has no serial number or device ID command and reports the same module
+    /// information on every unit, so this is the only stable way to tell two readers apart.
+    pub serial_number: Option<String>,
+}
+
+#[cfg(feature = "serialport")]
+impl PortInfo {
+    /// USB to UART bridges R200 boards usually ship with: CH340/CH341 and CP210x.
+    ///
+    /// Only a hint, the same chips are used by plenty of other devices.
+    pub fn is_probably_r200(&self) -> bool {
+        matches!(
+            (self.vid, self.pid),
+            (Some(0x1A86), Some(0x7523 | 0x5523)) | (Some(0x10C4), Some(0xEA60))
+        )
+    }
+}
+
+#[cfg(feature = "serialport")]
+impl Connector<Box<dyn serialport::SerialPort>> {
+    /// Switch the UART of the device to `baud`, then the local port to match.
+    ///
+    /// The device does not answer this command, it starts using the new rate right away.
+    ///
+    /// Returns
+    /// - Ok(()) once both sides use the new rate.
+    /// - Err(ConnectorError::UnsupportedBaud) if `baud` is not in `SUPPORTED_BAUD_RATES`.
+    /// - Other ConnectorError variants on I/O failure.
+    pub fn set_baud_rate(&mut self, baud: u32) -> Result<(), ConnectorError> {
+        use sync::SyncIO;
+
+        Self::check_baud_rate(baud)?;
+        self.send_packet(Command::SetBaudRate(baud))?;
+        Ok(self.port.set_baud_rate(baud)?)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Connector<ReplayPort> {
+    /// Decode bytes captured on a real link, e.g. a file, with the same pipeline as a port.
+    ///
+    /// Meant to replay field captures in tests and bug reports: issue the command that
+    /// produced the capture (e.g. `single_polling_instruction`) and the recording is read
+    /// as its response. What the connector writes is kept, see `ReplayPort::written`.
+    pub fn from_reader(reader: Box<dyn std::io::Read>) -> Self {
+        Connector::new(ReplayPort::new(reader))
+    }
+
+    /// The replayed port, to check the commands written
+    pub fn replay_port(&self) -> &ReplayPort {
+        &self.port
+    }
+}
+
+/// Connector over a tokio serial stream, driven through the `AsyncIO` trait.
+#[cfg(feature = "tokio-serial")]
+pub type AsyncConnector = Connector<tokio_serial::SerialStream>;
+
+#[cfg(feature = "tokio-serial")]
+impl AsyncConnector {
+    /// Open `port_name` as 8N1 at the given baud rate for async use.
+    ///
+    /// Must be called from within a tokio runtime, the stream registers with its reactor.
+    pub fn open_async(port_name: &str, baud: u32) -> Result<Self, ConnectorError> {
+        use tokio_serial::SerialPortBuilderExt;
+
+        let port = tokio_serial::new(port_name, baud)
+            .data_bits(tokio_serial::DataBits::Eight)
+            .parity(tokio_serial::Parity::None)
+            .stop_bits(tokio_serial::StopBits::One)
+            .open_native_async()
+            .map_err(|e| ConnectorError::Io(e.into()))?;
+        Ok(Connector::new(port))
+    }
+
+    /// Switch the UART of the device to `baud`, then the local stream to match.
+    ///
+    /// See `Connector::set_baud_rate`.
+    pub async fn set_baud_rate(&mut self, baud: u32) -> Result<(), ConnectorError> {
+        use tokio_serial::SerialPort;
+
+        Self::check_baud_rate(baud)?;
+        AsyncIO::send_packet(self, Command::SetBaudRate(baud)).await?;
+        self.port
+            .set_baud_rate(baud)
+            .map_err(|e| ConnectorError::Io(e.into()))
+    }
+}
+
+impl<P> Connector<P> {
+    fn parse_working_area_code(p: Packet) -> Result<u8, ConnectorError> {
+        match p.get_data().first() {
+            Some(&code) => Ok(code),
+            None => Err(ConnectorError::InvalidResponse(
+                "Empty working area response".into(),
+            )),
+        }
+    }
+
+    fn parse_working_channel_index(p: Packet) -> Result<u8, ConnectorError> {
+        p.get_data()
+            .first()
+            .copied()
+            .ok_or_else(|| ConnectorError::InvalidResponse("Empty working channel response".into()))
+    }
+
+    fn check_applied_power(requested: f64, applied: f64) {
+        if (applied - requested).abs() > 0.5 {
+            warn!(
+                "Transmission power {} requested, the device applied {}",
+                requested, applied
+            );
+        }
+    }
+
+    /// Acknowledge of a setter, logged as `what`
+    fn check_ack(p: Option<Packet>, what: fmt::Arguments<'_>) -> Result<(), ConnectorError> {
+        let p = p.ok_or(ConnectorError::NoPacketReceived)?;
+        match expect_ack(&p) {
+            Ok(()) => {
+                info!("{} correct set", what);
+                Ok(())
+            }
+            Err(e) => {
+                error!("{} not set: {}", what, e);
+                Err(e)
+            }
+        }
+    }
+
+    /// Check `power` against the `(min, max)` range of `get_power_range`
+    fn check_power((min, max): (f64, f64), power: f64) -> Result<(), ConnectorError> {
+        if !(min..=max).contains(&power) {
+            return Err(ConnectorError::PowerOutOfRange {
+                requested: power,
+                min,
+                max,
+            });
+        }
+        Ok(())
+    }
+
+    /// Power range of a module from its hardware version, e.g. "M100 26dBm V1.0": the rated
+    /// power is the maximum, the minimum is the one of the R200 datasheet
+    fn parse_power_range(p: Option<Packet>) -> (f64, f64) {
+        let hardware = Self::parse_module_info(p);
+        let rating = hardware.split_whitespace().find_map(|word| {
+            let dbm = word
+                .len()
+                .checked_sub(3)
+                .filter(|&i| word.is_char_boundary(i))?;
+            if !word[dbm..].eq_ignore_ascii_case("dbm") {
+                return None;
+            }
+            word[..dbm].parse::<f64>().ok()
+        });
+        match rating {
+            Some(max) if max > DEFAULT_POWER_RANGE.0 => (DEFAULT_POWER_RANGE.0, max),
+            _ => DEFAULT_POWER_RANGE,
+        }
+    }
+
+    fn _set_transmission_power(p: Option<Packet>, power: f64) -> Result<(), ConnectorError> {
+        Self::check_ack(p, format_args!("Transmission power {}", power))
+    }
+
+    fn _set_working_area(p: Option<Packet>, area: WorkingArea) -> Result<(), ConnectorError> {
+        Self::check_ack(p, format_args!("Working area {}", area))
+    }
+
+    fn _set_working_channel(p: Option<Packet>, channel_index: u8) -> Result<(), ConnectorError> {
+        Self::check_ack(p, format_args!("Working channel {}", channel_index))
+    }
+
+    fn _set_select(p: Option<Packet>, bank: MemoryBank) -> Result<(), ConnectorError> {
+        Self::check_ack(p, format_args!("Select parameter on {:?}", bank))
+    }
+
+    fn _set_select_mode(p: Option<Packet>, enabled: bool) -> Result<(), ConnectorError> {
+        Self::check_ack(p, format_args!("Select mode {}", enabled))
+    }
+
+    /// Reject a channel index past the last channel of the working area, the device
+    /// would silently ignore it.
+    fn check_channel_index(area: WorkingArea, channel_index: u8) -> Result<(), ConnectorError> {
+        if channel_index >= area.channel_count() {
+            return Err(ConnectorError::InvalidParameter(format!(
+                "Channel {} is out of range, {:?} has {} channels",
+                channel_index,
+                area,
+                area.channel_count()
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_hopping_channels(area: WorkingArea, channels: &[u8]) -> Result<(), ConnectorError> {
+        if channels.len() > u8::MAX as usize {
+            return Err(ConnectorError::InvalidParameter(format!(
+                "At most {} hopping channels, got {}",
+                u8::MAX,
+                channels.len()
+            )));
+        }
+        channels
+            .iter()
+            .try_for_each(|&channel| Self::check_channel_index(area, channel))
+    }
+
+    fn check_gpio_pin(pin: u8) -> Result<(), ConnectorError> {
+        if !(1..=GPIO_PIN_COUNT).contains(&pin) {
+            return Err(ConnectorError::InvalidParameter(format!(
+                "GPIO pin must be between 1 and {}, got {}",
+                GPIO_PIN_COUNT, pin
+            )));
+        }
+        Ok(())
+    }
+
+    /// Result byte of a GPIO response: operation, pin, then the result or level
+    fn parse_gpio_result(p: Option<Packet>, pin: u8) -> Result<bool, ConnectorError> {
+        let p = p.ok_or(ConnectorError::NoPacketReceived)?;
+        let data = p.get_data();
+        if data.len() < 3 || data[1] != pin {
+            return Err(ConnectorError::InvalidResponse(format!(
+                "Unexpected GPIO response {:02X?}",
+                data
+            )));
+        }
+        Ok(data[2] == 0x01)
+    }
+
+    fn _set_gpio(p: Option<Packet>, pin: u8, what: &str) -> Result<(), ConnectorError> {
+        if Self::parse_gpio_result(p, pin)? {
+            info!("GPIO {} {} correct set", pin, what);
+            Ok(())
+        } else {
+            error!("GPIO {} {} not set", pin, what);
+            Err(ConnectorError::FailedSetting(format!(
+                "GPIO {} {} not set",
+                pin, what
+            )))
+        }
+    }
+
+    fn _set_rf_carrier(p: Option<Packet>, on: bool) -> Result<(), ConnectorError> {
+        Self::check_ack(p, format_args!("Continuous carrier {}", on))
+    }
+
+    fn _set_power_mode(p: Option<Packet>, mode: PowerMode) -> Result<(), ConnectorError> {
+        Self::check_ack(p, format_args!("Power mode {:?}", mode))
+    }
+
+    fn check_select_mask(mask: &[u8], mask_bit_len: u16) -> Result<(), ConnectorError> {
+        if mask_bit_len > u8::MAX as u16 {
+            return Err(ConnectorError::InvalidParameter(format!(
+                "Select mask can be at most 255 bits long, got {}",
+                mask_bit_len
+            )));
+        }
+        if mask.len() * 8 < mask_bit_len as usize {
+            return Err(ConnectorError::InvalidParameter(format!(
+                "Select mask of {} bytes is shorter than {} bits",
+                mask.len(),
+                mask_bit_len
+            )));
+        }
+        Ok(())
+    }
+
+    /// Mask bytes and bit length of a SelectMask agree: a byte past the last bit would be
+    /// sent and ignored, likely a wrong length
+    fn check_select_mask_bytes(select: &SelectMask) -> Result<(), ConnectorError> {
+        Self::check_select_mask(&select.mask, select.bit_len)?;
+        if select.mask.len() > (select.bit_len as usize).div_ceil(8) {
+            return Err(ConnectorError::InvalidParameter(format!(
+                "Select mask of {} bytes is longer than {} bits",
+                select.mask.len(),
+                select.bit_len
+            )));
+        }
+        Ok(())
+    }
+
+    fn _set_query_parameters(
+        p: Option<Packet>,
+        params: QueryParameters,
+    ) -> Result<(), ConnectorError> {
+        Self::check_ack(p, format_args!("Query parameters {:?}", params))
     }
 
-    fn parse_to_working_area(p: Packet) -> Result<WorkingArea, ConnectorError> {
+    /// Take the next complete frame out of `decoder`, reporting it to the event sink.
+    /// Command frames echoed by the serial adapter are skipped.
+    pub(crate) fn take_frame(&mut self, decoder: &mut FrameDecoder) -> Option<Packet> {
+        loop {
+            let p = match self.event_sink.as_mut() {
+                None => decoder.next_packet()?,
+                Some(sink) => {
+                    let p = decoder.next_packet_with(|dropped| {
+                        sink(ReaderEvent::ChecksumFailed(dropped.to_vec()))
+                    })?;
+                    sink(ReaderEvent::PacketDecoded(p.clone()));
+                    p
+                }
+            };
+            // Only the host sends commands: this is our own frame looped back by the adapter
+            if p.kind() == FrameType::Command {
+                debug!("Skipping echoed command frame: {}", p.debug());
+                continue;
+            }
+            return Some(p);
+        }
+    }
+
+    /// Text of a module information response, the leading info type byte is trimmed with
+    /// the other control characters. A missing response gives an empty string.
+    fn parse_module_info(p: Option<Packet>) -> String {
+        let text = p
+            .map(|p| String::from_utf8_lossy(&p.get_data()).into_owned())
+            .unwrap_or_default();
+        clear_non_ascii(&text)
+            .trim_matches(|c: char| c.is_ascii_control() || c.is_ascii_whitespace())
+            .to_string()
+    }
+
+    /// SelParam (Target 3 bits, Action 3 bits, MemBank 2 bits), pointer (4 bytes),
+    /// mask length, truncate and the mask
+    fn parse_select_parameters(p: Packet) -> Result<SelectParameters, ConnectorError> {
+        let data = p.get_data();
+        if data.len() < 7 {
+            return Err(ConnectorError::InvalidResponse(
+                "Short select parameters response".into(),
+            ));
+        }
+        let mask_bit_len = data[5] as u16;
+        let mask = &data[7..];
+        if mask.len() < (mask_bit_len as usize).div_ceil(8) {
+            return Err(ConnectorError::InvalidResponse(format!(
+                "Select mask of {} bits with only {} bytes",
+                mask_bit_len,
+                mask.len()
+            )));
+        }
+        Ok(SelectParameters {
+            target: data[0] >> 5,
+            action: (data[0] >> 2) & 0x07,
+            mask_bank: match data[0] & 0x03 {
+                0 => MemoryBank::Reserved,
+                1 => MemoryBank::Epc,
+                2 => MemoryBank::Tid,
+                _ => MemoryBank::User,
+            },
+            bit_pointer: u32::from_be_bytes([data[1], data[2], data[3], data[4]]),
+            mask_bit_len,
+            truncate: data[6] == 0x80,
+            mask: mask[..(mask_bit_len as usize).div_ceil(8)].to_vec(),
+        })
+    }
+
+    fn parse_query_parameters(p: Packet) -> Result<QueryParameters, ConnectorError> {
+        let data = p.get_data();
+        if data.len() < 2 {
+            return Err(ConnectorError::InvalidResponse(
+                "Short query parameters response".into(),
+            ));
+        }
+        Ok(QueryParameters::from_word(
+            ((data[0] as u16) << 8) | data[1] as u16,
+        ))
+    }
+
+    fn check_query_parameters(q: u8) -> Result<(), ConnectorError> {
+        if q > 15 {
+            return Err(ConnectorError::InvalidParameter(format!(
+                "Q must be between 0 and 15, got {}",
+                q
+            )));
+        }
+        Ok(())
+    }
+
+    fn _set_auto_frequency_hopping(p: Option<Packet>, enabled: bool) -> Result<(), ConnectorError> {
+        Self::check_ack(p, format_args!("Automatic frequency hopping {}", enabled))
+    }
+
+    fn _set_hopping_channels(p: Option<Packet>, channels: &[u8]) -> Result<(), ConnectorError> {
+        Self::check_ack(p, format_args!("Hopping channels {:?}", channels))
+    }
+
+    fn parse_tag_memory(p: Packet) -> Result<Vec<u8>, ConnectorError> {
         let data = p.get_data();
         if data.is_empty() {
             return Err(ConnectorError::InvalidResponse(
-                "Empty working area response".into(),
+                "Empty read tag memory response".into(),
             ));
         }
-        match data[0] {
-            0 => Ok(WorkingArea::China900Mhz),
-            1 => Ok(WorkingArea::China800Mhz),
-            2 => Ok(WorkingArea::US),
-            3 => Ok(WorkingArea::EU),
-            4 => Ok(WorkingArea::Korea),
-            _ => Err(ConnectorError::InvalidWorkingArea),
+        // Response: UL (length of PC + EPC), PC + EPC of the tag, then the words read
+        let data_start = 1 + data[0] as usize;
+        if data.len() < data_start {
+            return Err(ConnectorError::InvalidResponse(
+                "Truncated read tag memory response".into(),
+            ));
         }
+        Ok(data[data_start..].to_vec())
     }
 
-    fn _set_transmission_power(p: Option<Packet>, power: f64) -> Result<(), ConnectorError> {
-        if let Some(p) = p {
-            let data = p.get_data();
-            if data.is_empty() {
-                return Err(ConnectorError::InvalidResponse(
-                    "Empty set-power ACK".into(),
-                ));
-            }
-            if data[0] == 0x00 {
-                info!("Power correct set to {}", power);
-                return Ok(());
-            } else {
-                error!("Power not set to {}", power);
-                return Err(ConnectorError::FailedSetting(format!(
-                    "Transmission power not set to {}",
-                    power
-                )));
+    /// Map a device error returned by a tag access command to the matching tag error
+    fn tag_access_error(err: ConnectorError) -> ConnectorError {
+        match err {
+            ConnectorError::Device(code) => {
+                error!("Tag access failed with code 0x{:02X}", code);
+                match code {
+                    // Read, write, kill and lock fail: the tag did not answer
+                    0x09 | 0x10 | 0x12 | 0x13 => ConnectorError::TagNotFound,
+                    // Gen2 "memory locked" on write and lock
+                    0xB4 | 0xC4 => ConnectorError::TagWriteProtected,
+                    // Access fail, the only password failure code of the manual
+                    0x16 => ConnectorError::WrongPassword,
+                    code => ConnectorError::TagAccessFailed(code),
+                }
             }
+            err => err,
+        }
+    }
+
+    fn check_words_to_read(word_count: u16) -> Result<(), ConnectorError> {
+        if word_count > MAX_READ_WORDS {
+            return Err(ConnectorError::InvalidParameter(format!(
+                "At most {} words can be read at once, got {}",
+                MAX_READ_WORDS, word_count
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_words_to_write(words: &[u16]) -> Result<(), ConnectorError> {
+        if words.is_empty() {
+            return Err(ConnectorError::InvalidParameter("No words to write".into()));
+        }
+        if words.len() > 32 {
+            return Err(ConnectorError::InvalidParameter(format!(
+                "At most 32 words can be written at once, got {}",
+                words.len()
+            )));
+        }
+        Ok(())
+    }
+
+    /// An EPC fits the 5 bit length field of the PC word: 1 to 31 words
+    fn check_epc_to_write(epc: &[u8]) -> Result<(), ConnectorError> {
+        if epc.is_empty() || !epc.len().is_multiple_of(2) || epc.len() > 62 {
+            return Err(ConnectorError::InvalidParameter(format!(
+                "An EPC is an even number of bytes from 2 to 62, got {}",
+                epc.len()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Words to write from word 1 of the EPC bank: the PC with the EPC length of `epc`,
+    /// the other fields of `current_pc` kept, then the EPC
+    fn epc_words(current_pc: &[u8], epc: &[u8]) -> Result<Vec<u16>, ConnectorError> {
+        let [hi, lo] = current_pc[..] else {
+            return Err(ConnectorError::InvalidResponse(format!(
+                "PC word of {} bytes",
+                current_pc.len()
+            )));
+        };
+        let pc = (u16::from_be_bytes([hi, lo]) & 0x07FF) | (((epc.len() / 2) as u16) << 11);
+        let mut words = Vec::with_capacity(1 + epc.len() / 2);
+        words.push(pc);
+        words.extend(epc.chunks(2).map(|w| u16::from_be_bytes([w[0], w[1]])));
+        Ok(words)
+    }
+
+    #[cfg(any(feature = "serialport", feature = "tokio-serial"))]
+    fn check_baud_rate(baud: u32) -> Result<(), ConnectorError> {
+        if !SUPPORTED_BAUD_RATES.contains(&baud) {
+            return Err(ConnectorError::UnsupportedBaud(baud));
+        }
+        Ok(())
+    }
+
+    fn check_lock_mask(lock_mask: &LockMask) -> Result<(), ConnectorError> {
+        if lock_mask.is_empty() {
+            return Err(ConnectorError::InvalidParameter(
+                "Lock mask does not change any area".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Status of a tag operation that only acknowledges (write, kill, lock)
+    fn parse_tag_status(p: Packet) -> Result<(), ConnectorError> {
+        let data = p.get_data();
+        if data.is_empty() {
+            return Err(ConnectorError::InvalidResponse(
+                "Empty tag operation response".into(),
+            ));
+        }
+        // Response: UL (length of PC + EPC), PC + EPC of the tag, then the status byte
+        match data.get(1 + data[0] as usize) {
+            Some(0x00) => Ok(()),
+            Some(code) => Err(ConnectorError::TagAccessFailed(*code)),
+            None => Err(ConnectorError::InvalidResponse(
+                "Truncated tag operation response".into(),
+            )),
         }
-        Err(ConnectorError::NoPacketReceived)
     }
 
     fn parse_rfid_packets(
         &self,
         response: Option<Vec<Packet>>,
     ) -> Result<Vec<Rfid>, ConnectorError> {
+        let tags = self.parse_rfid_frames(response)?;
+        Ok(tags.into_iter().map(|(rfid, _)| rfid).collect())
+    }
+
+    /// Tags of a polling response, each with the frame it was decoded from
+    fn parse_rfid_frames(
+        &self,
+        response: Option<Vec<Packet>>,
+    ) -> Result<Vec<(Rfid, Packet)>, ConnectorError> {
         let mut rfids = Vec::new();
-        if let Some(ps) = response {
-            if ps.len() == 1 && ps[0].get_data().first() == Some(&0x15) {
+        for p in response.into_iter().flatten() {
+            if let Some(rfid) = self.parse_rfid_packet(&p)? {
+                rfids.push((rfid, p));
+            }
+        }
+        Ok(rfids)
+    }
+
+    /// Tag of a polling response frame, None for the "no tags" answer, a tag under the
+    /// RSSI filter or a malformed payload
+    fn parse_rfid_packet(&self, p: &Packet) -> Result<Option<Rfid>, ConnectorError> {
+        match p.as_error() {
+            Some(0x15) => {
                 debug!("No tags present");
-            } else {
-                for p in ps {
-                    let data = p.get_data();
-                    if data.len() == 17 {
-                        rfids.push(Rfid::from_raw(data));
-                    }
-                }
+                return Ok(None);
+            }
+            Some(code) => return Err(ConnectorError::Device(code)),
+            None => {}
+        }
+        match self.parse_tag(p.get_data()) {
+            Some(rfid) if !self.rssi_accepted(&rfid) => Ok(None),
+            Some(rfid) => Ok(Some(rfid)),
+            None => {
+                warn!("Skipping malformed tag payload: {:?}", p.get_data());
+                Ok(None)
+            }
+        }
+    }
+
+    /// Tags of a polling response read with the clock time of their read
+    #[cfg(feature = "std")]
+    fn parse_timed_frames(
+        &self,
+        response: Option<Vec<(Packet, Duration)>>,
+    ) -> Result<Vec<TimedRfid>, ConnectorError> {
+        let mut rfids = Vec::new();
+        for (p, timestamp) in response.into_iter().flatten() {
+            if let Some(rfid) = self.parse_rfid_packet(&p)? {
+                rfids.push(TimedRfid { rfid, timestamp });
             }
         }
         Ok(rfids)
@@ -92,25 +932,579 @@ pub enum WorkingArea {
     Korea,
 }
 
+/// Channel layout of a WorkingArea: channel `i` is centred on `base_mhz + i * step_mhz`
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FrequencyPlan {
+    pub base_mhz: f64,
+    pub step_mhz: f64,
+    pub channel_count: u8,
+}
+
+impl FrequencyPlan {
+    /// Centre frequency of a channel, None when the index is outside the plan
+    pub fn frequency(&self, channel_index: u8) -> Option<f64> {
+        self.channels().nth(channel_index as usize)
+    }
+
+    /// Centre frequencies of every channel of the plan, in channel order
+    pub fn channels(&self) -> impl Iterator<Item = f64> + '_ {
+        (0..self.channel_count).map(|i| self.base_mhz + i as f64 * self.step_mhz)
+    }
+}
+
 impl WorkingArea {
+    /// Region code used by the device, 0x00 to 0x04
+    pub fn to_code(&self) -> u8 {
+        match self {
+            WorkingArea::China900Mhz => 0x00,
+            WorkingArea::China800Mhz => 0x01,
+            WorkingArea::US => 0x02,
+            WorkingArea::EU => 0x03,
+            WorkingArea::Korea => 0x04,
+        }
+    }
+
+    /// Area of a device region code, None for an unknown code
+    pub fn from_code(code: u8) -> Option<WorkingArea> {
+        match code {
+            0x00 => Some(WorkingArea::China900Mhz),
+            0x01 => Some(WorkingArea::China800Mhz),
+            0x02 => Some(WorkingArea::US),
+            0x03 => Some(WorkingArea::EU),
+            0x04 => Some(WorkingArea::Korea),
+            _ => None,
+        }
+    }
+
+    /// Channel layout of the area, base and step as in the R200 protocol manual
+    pub fn frequency_plan(&self) -> FrequencyPlan {
+        let (base_mhz, step_mhz, channel_count) = match self {
+            WorkingArea::China900Mhz => (920.125, 0.25, 20),
+            WorkingArea::China800Mhz => (840.125, 0.25, 20),
+            WorkingArea::US => (902.25, 0.5, 52),
+            WorkingArea::EU => (865.1, 0.2, 15),
+            WorkingArea::Korea => (917.1, 0.2, 32),
+        };
+        FrequencyPlan {
+            base_mhz,
+            step_mhz,
+            channel_count,
+        }
+    }
+
+    /// Number of channels of the area, valid channel indexes are `0..channel_count()`
+    pub fn channel_count(&self) -> u8 {
+        self.frequency_plan().channel_count
+    }
+
     pub fn packet_to_64(&self, p: Packet) -> f64 {
         let data = p.get_data();
         if data.is_empty() {
             return 0.0;
         }
-        match self {
-            WorkingArea::China900Mhz => return (data[0] as f64) * 0.25 + 920.125,
-            WorkingArea::China800Mhz => return (data[0] as f64) * 0.25 + 840.125,
-            WorkingArea::US => return (data[0] as f64) * 0.50 + 902.25,
-            WorkingArea::EU => return (data[0] as f64) * 0.2 + 865.1,
-            WorkingArea::Korea => return (data[0] as f64) * 0.2 + 917.1,
+        self.channel_mhz(data[0])
+    }
+
+    /// Centre frequency in MHz of a raw channel index of this area.
+    ///
+    /// Unlike `FrequencyPlan::frequency` an index past the last channel is not rejected.
+    pub fn channel_mhz(&self, channel_index: u8) -> f64 {
+        let plan = self.frequency_plan();
+        channel_index as f64 * plan.step_mhz + plan.base_mhz
+    }
+
+    /// Convert a frequency in MHz into the channel index used by the device for this area.
+    ///
+    /// Returns None when the frequency is outside the channels of the area.
+    pub fn channel_index(&self, mhz: f64) -> Option<u8> {
+        let plan = self.frequency_plan();
+        let index = libm::round((mhz - plan.base_mhz) / plan.step_mhz);
+        if (0.0..plan.channel_count as f64).contains(&index) {
+            Some(index as u8)
+        } else {
+            None
         }
     }
 }
 
+impl fmt::Display for WorkingArea {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            WorkingArea::China900Mhz => "China 900 MHz",
+            WorkingArea::China800Mhz => "China 800 MHz",
+            WorkingArea::US => "US",
+            WorkingArea::EU => "EU",
+            WorkingArea::Korea => "Korea",
+        };
+        let plan = self.frequency_plan();
+        // Rounded so that float steps print as in the manual (867.9, not 867.9000000000001)
+        let round = |mhz: f64| libm::round(mhz * 1000.0) / 1000.0;
+        let last = plan.base_mhz + (plan.channel_count - 1) as f64 * plan.step_mhz;
+        write!(f, "{} ({}–{} MHz)", name, round(plan.base_mhz), round(last))
+    }
+}
+
+/// Outcome of a multiple polling inventory, see `multi_polling_detailed`
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug)]
+pub struct InventoryResult {
+    /// Every tag notification, duplicates included, in the order received
+    pub reads: Vec<Rfid>,
+    /// Rounds the device ran. Always None: the R200 never reports it.
+    pub rounds_completed: Option<u16>,
+    /// Rounds in which no tag answered, the device reports each with error 0x15
+    pub empty_rounds: u16,
+    /// Number of distinct EPCs in `reads`
+    pub unique_count: usize,
+}
+
+/// Transmit power reported by the device
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TransmitPower {
+    pub dbm: f64,
+}
+
+impl TransmitPower {
+    /// Conducted power in milliwatts, `10^(dbm/10)`
+    pub fn milliwatts(&self) -> f64 {
+        libm::pow(10.0, self.dbm / 10.0)
+    }
+}
+
+impl fmt::Display for TransmitPower {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.2} dBm ({:.1} mW)", self.dbm, self.milliwatts())
+    }
+}
+
+/// Memory banks of a Gen2 tag
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MemoryBank {
+    Reserved = 0,
+    Epc = 1,
+    Tid = 2,
+    User = 3,
+}
+
+/// Lock action applied to one area of a tag, as defined by the Gen2 Lock command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LockAction {
+    /// Writable (or readable for passwords) without access password
+    Unlock = 0b00,
+    /// Writable forever, cannot be locked anymore
+    PermaUnlock = 0b01,
+    /// Writable only in the secured state, with the access password
+    Lock = 0b10,
+    /// Never writable anymore
+    PermaLock = 0b11,
+}
+
+/// Lock actions for every area of a tag, areas left unset are not changed.
+///
+/// ```
+/// use r200_uhf::connector::{LockAction, LockMask};
+/// let mask = LockMask::new()
+///     .kill_password(LockAction::PermaLock)
+///     .epc(LockAction::Lock);
+/// assert_eq!(mask.payload(), 0xCC320);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LockMask {
+    kill_password: Option<LockAction>,
+    access_password: Option<LockAction>,
+    epc: Option<LockAction>,
+    tid: Option<LockAction>,
+    user: Option<LockAction>,
+}
+
+impl LockMask {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn kill_password(mut self, action: LockAction) -> Self {
+        self.kill_password = Some(action);
+        self
+    }
+
+    pub fn access_password(mut self, action: LockAction) -> Self {
+        self.access_password = Some(action);
+        self
+    }
+
+    pub fn epc(mut self, action: LockAction) -> Self {
+        self.epc = Some(action);
+        self
+    }
+
+    pub fn tid(mut self, action: LockAction) -> Self {
+        self.tid = Some(action);
+        self
+    }
+
+    pub fn user(mut self, action: LockAction) -> Self {
+        self.user = Some(action);
+        self
+    }
+
+    /// Build a LockMask from a raw 20 bit Gen2 lock payload (10 mask bits then 10 action bits).
+    ///
+    /// Every area must have both mask bits set or both clear, and no action bit may be set
+    /// for an area whose mask is clear.
+    pub fn from_payload(payload: u32) -> Result<Self, ConnectorError> {
+        if payload > 0xF_FFFF {
+            return Err(ConnectorError::InvalidParameter(format!(
+                "Lock payload 0x{:X} is wider than 20 bits",
+                payload
+            )));
+        }
+        let mut areas = [None; 5];
+        for (i, area) in areas.iter_mut().enumerate() {
+            let shift = 8 - 2 * i;
+            let mask = (payload >> (10 + shift)) & 0b11;
+            let action = (payload >> shift) & 0b11;
+            *area = match (mask, action) {
+                (0b00, 0b00) => None,
+                (0b11, 0b00) => Some(LockAction::Unlock),
+                (0b11, 0b01) => Some(LockAction::PermaUnlock),
+                (0b11, 0b10) => Some(LockAction::Lock),
+                (0b11, 0b11) => Some(LockAction::PermaLock),
+                _ => {
+                    return Err(ConnectorError::InvalidParameter(format!(
+                        "Conflicting lock mask {:02b} and action {:02b} bits in payload 0x{:05X}",
+                        mask, action, payload
+                    )));
+                }
+            };
+        }
+        let [kill_password, access_password, epc, tid, user] = areas;
+        Ok(LockMask {
+            kill_password,
+            access_password,
+            epc,
+            tid,
+            user,
+        })
+    }
+
+    /// Raw 20 bit Gen2 lock payload: mask bits (Kill, Access, EPC, TID, User) then action bits
+    pub fn payload(&self) -> u32 {
+        [
+            self.kill_password,
+            self.access_password,
+            self.epc,
+            self.tid,
+            self.user,
+        ]
+        .iter()
+        .enumerate()
+        .filter_map(|(i, area)| area.map(|action| (8 - 2 * i, action)))
+        .fold(0, |payload, (shift, action)| {
+            payload | 0b11 << (10 + shift) | (action as u32) << shift
+        })
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.payload() == 0
+    }
+}
+
+/// Versions and manufacturer reported by the module
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModuleInfo {
+    pub hardware: String,
+    pub software: String,
+    pub manufacturer: String,
+}
+
+impl fmt::Display for ModuleInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Hardware: {} - Software: {} - Manufacturer: {}",
+            self.hardware, self.software, self.manufacturer
+        )
+    }
+}
+
+/// Number of GPIO pins of the R200, numbered from 1 (IO1 to IO4)
+pub const GPIO_PIN_COUNT: u8 = 4;
+
+/// Levels of the GPIO pins, `levels[0]` is IO1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GpioState {
+    /// true for a high level
+    pub levels: [bool; GPIO_PIN_COUNT as usize],
+}
+
+impl GpioState {
+    /// Level of a pin numbered from 1, None for a pin the module does not have
+    pub fn level(&self, pin: u8) -> Option<bool> {
+        self.levels.get((pin as usize).checked_sub(1)?).copied()
+    }
+}
+
+/// Power mode of the module
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PowerMode {
+    /// Normal operation
+    Active,
+    /// RF part powered off, settings kept, the serial interface still answers
+    Idle,
+    /// Chip powered down until a byte is received, settings are lost on wake-up
+    Sleep,
+}
+
+/// Gen2 session used by the Query command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Session {
+    S0 = 0,
+    S1 = 1,
+    S2 = 2,
+    S3 = 3,
+}
+
+/// Inventoried flag targeted by the Query command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Target {
+    A = 0,
+    B = 1,
+}
+
+/// Tags answering the Query command, according to their SL flag
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Sel {
+    All = 0,
+    NotSl = 2,
+    Sl = 3,
+}
+
+/// Parameters of the Gen2 Query command used by the device during inventories.
+///
+/// DR, M and TRext are fixed by the R200 (DR=8, M=1, pilot tone) and are not exposed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QueryParameters {
+    pub sel: Sel,
+    pub session: Session,
+    pub target: Target,
+    /// Number of slots as 2^q, from 0 to 15
+    pub q: u8,
+}
+
+impl QueryParameters {
+    const TREXT_PILOT_TONE: u16 = 1 << 12;
+
+    /// Bit layout used by the device: DR(1) M(2) TRext(1) Sel(2) Session(2) Target(1) Q(4) 000
+    pub(crate) fn to_word(self) -> u16 {
+        Self::TREXT_PILOT_TONE
+            | (self.sel as u16) << 10
+            | (self.session as u16) << 8
+            | (self.target as u16) << 7
+            | ((self.q & 0x0F) as u16) << 3
+    }
+
+    pub(crate) fn from_word(word: u16) -> Self {
+        QueryParameters {
+            sel: match (word >> 10) & 0x03 {
+                2 => Sel::NotSl,
+                3 => Sel::Sl,
+                _ => Sel::All,
+            },
+            session: match (word >> 8) & 0x03 {
+                0 => Session::S0,
+                1 => Session::S1,
+                2 => Session::S2,
+                _ => Session::S3,
+            },
+            target: if word & (1 << 7) == 0 {
+                Target::A
+            } else {
+                Target::B
+            },
+            q: ((word >> 3) & 0x0F) as u8,
+        }
+    }
+}
+
+/// Query presets for common inventories, see `set_inventory_mode`.
+///
+/// Every preset answers all tags (`Sel::All`) and targets flag A. The R200 has no target
+/// flip: a tag inventoried in S1, S2 or S3 moves to B and stays silent until its session
+/// flag decays back to A, which is what keeps it from being reported every round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InventoryMode {
+    /// One tag at a time, e.g. a desk reader: S0, Q=0. A single slot per round gives the
+    /// fastest reply, and S0 reports the tag again every round.
+    SingleTagFast,
+    /// Many tags in the field at once: S1, Q=6. 64 slots per round limit collisions, and
+    /// S1 rests a tag for 0.5 to 5 seconds once read so the others get a chance.
+    MultiTagDense,
+    /// Tags passing through a gate: S2, Q=4. S2 keeps a read tag silent for at least
+    /// 2 seconds after it leaves the field, so each passage is reported once.
+    PortalAntiRepeat,
+}
+
+impl InventoryMode {
+    /// Query parameters loaded on the device by the preset
+    pub fn query_parameters(&self) -> QueryParameters {
+        let (session, q) = match self {
+            InventoryMode::SingleTagFast => (Session::S0, 0),
+            InventoryMode::MultiTagDense => (Session::S1, 6),
+            InventoryMode::PortalAntiRepeat => (Session::S2, 4),
+        };
+        QueryParameters {
+            sel: Sel::All,
+            session,
+            target: Target::A,
+            q,
+        }
+    }
+}
+
+/// Select parameters loaded on the device, as read by `get_select`.
+///
+/// `mask_bank`, `bit_pointer`, `mask` and `mask_bit_len` are the arguments of `set_select`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SelectParameters {
+    /// Gen2 Select target, 3 bits (0 for the inventoried flag of S0)
+    pub target: u8,
+    /// Gen2 Select action, 3 bits
+    pub action: u8,
+    pub mask_bank: MemoryBank,
+    /// First bit compared in `mask_bank`
+    pub bit_pointer: u32,
+    pub mask_bit_len: u16,
+    /// Truncated replies enabled
+    pub truncate: bool,
+    /// Mask bytes, `mask_bit_len` bits rounded up to whole bytes
+    pub mask: Vec<u8>,
+}
+
+/// Select mask picking the tags to access, the arguments of `set_select` in one value.
+///
+/// Start from the bank to compare and give the bytes to match, the bit length follows them:
+///
+/// ```
+/// use r200_uhf::connector::SelectMask;
+///
+/// // Tags whose EPC starts with 30 00
+/// let mask = SelectMask::epc().starting_at_bit(0x20).match_bytes(&[0x30, 0x00]);
+/// assert_eq!(mask.bit_len, 16);
+/// ```
+///
+/// The mask is checked when it is sent, see `set_select_mask`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectMask {
+    pub bank: MemoryBank,
+    /// First bit compared in `bank`
+    pub bit_pointer: u32,
+    /// Mask bytes, `bit_len` bits rounded up to whole bytes
+    pub mask: Vec<u8>,
+    pub bit_len: u16,
+}
+
+impl SelectMask {
+    /// Empty mask on `bank`, starting at bit 0
+    pub fn new(bank: MemoryBank) -> Self {
+        SelectMask {
+            bank,
+            bit_pointer: 0,
+            mask: Vec::new(),
+            bit_len: 0,
+        }
+    }
+
+    /// Empty mask on the EPC bank, starting at the first EPC bit (0x20, after CRC and PC)
+    pub fn epc() -> Self {
+        SelectMask::new(MemoryBank::Epc).starting_at_bit(0x20)
+    }
+
+    /// Empty mask on the TID bank, starting at bit 0
+    pub fn tid() -> Self {
+        SelectMask::new(MemoryBank::Tid)
+    }
+
+    /// Match the whole EPC of a tag, as reported by `Rfid::epc_bytes`.
+    pub fn matching_epc(epc: &[u8]) -> Self {
+        SelectMask::epc().match_bytes(epc)
+    }
+
+    /// Compare from bit `bit_pointer` of the bank
+    pub fn starting_at_bit(mut self, bit_pointer: u32) -> Self {
+        self.bit_pointer = bit_pointer;
+        self
+    }
+
+    /// Compare every bit of `bytes`
+    pub fn match_bytes(self, bytes: &[u8]) -> Self {
+        self.match_bits(bytes, (bytes.len() * 8) as u16)
+    }
+
+    /// Compare the first `bit_len` bits of `bytes`, for a mask that does not end on a byte
+    pub fn match_bits(mut self, bytes: &[u8], bit_len: u16) -> Self {
+        self.mask = bytes.to_vec();
+        self.bit_len = bit_len;
+        self
+    }
+}
+
+/// Transmit power range of the R200 in dBm from its datasheet, returned by `get_power_range`
+/// when the hardware version carries no rating
+pub const DEFAULT_POWER_RANGE: (f64, f64) = (15.0, 26.0);
+
+const DEFAULT_READ_BUF_SIZE: usize = 1024;
+const DEFAULT_ROLLING_CAP: usize = 4096;
+/// Consecutive empty reads tolerated before the port is considered closed, some drivers
+/// return a few before the data arrives
+pub(crate) const MAX_EMPTY_READS: u32 = 3;
+/// Smallest read buffer accepted by `Connector::set_buffer_sizes`
+pub const MIN_READ_BUF_SIZE: usize = 64;
+/// Smallest rolling cap accepted by `Connector::set_buffer_sizes`, room for a few frames
+pub const MIN_ROLLING_CAP: usize = 512;
+
+/// Longest PC + EPC a tag reports: PC, 31 EPC words and the two XPC words
+const MAX_PC_EPC_LEN: usize = 2 + 62 + 4;
+/// Most words `read_tag_memory` reads at once, so that the answer (UL, PC + EPC and the
+/// words read) fits in a frame the decoder accepts
+pub const MAX_READ_WORDS: u16 = ((MAX_FRAME_DATA_LEN - 1 - MAX_PC_EPC_LEN) / 2) as u16;
+
+/// Byte sent to wake the module up, the module discards it
+pub(crate) const WAKE_UP_BYTE: u8 = 0x00;
+
+/// Select enabling Impinj FastID: 8 bits written to a reserved TID location, FastID on and
+/// TagFocus off. Tags without FastID do not match it, so with Select mode on they do not answer.
+pub(crate) const FASTID_SELECT_POINTER: u32 = 0x3D0;
+pub(crate) const FASTID_SELECT_MASK: [u8; 1] = [0b0000_0010];
+
+/// UART baud rates accepted by `set_baud_rate`
+pub const SUPPORTED_BAUD_RATES: [u32; 8] =
+    [9600, 19200, 38400, 57600, 115200, 230400, 460800, 921600];
+
+/// Command code used by the device for every execution failure response
+pub(crate) const RESPONSE_COMMAND_FAILURE: u8 = 0xFF;
+
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum ConnectorError {
+    #[cfg(feature = "std")]
     Io(io::Error),
+    /// The serial port could not be opened or configured
+    #[cfg(feature = "serialport")]
+    Serial(serialport::Error),
     Timeout,
     InvalidWorkingArea,
     NoPacketReceived,
@@ -118,12 +1512,42 @@ pub enum ConnectorError {
     InvalidResponse(String),
     SerialRead(String),
     ErrorStopMultiPolling(String),
+    /// The device answered with an execution failure frame, with the error code it returned
+    Device(u8),
+    /// The device could not access the tag, with the error code it returned
+    /// (0xA0 | Gen2 error code on read, 0xD0 | Gen2 error code on kill, ...)
+    TagAccessFailed(u8),
+    /// The tag refused the access password (device error 0x16)
+    WrongPassword,
+    /// No tag answered in the field, or the selected EPC did not match any tag
+    TagNotFound,
+    /// The tag memory is locked against writing (Gen2 "memory locked")
+    TagWriteProtected,
+    /// A parameter was rejected before sending anything to the device
+    InvalidParameter(String),
+    /// The baud rate is not one of `SUPPORTED_BAUD_RATES`
+    UnsupportedBaud(u32),
+    /// The response carries another command code than the request, e.g. a frame left over
+    /// by an inventory
+    UnexpectedResponse {
+        expected: u8,
+        got: u8,
+    },
+    /// The transmit power is outside of the range of the module, see `get_power_range`
+    PowerOutOfRange {
+        requested: f64,
+        min: f64,
+        max: f64,
+    },
 }
 
 impl fmt::Display for ConnectorError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            #[cfg(feature = "std")]
             ConnectorError::Io(e) => write!(f, "IO error: {}", e),
+            #[cfg(feature = "serialport")]
+            ConnectorError::Serial(e) => write!(f, "Serial port error ({:?}): {}", e.kind, e),
             ConnectorError::Timeout => write!(f, "Timeout"),
             ConnectorError::InvalidWorkingArea => write!(f, "Invalid working area"),
             ConnectorError::NoPacketReceived => write!(f, "No packet received"),
@@ -133,18 +1557,49 @@ impl fmt::Display for ConnectorError {
             ConnectorError::ErrorStopMultiPolling(msg) => {
                 write!(f, "Impossible to stop multiple polling [{msg}]")
             }
+            ConnectorError::Device(code) => write!(f, "Device error [error code 0x{code:02X}]"),
+            ConnectorError::TagAccessFailed(code) => {
+                write!(f, "Tag access failed [error code 0x{code:02X}]")
+            }
+            ConnectorError::WrongPassword => write!(f, "Wrong tag password"),
+            ConnectorError::TagNotFound => write!(f, "No tag found in the field"),
+            ConnectorError::TagWriteProtected => write!(f, "Tag memory is write protected"),
+            ConnectorError::InvalidParameter(msg) => write!(f, "Invalid parameter: {}", msg),
+            ConnectorError::UnsupportedBaud(baud) => write!(f, "Unsupported baud rate {baud}"),
+            ConnectorError::UnexpectedResponse { expected, got } => {
+                write!(
+                    f,
+                    "Unexpected response 0x{got:02X} to command 0x{expected:02X}"
+                )
+            }
+            ConnectorError::PowerOutOfRange {
+                requested,
+                min,
+                max,
+            } => write!(
+                f,
+                "Transmit power {requested} dBm out of the {min} to {max} dBm range"
+            ),
         }
     }
 }
 
-impl std::error::Error for ConnectorError {}
+impl core::error::Error for ConnectorError {}
 
+#[cfg(feature = "std")]
 impl From<io::Error> for ConnectorError {
     fn from(err: io::Error) -> Self {
         ConnectorError::Io(err)
     }
 }
 
+#[cfg(feature = "serialport")]
+impl From<serialport::Error> for ConnectorError {
+    fn from(err: serialport::Error) -> Self {
+        ConnectorError::Serial(err)
+    }
+}
+
 pub(crate) fn clear_non_ascii(s: &str) -> String {
     s.chars().filter(|c| c.is_ascii()).collect()
 }
@@ -154,15 +1609,29 @@ pub(crate) fn hexdump_line(prefix: &str, data: &[u8]) {
     for b in data {
         out.push_str(format!("{:02X} ", b).as_str());
     }
-    log::debug!("{} {}", prefix, out);
+    debug!("{} {}", prefix, out);
+}
+
+/// Check the status byte a setter is acknowledged with: 0x00 is success, any other
+/// value is the error code of the device
+pub(crate) fn expect_ack(p: &Packet) -> Result<(), ConnectorError> {
+    match p.get_data().first() {
+        Some(0x00) => Ok(()),
+        Some(&code) => Err(ConnectorError::Device(code)),
+        None => Err(ConnectorError::InvalidResponse("Empty ACK".into())),
+    }
 }
 
-pub(crate) fn calculate_transmit_power(p: Packet) -> Result<f64, ConnectorError> {
+pub(crate) fn calculate_transmit_power(p: Packet) -> Result<TransmitPower, ConnectorError> {
     let data = p.get_data();
     if data.len() >= 2 {
-        Ok(((data[0] as u16) * 256 + (data[1] as u16)) as f64 / 100.0)
+        Ok(TransmitPower {
+            dbm: raw_to_dbm([data[0], data[1]]),
+        })
     } else if data.len() == 1 {
-        Ok(data[0] as f64)
+        Ok(TransmitPower {
+            dbm: data[0] as f64,
+        })
     } else {
         Err(ConnectorError::InvalidResponse(
             "Empty power response".into(),
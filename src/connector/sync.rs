@@ -1,20 +1,49 @@
+use crate::connector::transport::Transport;
 use crate::connector::{
-    Connector, ConnectorError, WorkingArea, calculate_transmit_power, clear_non_ascii, hexdump_line,
+    Connector, ConnectorError, FASTID_SELECT_MASK, FASTID_SELECT_POINTER, GPIO_PIN_COUNT,
+    GpioState, InventoryMode, InventoryResult, LockMask, MAX_EMPTY_READS, MemoryBank, ModuleInfo,
+    PowerMode, QueryParameters, ReaderEvent, Sel, SelectMask, SelectParameters, Session, Target,
+    TransmitPower, WAKE_UP_BYTE, WorkingArea, calculate_transmit_power, hexdump_line,
 };
-use crate::frame::{Command, Frame, R200_FRAME_END, R200_FRAME_HEADER};
-use crate::packet::Packet;
-use crate::rfid::Rfid;
-use log::{debug, error};
-use std::io::{self, Read, Write};
+use crate::frame::{Command, Frame};
+use crate::logging::{debug, error, warn};
+use crate::packet::{FrameDecoder, Packet};
+#[cfg(feature = "std")]
+use crate::rfid::TimedRfid;
+use crate::rfid::{Rfid, TagObservation};
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::ControlFlow;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
 
 pub trait SyncIO {
-    type Socket: Read + Write;
+    type Socket: Transport;
     /// Setup the reader with default settings (inspired by e710_uhf)
     fn setup_reader(&mut self) -> Result<(), ConnectorError>;
+    /// Hardware, software and manufacturer of the module as one line of text.
     fn get_module_info(&mut self) -> Result<String, ConnectorError>;
+    /// Query the hardware version, software version and manufacturer of the module.
+    ///
+    /// To tell readers apart see `PortInfo::serial_number`.
+    ///
+    /// Returns
+    /// - Ok(ModuleInfo) with every field stripped of non-ASCII and control characters,
+    ///   a field is empty when its query gets no response.
+    /// - Other ConnectorError variants on I/O failure or timeout.
+    fn get_module_info_struct(&mut self) -> Result<ModuleInfo, ConnectorError>;
     /// Builds and sends the command
     fn send_packet(&mut self, command: Command) -> Result<(), ConnectorError>;
     fn single_read_from_serial(&mut self) -> Result<Option<Packet>, ConnectorError>;
+    /// Send the command and read its response, retrying as configured with
+    /// `Connector::set_command_retries`.
+    ///
+    /// An execution failure frame from the device is returned as `ConnectorError::Device`.
+    /// A response to another command is returned as `ConnectorError::UnexpectedResponse`.
+    fn send_and_receive(&mut self, command: Command) -> Result<Option<Packet>, ConnectorError>;
     fn read_from_serial(
         &mut self,
         num_expected_responses: Option<u32>,
@@ -27,36 +56,130 @@ pub trait SyncIO {
     /// - Err(ConnectorError::NoPacketReceived) if nothing is received.
     /// - Other ConnectorError variants on I/O failure or timeout.
     fn get_working_area(&mut self) -> Result<WorkingArea, ConnectorError>;
+    /// Get the region code of the working area configured on the device, 0x00 to 0x04 for
+    /// the areas of `WorkingArea`.
+    ///
+    /// Unlike `get_working_area` a code unknown to the library is returned as is, e.g. a
+    /// region added by a newer firmware, and `Connector::working_area` is cleared.
+    ///
+    /// Returns
+    /// - Ok(u8) with the region code reported by the device.
+    /// - Err(ConnectorError::InvalidResponse) if the response is empty.
+    /// - Err(ConnectorError::NoPacketReceived) if nothing is received.
+    /// - Other ConnectorError variants on I/O failure or timeout.
+    fn get_working_area_raw(&mut self) -> Result<u8, ConnectorError>;
+    /// Set the regulatory working area of the device.
+    ///
+    /// Returns
+    /// - Ok(()) when the device acknowledges the setting.
+    /// - Err(ConnectorError::Device) if the device answers with a non-zero status.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    /// - Other ConnectorError variants on I/O failure or timeout.
+    fn set_working_area(&mut self, area: WorkingArea) -> Result<(), ConnectorError>;
     /// Get the current working RF channel as a frequency in MHz.
     ///
     /// The raw channel index returned by the device is converted to MHz based on
     /// the configured WorkingArea. Different regions use different spacing and base frequencies.
+    /// The area is read from the device only the first time, see `Connector::working_area`.
     ///
     /// Returns
     /// - Ok(f64) with the center frequency in MHz.
+    /// - Err(ConnectorError::InvalidWorkingArea) if the region code of the device is unknown,
+    ///   its frequencies cannot be computed: use `get_working_area_raw` and
+    ///   `get_working_channel_index` instead.
     /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
-    /// - Other ConnectorError variants on I/O failure, timeout, or unknown working area.
+    /// - Other ConnectorError variants on I/O failure or timeout.
     fn get_working_channel(&mut self) -> Result<f64, ConnectorError>;
+    /// Every channel of the working area of the device, as (channel index, centre frequency in
+    /// MHz) pairs in channel order, e.g. for a site survey.
+    ///
+    /// The working area is read from the device, see `WorkingArea::frequency_plan`.
+    ///
+    /// Returns
+    /// - Ok(Vec<(u8, f64)>) with one entry per channel.
+    /// - Err(ConnectorError) as for get_working_area.
+    fn channel_plan(&mut self) -> Result<Vec<(u8, f64)>, ConnectorError>;
+    /// Get the raw channel index of the working RF channel, as set with `set_working_channel`.
+    ///
+    /// No region math is involved, the working area is not read.
+    ///
+    /// Returns
+    /// - Ok(u8) with the channel index reported by the device.
+    /// - Err(ConnectorError::InvalidResponse) if the response is empty.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    /// - Other ConnectorError variants on I/O failure or timeout.
+    fn get_working_channel_index(&mut self) -> Result<u8, ConnectorError>;
+    /// Set the working RF channel using the raw channel index of the current WorkingArea.
+    ///
+    /// Ineffective while automatic frequency hopping is enabled.
+    ///
+    /// Returns
+    /// - Ok(()) when the device acknowledges the setting.
+    /// - Err(ConnectorError::InvalidParameter) if the index is past the last channel of the
+    ///   WorkingArea, see `WorkingArea::channel_count`. The area is read from the device only
+    ///   the first time, see `Connector::working_area`.
+    /// - Err(ConnectorError::Device) with the status if the device refuses it.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    /// - Other ConnectorError variants as for get_working_area.
+    fn set_working_channel(&mut self, channel_index: u8) -> Result<(), ConnectorError>;
+    /// Set the working RF channel from a frequency in MHz.
+    ///
+    /// The channel index is computed from the WorkingArea of the device, read once and cached.
+    ///
+    /// Returns
+    /// - Ok(()) when the device acknowledges the setting.
+    /// - Err(ConnectorError::InvalidParameter) if the frequency has no channel in the area.
+    /// - Other ConnectorError variants as for set_working_channel.
+    fn set_working_channel_mhz(&mut self, mhz: f64) -> Result<(), ConnectorError>;
     /// Read the current transmit power reported by the device.
     ///
-    /// The device returns two bytes that represent the power value scaled by 100.
-    /// This method combines them and returns the value as f64.
+    /// The device returns two bytes that represent the power in dBm scaled by 100.
     ///
     /// Returns
-    /// - Ok(f64) with the transmit power (device-specific units, typically dBm).
+    /// - Ok(TransmitPower) with the transmit power in dBm, see `TransmitPower::milliwatts`.
     /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
     /// - Other ConnectorError variants on I/O failure or timeout.
-    fn get_transmit_power(&mut self) -> Result<f64, ConnectorError>;
+    fn get_transmit_power(&mut self) -> Result<TransmitPower, ConnectorError>;
+    /// Read the current transmit power in dBm.
+    ///
+    /// Returns
+    /// - Ok(f64) with `get_transmit_power().dbm`.
+    /// - Err(ConnectorError) as for get_transmit_power.
+    #[deprecated(note = "use get_transmit_power, which returns a TransmitPower")]
+    fn get_transmit_power_dbm(&mut self) -> Result<f64, ConnectorError>;
+    /// Range of transmit power the module accepts, as (min, max) in dBm.
+    ///
+    /// The protocol has no command for it: the maximum is scraped from the rating in the
+    /// hardware version string (e.g. "M100 26dBm V1.0"), the minimum is assumed from the R200
+    /// datasheet. Without a rating `DEFAULT_POWER_RANGE` is returned. The range is read once
+    /// and then used by `set_transmission_power` to reject requests the module would clamp.
+    ///
+    /// Returns
+    /// - Ok((f64, f64)) with the minimum and maximum power.
+    /// - Other ConnectorError variants on I/O failure or timeout.
+    fn get_power_range(&mut self) -> Result<(f64, f64), ConnectorError>;
     /// Set the transmitter output power.
     ///
     /// Parameters
-    /// - power: Desired transmit power in device-specific units (typically dBm).
+    /// - power: Desired transmit power in dBm, within `get_power_range`.
     ///
     /// Returns
     /// - Ok(()) when the device acknowledges the setting.
+    /// - Err(ConnectorError::PowerOutOfRange) if `power` is outside of the range of the module,
+    ///   read with `get_power_range` the first time; the power is not sent.
+    /// - Err(ConnectorError::Device) if the device answers with a non-zero status.
     /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
     /// - Other ConnectorError variants on I/O failure or timeout.
     fn set_transmission_power(&mut self, power: f64) -> Result<(), ConnectorError>;
+    /// Set the transmitter output power and read back the power actually applied.
+    ///
+    /// The device may clamp the request to the range it supports without reporting it,
+    /// so a warning is logged when the applied power is more than 0.5 off the request.
+    ///
+    /// Returns
+    /// - Ok(f64) with the power reported by the device after the setting.
+    /// - Err(ConnectorError) as for set_transmission_power and get_transmit_power.
+    fn set_transmit_power_checked(&mut self, power: f64) -> Result<f64, ConnectorError>;
     /// Perform a single inventory (poll) and return the list of detected tags.
     ///
     /// Sends a SinglePollingInstruction to the reader and parses all returned packets
@@ -66,17 +189,477 @@ pub trait SyncIO {
     /// - Ok(Vec<Rfid>) possibly empty if no tags are present.
     /// - Err(ConnectorError::Timeout or other) on communication errors.
     fn single_polling_instruction(&mut self) -> Result<Vec<Rfid>, ConnectorError>;
+    /// Perform a single inventory like `single_polling_instruction`, pairing every tag with
+    /// the frame it came from.
+    ///
+    /// `Rfid::raw` only holds the tag payload, the frame bytes here run from the 0xAA header
+    /// to the 0xDD end byte, checksum included, to check another decoder against this one.
+    ///
+    /// Returns
+    /// - Ok(Vec<(Rfid, Vec<u8>)>) possibly empty if no tags are present.
+    /// - Err(ConnectorError::Timeout or other) on communication errors.
+    fn single_polling_instruction_raw(&mut self) -> Result<Vec<(Rfid, Vec<u8>)>, ConnectorError>;
+    /// Perform a single inventory like `single_polling_instruction`, with the time every
+    /// tag frame was read from the port, see `TimedRfid`.
+    ///
+    /// Returns
+    /// - Ok(Vec<TimedRfid>) possibly empty if no tags are present.
+    /// - Err(ConnectorError::Timeout or other) on communication errors.
+    #[cfg(feature = "std")]
+    fn single_polling_instruction_timed(&mut self) -> Result<Vec<TimedRfid>, ConnectorError>;
+    /// Perform a single inventory like `single_polling_instruction`, but stop collecting
+    /// tags once `deadline` has elapsed.
+    ///
+    /// Every read of the port is cut short at the deadline, whatever the timeout of the port:
+    /// see `Transport::read_with_timeout`, serial ports (`Box<dyn SerialPort>`) support it.
+    /// With a transport that cannot bound its reads the deadline is only checked between
+    /// reads.
+    ///
+    /// Returns
+    /// - Ok(Vec<Rfid>) with the tags gathered before the deadline, possibly empty.
+    /// - Err(ConnectorError::Timeout or other) on communication errors.
+    #[cfg(feature = "std")]
+    fn single_polling_instruction_with_timeout(
+        &mut self,
+        deadline: core::time::Duration,
+    ) -> Result<Vec<Rfid>, ConnectorError>;
+    /// Poll until a tag answers, for a turnstile or a badge reader.
+    ///
+    /// Single polls are repeated until one returns a tag or `timeout` has elapsed since the
+    /// call, the timeout is a deadline across all the polls.
+    ///
+    /// Returns
+    /// - Ok(Some(Rfid)) with the first tag seen.
+    /// - Ok(None) if no tag answered before the deadline.
+    /// - Err(ConnectorError) on communication errors other than a read timeout.
+    #[cfg(feature = "std")]
+    fn wait_for_tag(
+        &mut self,
+        timeout: core::time::Duration,
+    ) -> Result<Option<Rfid>, ConnectorError>;
     fn multi_polling_instruction(&mut self) -> Result<Vec<Rfid>, ConnectorError>; // Start Multi: AA 00 27 00 03 22 FF FF 4A DD
+    /// Run a multiple polling inventory of `max` rounds, calling `on_tag` for each tag as soon
+    /// as its frame is decoded.
+    ///
+    /// Returning `ControlFlow::Break` from `on_tag` stops the inventory on the device and
+    /// discards the frames still in flight. Otherwise the inventory ends when the serial port
+    /// times out without receiving anything.
+    ///
+    /// Returns
+    /// - Ok(()) when the inventory is over or has been stopped by `on_tag`.
+    /// - Err(ConnectorError::Device) if the device reports an error other than "no tag".
+    /// - Other ConnectorError variants on I/O failure.
+    fn multi_polling_with<F>(&mut self, max: u16, on_tag: F) -> Result<(), ConnectorError>
+    where
+        F: FnMut(Rfid) -> ControlFlow<()>;
+    /// Run a multiple polling inventory of `max` rounds and keep the round statistics.
+    ///
+    /// Same inventory as `multi_polling_instruction`, with `max` rounds and the counters
+    /// needed for a throughput figure per burst.
+    ///
+    /// Returns
+    /// - Ok(InventoryResult) once the port goes quiet and the inventory has been stopped.
+    /// - Err(ConnectorError) as for multi_polling_with.
+    fn multi_polling_detailed(&mut self, max: u16) -> Result<InventoryResult, ConnectorError>;
+    /// Run a multiple polling inventory of `max` rounds, sending each tag through `tx` as
+    /// soon as it is decoded.
+    ///
+    /// A full channel blocks the read loop until the receiver catches up, so a long burst
+    /// is never buffered in memory; the reader keeps the tags meanwhile. Dropping the
+    /// receiver stops the inventory on the device.
+    ///
+    /// Returns
+    /// - Ok(()) when the inventory is over or the receiver has been dropped.
+    /// - Err(ConnectorError) as for multi_polling_with.
+    #[cfg(feature = "std")]
+    fn multi_polling_streaming(
+        &mut self,
+        tx: std::sync::mpsc::SyncSender<Rfid>,
+        max: u16,
+    ) -> Result<(), ConnectorError>;
+    /// Run an inventory for `dwell`, for a reading station where tags pass by.
+    ///
+    /// The inventory is started without a round limit, tags are collected until `dwell` has
+    /// elapsed, port timeouts included, then the inventory is stopped and the frames still in
+    /// flight are discarded. The last read is cut short at the end of the dwell as in
+    /// `single_polling_instruction_with_timeout`. The stop is sent also when the collection
+    /// fails.
+    ///
+    /// Returns
+    /// - Ok(Vec<Rfid>) with every read, duplicates included, possibly empty.
+    /// - Err(ConnectorError::Device) if the device reports an error other than "no tag".
+    /// - Err(ConnectorError::ErrorStopMultiPolling) if the stop is not acknowledged.
+    /// - Other ConnectorError variants on I/O failure.
+    #[cfg(feature = "std")]
+    fn inventory_for(&mut self, dwell: core::time::Duration) -> Result<Vec<Rfid>, ConnectorError>;
+    /// Run a multiple polling inventory of `max` rounds that can be cancelled from another
+    /// thread, e.g. a "stop scan" button.
+    ///
+    /// `cancel` is checked before every read of the port; once it is set the inventory is
+    /// stopped and the frames still in flight are discarded. Otherwise the inventory ends
+    /// when the serial port times out without receiving anything.
+    ///
+    /// Returns
+    /// - Ok(Vec<Rfid>) with the reads gathered until the end or the cancellation.
+    /// - Err(ConnectorError) as for inventory_for.
+    fn multi_polling_cancellable(
+        &mut self,
+        max: u16,
+        cancel: &AtomicBool,
+    ) -> Result<Vec<Rfid>, ConnectorError>;
+    /// Run a multiple polling inventory of `total_rounds` rounds, split in runs of at most
+    /// `u16::MAX` rounds, the limit of a single start.
+    ///
+    /// The R200 does not report how many rounds it ran, so an inventory the device ends
+    /// early, e.g. when throttling on temperature, cannot be detected: every run is taken
+    /// as complete and the rounds it skipped are not run again.
+    ///
+    /// Returns
+    /// - Ok(Vec<Rfid>) with every read of every run, duplicates included.
+    /// - Err(ConnectorError) as for multi_polling_with.
+    fn inventory_resilient(&mut self, total_rounds: u32) -> Result<Vec<Rfid>, ConnectorError>;
+    /// Run a multiple polling inventory of `rounds` rounds and group the tags by EPC.
+    ///
+    /// Returns
+    /// - Ok(Vec<TagObservation>) with one entry per EPC, in the order they were first seen.
+    /// - Err(ConnectorError) as for multi_polling_with.
+    fn inventory_unique(&mut self, rounds: u16) -> Result<Vec<TagObservation>, ConnectorError>;
+    /// Run a multiple polling inventory of `max` rounds with Impinj FastID enabled.
+    ///
+    /// FastID tags backscatter their TID with the EPC in the same reply, which is stored in
+    /// `Rfid::tid`. Select mode is on during the inventory, so tags without FastID do not
+    /// answer. The previous Select filter is restored afterwards, with the Select mode last
+    /// set through the connector (off if it was never set).
+    ///
+    /// Returns
+    /// - Ok(Vec<Rfid>) possibly empty if no tags are present.
+    /// - Err(ConnectorError::Device) if the device rejects the Select setup or restore.
+    /// - Err(ConnectorError) as for multi_polling_with.
+    fn inventory_with_tid(&mut self, max: u16) -> Result<Vec<Rfid>, ConnectorError>;
     fn enable_multiple_polling_instructions(
         &mut self,
         pool_times: u16,
     ) -> Result<(), ConnectorError>; // Stop Multi: AA 00 28 00 00 28 DD
-    fn stop_multiple_polling_instructions(&mut self) -> Result<(), ConnectorError>;
+    /// Stop a multiple polling inventory and wait for the device to confirm it.
+    ///
+    /// Tag frames still in flight are read and discarded up to the stop acknowledge, so the
+    /// next command cannot mistake one of them for its response. Safe to call when no
+    /// inventory is running, the device acknowledges the stop anyway.
+    ///
+    /// Returns
+    /// - Ok(usize) with the number of frames discarded before the acknowledge.
+    /// - Err(ConnectorError::ErrorStopMultiPolling) if no acknowledge arrives before the
+    ///   port times out, after the retries set with `Connector::set_command_retries`.
+    /// - Other ConnectorError variants on I/O failure.
+    fn stop_multiple_polling_instructions(&mut self) -> Result<usize, ConnectorError>;
+    /// Get the Gen2 Query parameters used by the device during inventories.
+    ///
+    /// Returns
+    /// - Ok(QueryParameters) decoded from the device response.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    /// - Other ConnectorError variants on I/O failure or timeout.
+    fn get_query_parameters(&mut self) -> Result<QueryParameters, ConnectorError>;
+    /// Set the Gen2 Query parameters used by the device during inventories.
+    ///
+    /// A higher `q` gives more slots per round, which helps with many tags in the field.
+    ///
+    /// Returns
+    /// - Ok(()) when the device acknowledges the setting.
+    /// - Err(ConnectorError::InvalidParameter) if `q` is greater than 15; nothing is sent.
+    /// - Err(ConnectorError::Device) if the device answers with a non-zero status.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    fn set_query_parameters(
+        &mut self,
+        q: u8,
+        session: Session,
+        target: Target,
+        sel: Sel,
+    ) -> Result<(), ConnectorError>;
+    /// Load the Query parameters of an inventory preset, see `InventoryMode` for what
+    /// each one sets.
+    ///
+    /// Returns
+    /// - Ok(()) when the device acknowledges the setting.
+    /// - Err(ConnectorError) as for set_query_parameters.
+    fn set_inventory_mode(&mut self, mode: InventoryMode) -> Result<(), ConnectorError>;
+    /// Read the Select parameters currently loaded on the device.
+    ///
+    /// Returns
+    /// - Ok(SelectParameters) decoded from the device response.
+    /// - Err(ConnectorError::InvalidResponse) if the response is too short for its mask.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    fn get_select(&mut self) -> Result<SelectParameters, ConnectorError>;
+    /// Set the Select parameters used to single out tags before tag operations.
+    ///
+    /// The mask is bit addressed: `bit_pointer` is the first bit compared in `mask_bank`
+    /// (the EPC starts at bit 0x20 of the EPC bank, after CRC and PC) and only the first
+    /// `mask_bit_len` bits of `mask` are used. Enable it with `set_select_mode`.
+    ///
+    /// Returns
+    /// - Ok(()) when the device acknowledges the setting.
+    /// - Err(ConnectorError::InvalidParameter) if `mask` holds fewer than `mask_bit_len`
+    ///   bits or the mask is longer than 255 bits; nothing is sent.
+    /// - Err(ConnectorError::Device) if the device answers with a non-zero status.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    fn set_select(
+        &mut self,
+        mask_bank: MemoryBank,
+        bit_pointer: u32,
+        mask: &[u8],
+        mask_bit_len: u16,
+    ) -> Result<(), ConnectorError>;
+    /// Set the Select parameters from a `SelectMask`, see `set_select`.
+    ///
+    /// Returns
+    /// - Ok(()) when the device acknowledges the setting.
+    /// - Err(ConnectorError::InvalidParameter) if the mask bytes do not hold exactly
+    ///   `bit_len` bits rounded up to whole bytes, or the mask is longer than 255 bits;
+    ///   nothing is sent.
+    /// - Err(ConnectorError::Device) if the device answers with a non-zero status.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    fn set_select_mask(&mut self, select: &SelectMask) -> Result<(), ConnectorError>;
+    /// Enable or disable the Select filter set with `set_select`.
+    ///
+    /// When enabled the device sends Select before every tag operation, polling
+    /// included, so only matching tags answer.
+    ///
+    /// Returns
+    /// - Ok(()) when the device acknowledges the setting.
+    /// - Err(ConnectorError::Device) if the device answers with a non-zero status.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    fn set_select_mode(&mut self, enabled: bool) -> Result<(), ConnectorError>;
+    /// Enable or disable automatic frequency hopping.
+    ///
+    /// While hopping is enabled the device picks the channel on its own, so
+    /// `set_working_channel` has no lasting effect.
+    ///
+    /// Returns
+    /// - Ok(()) when the device acknowledges the setting.
+    /// - Err(ConnectorError::Device) if the device answers with a non-zero status.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    fn set_auto_frequency_hopping(&mut self, enabled: bool) -> Result<(), ConnectorError>;
+    /// Restrict automatic frequency hopping to a list of channel indexes of the WorkingArea,
+    /// e.g. to keep away from congested sub-bands. An empty list clears it, and the device
+    /// hops over every channel again.
+    ///
+    /// The device cannot report the list, the one set last is kept in
+    /// `Connector::hopping_channels`.
+    ///
+    /// Returns
+    /// - Ok(()) when the device acknowledges the list.
+    /// - Err(ConnectorError::InvalidParameter) if an index is past the last channel of the
+    ///   WorkingArea, read as for set_working_channel, or the list has more than 255 entries.
+    /// - Err(ConnectorError::Device) with the status if the device refuses it.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    /// - Other ConnectorError variants as for get_working_area.
+    fn set_hopping_channels(&mut self, channels: &[u8]) -> Result<(), ConnectorError>;
+    /// Send any command code with its parameters and return the first response frame.
+    ///
+    /// Length and checksum are computed for you. Error frames (command code 0xFF)
+    /// are returned as they are, so they can be inspected.
+    ///
+    /// Returns
+    /// - Ok(Packet) with the first valid frame received.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    /// - Other ConnectorError variants on I/O failure or timeout.
+    fn send_raw(&mut self, command_code: u8, params: &[u8]) -> Result<Packet, ConnectorError>;
+    /// Read and discard bytes until the stream is back on a frame boundary, e.g. after a USB
+    /// hiccup cut a frame in half.
+    ///
+    /// Stops when a read ends exactly after a complete frame, when the port times out, or
+    /// once the rolling cap set with `Connector::set_buffer_sizes` has been discarded, so a
+    /// reader still streaming tags cannot keep it going. Everything read, complete frames
+    /// included, is dropped. `send_and_receive` calls it after an `UnexpectedResponse`, since
+    /// the rest of the foreign answer is still queued.
+    ///
+    /// Returns
+    /// - Ok(usize) with the number of bytes discarded.
+    /// - Err(ConnectorError) on I/O failures other than a timeout.
+    fn resync(&mut self) -> Result<usize, ConnectorError>;
+    /// Permanently kill the selected tag.
+    ///
+    /// Select the tag first with `set_select`, otherwise any tag in the field may be killed.
+    /// A tag with an all zero kill password cannot be killed.
+    ///
+    /// Returns
+    /// - Ok(()) when the tag confirms the kill.
+    /// - Err(ConnectorError::TagNotFound) if no tag answered. A tag given the wrong kill
+    ///   password does not answer either, so this is also how a wrong password shows up.
+    /// - Err(ConnectorError::TagAccessFailed) for any other device error code.
+    fn kill_tag(&mut self, kill_password: u32) -> Result<(), ConnectorError>;
+    /// Reset the module by putting it to sleep and waking it up again.
+    ///
+    /// The R200 has no dedicated reset command, but sleeping powers the chip down and waking
+    /// it reloads the firmware. Power, channel, frequency hopping, Select and demodulator
+    /// settings go back to their defaults, so the caller has to configure them again.
+    /// Anything left in the serial buffers is discarded while the module restarts.
+    ///
+    /// Returns
+    /// - Ok(()) once the module has been woken up.
+    /// - Err(ConnectorError::Device) if the module refuses to sleep.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    /// - Other ConnectorError variants on I/O failure.
+    fn reset_module(&mut self) -> Result<(), ConnectorError>;
+    /// Switch the module to a power mode.
+    ///
+    /// - Idle powers the RF part off and keeps every setting, the next tag operation
+    ///   brings the module back to Active by itself.
+    /// - Sleep powers the chip down. Waking it up (Active) reloads the firmware, so power,
+    ///   channel, frequency hopping, Select and demodulator settings go back to their defaults.
+    ///
+    /// Returns
+    /// - Ok(()) when the device acknowledges the mode, or has been woken up from Sleep.
+    /// - Err(ConnectorError::Device) if the device answers with a non-zero status.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    /// - Other ConnectorError variants on I/O failure.
+    fn set_power_mode(&mut self, mode: PowerMode) -> Result<(), ConnectorError>;
+    /// Start or stop transmitting an unmodulated carrier on the current channel and power,
+    /// for bench measurements such as antenna tuning.
+    ///
+    /// Once started the reader transmits continuously until it is stopped, which may break
+    /// radio regulations and heats the module. Prefer `start_rf_carrier`, which stops it
+    /// when the guard is dropped.
+    ///
+    /// Returns
+    /// - Ok(()) when the device acknowledges the setting.
+    /// - Err(ConnectorError::Device) if the device answers with a non-zero status.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    fn set_rf_carrier(&mut self, on: bool) -> Result<(), ConnectorError>;
+    /// Configure a GPIO pin (1 to `GPIO_PIN_COUNT`) as output or input.
+    ///
+    /// Returns
+    /// - Ok(()) when the device confirms the configuration.
+    /// - Err(ConnectorError::InvalidParameter) if the pin does not exist.
+    /// - Err(ConnectorError::FailedSetting) if the device reports a failure.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    fn set_gpio_direction(&mut self, pin: u8, output: bool) -> Result<(), ConnectorError>;
+    /// Drive a GPIO output pin (1 to `GPIO_PIN_COUNT`) high or low.
+    ///
+    /// The pin must be configured as output with `set_gpio_direction` first.
+    ///
+    /// Returns
+    /// - Ok(()) when the device confirms the level.
+    /// - Err(ConnectorError::InvalidParameter) if the pin does not exist.
+    /// - Err(ConnectorError::FailedSetting) if the device reports a failure.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    fn set_gpio(&mut self, pin: u8, level: bool) -> Result<(), ConnectorError>;
+    /// Read the level of every GPIO pin.
+    ///
+    /// Returns
+    /// - Ok(GpioState) with the level of each pin, inputs and outputs alike.
+    /// - Err(ConnectorError::InvalidResponse) if a response does not match the pin read.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    fn get_gpio(&mut self) -> Result<GpioState, ConnectorError>;
+    /// Start transmitting an unmodulated carrier, stopped again when the guard is dropped.
+    ///
+    /// Returns
+    /// - Ok(CarrierGuard) once the device acknowledges the carrier.
+    /// - Err(ConnectorError) as for set_rf_carrier.
+    fn start_rf_carrier(&mut self) -> Result<CarrierGuard<'_, Self::Socket>, ConnectorError>;
+    /// Lock or unlock areas of the selected tag.
+    ///
+    /// Select the tag first with `set_select`. Areas not set in `lock_mask` keep their state.
+    /// Without `access_password` the one set with `Connector::set_access_password` is used.
+    ///
+    /// Returns
+    /// - Ok(()) when the tag confirms the lock.
+    /// - Err(ConnectorError::InvalidParameter) if `lock_mask` does not change any area.
+    /// - Err(ConnectorError::TagNotFound) if no tag answered.
+    /// - Err(ConnectorError::WrongPassword) if the tag refused the access password.
+    /// - Err(ConnectorError::TagWriteProtected) if an area is permalocked.
+    /// - Err(ConnectorError::TagAccessFailed) for any other device error code.
+    fn lock_tag(
+        &mut self,
+        access_password: Option<u32>,
+        lock_mask: LockMask,
+    ) -> Result<(), ConnectorError>;
+    /// Start a multiple polling inventory of `max` rounds and yield tags as they arrive.
+    ///
+    /// The stream ends when the serial port times out without receiving anything.
+    /// Dropping it stops the inventory on the device and discards the frames still in flight.
+    ///
+    /// Returns
+    /// - Ok(InventoryStream) once the start command has been sent.
+    /// - Err(ConnectorError) if the command cannot be written.
+    fn inventory_stream(
+        &mut self,
+        max: u16,
+    ) -> Result<InventoryStream<'_, Self::Socket>, ConnectorError>;
+    /// Read `word_count` words starting at `word_addr` from a memory bank of the selected tag.
+    ///
+    /// Without `access_password` the one set with `Connector::set_access_password` is used,
+    /// 0 skips the access step on the tag.
+    ///
+    /// Returns
+    /// - Ok(Vec<u8>) with the bytes read (two per word).
+    /// - Err(ConnectorError::InvalidParameter) if `word_count` is above `MAX_READ_WORDS`;
+    ///   nothing is sent.
+    /// - Err(ConnectorError::TagNotFound) if no tag answered (0x09).
+    /// - Err(ConnectorError::WrongPassword) if the tag refused the access password (0x16).
+    /// - Err(ConnectorError::TagAccessFailed) with the device error code when the tag
+    ///   replied with a Gen2 error (0xA0 | code).
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    fn read_tag_memory(
+        &mut self,
+        bank: MemoryBank,
+        word_addr: u16,
+        word_count: u16,
+        access_password: Option<u32>,
+    ) -> Result<Vec<u8>, ConnectorError>;
+    /// Read a password protected tag: select it with `select`, then read `words` words starting
+    /// at `addr` of `bank` with `access_password`.
+    ///
+    /// The read command of the firmware sends Access before Read in the same device
+    /// transaction, so the only gap is between the Select setup and the read. Select mode is
+    /// disabled again afterwards, the Select filter is left as `select`.
+    ///
+    /// Returns
+    /// - Ok(Vec<u8>) with the bytes read (two per word).
+    /// - Err(ConnectorError::InvalidParameter) if the mask is rejected by `set_select_mask`.
+    /// - Err(ConnectorError::Device) if the device rejects the Select setup.
+    /// - Err(ConnectorError::TagAccessFailed) as for read_tag_memory.
+    fn access_and_read(
+        &mut self,
+        select: SelectMask,
+        access_password: u32,
+        bank: MemoryBank,
+        addr: u16,
+        words: u16,
+    ) -> Result<Vec<u8>, ConnectorError>;
+    /// Write `words` starting at `word_addr` into a memory bank of the selected tag.
+    ///
+    /// At most 32 words can be written with a single command. Without `access_password`
+    /// the one set with `Connector::set_access_password` is used, 0 skips the access step.
+    ///
+    /// Returns
+    /// - Ok(()) when the tag confirms the write.
+    /// - Err(ConnectorError::InvalidParameter) if `words` is empty or too long; nothing is sent.
+    /// - Err(ConnectorError::TagNotFound) if no tag answered.
+    /// - Err(ConnectorError::TagWriteProtected) if the bank is locked.
+    /// - Err(ConnectorError::TagAccessFailed) for any other device error code.
+    fn write_tag_memory(
+        &mut self,
+        bank: MemoryBank,
+        word_addr: u16,
+        words: &[u16],
+        access_password: Option<u32>,
+    ) -> Result<(), ConnectorError>;
+    /// Program a new EPC into the selected tag, for tag commissioning.
+    ///
+    /// The PC word is read first and written back with the EPC length of `new_epc`, its other
+    /// fields unchanged, followed by the EPC from word 2 of the EPC bank.
+    ///
+    /// Returns
+    /// - Ok(()) when the tag confirms the write.
+    /// - Err(ConnectorError::InvalidParameter) if `new_epc` is empty, an odd number of bytes or
+    ///   longer than 62 bytes; nothing is sent.
+    /// - Err(ConnectorError) as for read_tag_memory and write_tag_memory.
+    fn write_epc(&mut self, new_epc: &[u8], access_password: u32) -> Result<(), ConnectorError>;
 }
 
 impl<S> SyncIO for Connector<S>
 where
-    S: Read + Write,
+    S: Transport,
 {
     type Socket = S;
 
@@ -86,22 +669,29 @@ where
         Ok(())
     }
 
+    /// Hardware, software and manufacturer of the module as one line of text.
     fn get_module_info(&mut self) -> Result<String, ConnectorError> {
-        self.send_packet(Command::HardwareVersion)?;
-        let hardware = self.single_read_from_serial();
-        self.send_packet(Command::SoftwareVersion)?;
-        let software = self.single_read_from_serial();
-        self.send_packet(Command::Manufacturer)?;
-        let manufacture = self.single_read_from_serial();
-
-        let out = format!(
-            "Hardware: {} - Software: {} - Manufacturer: {}",
-            clear_non_ascii(hardware?.unwrap().to_string().as_str()),
-            clear_non_ascii(software?.unwrap().to_string().as_str()),
-            clear_non_ascii(manufacture?.unwrap().to_string().as_str())
-        );
+        Ok(self.get_module_info_struct()?.to_string())
+    }
+
+    /// Query the hardware version, software version and manufacturer of the module.
+    ///
+    /// To tell readers apart see `PortInfo::serial_number`.
+    ///
+    /// Returns
+    /// - Ok(ModuleInfo) with every field stripped of non-ASCII and control characters,
+    ///   a field is empty when its query gets no response.
+    /// - Other ConnectorError variants on I/O failure or timeout.
+    fn get_module_info_struct(&mut self) -> Result<ModuleInfo, ConnectorError> {
+        let hardware = self.send_and_receive(Command::HardwareVersion)?;
+        let software = self.send_and_receive(Command::SoftwareVersion)?;
+        let manufacturer = self.send_and_receive(Command::Manufacturer)?;
 
-        Ok(out)
+        Ok(ModuleInfo {
+            hardware: Connector::<S>::parse_module_info(hardware),
+            software: Connector::<S>::parse_module_info(software),
+            manufacturer: Connector::<S>::parse_module_info(manufacturer),
+        })
     }
 
     /// Builds and sends the command
@@ -115,7 +705,12 @@ where
         debug!("[TX] {out} - [{command}]");
 
         self.port.write_all(&frame)?;
+        self.record_tx(&frame);
+        self.emit(|| ReaderEvent::FrameSent(frame.clone()));
         self.port.flush()?;
+        if let Command::MultiplePollingInstruction(_) = command {
+            self.stop_on_drop = Some(Self::write_stop);
+        }
         Ok(())
     }
 
@@ -124,87 +719,51 @@ where
         Ok(out.unwrap_or(vec![]).pop())
     }
 
-    fn read_from_serial(
-        &mut self,
-        num_expected_responses: Option<u32>,
-    ) -> Result<Option<Vec<Packet>>, ConnectorError> {
-        let mut read_buf: [u8; 1024] = [0u8; 1024];
-        let mut rolling: Vec<u8> = Vec::with_capacity(4096);
-
-        let mut output: Vec<Packet> = Vec::new();
-
+    /// Send the command and read its response, retrying as configured with
+    /// `Connector::set_command_retries`.
+    ///
+    /// An execution failure frame from the device is returned as `ConnectorError::Device`.
+    /// A response to another command is returned as `ConnectorError::UnexpectedResponse`.
+    fn send_and_receive(&mut self, command: Command) -> Result<Option<Packet>, ConnectorError> {
+        let mut attempt = 0;
         loop {
-            let raw_data_size = self.port.read(&mut read_buf);
-            debug!("raw_data_size: {:?}", raw_data_size);
-            debug!("rolling: {:?}", rolling);
-            match raw_data_size {
-                Ok(n) if n > 0 => {
-                    rolling.extend_from_slice(&read_buf[..n]);
-
-                    debug!("rolling: {:?}", rolling);
-
-                    // print raw for debug
-                    hexdump_line("[RAW] ", &rolling);
-
-                    if !rolling.contains(&R200_FRAME_HEADER) {
-                        rolling.clear();
-                        continue;
-                    }
-                    if !rolling.contains(&R200_FRAME_END) {
-                        continue;
-                    }
-
-                    let first_frame_index = rolling
-                        .iter()
-                        .position(|&x| x == R200_FRAME_HEADER)
-                        .unwrap();
-                    let last_frame_index =
-                        rolling.iter().position(|&x| x == R200_FRAME_END).unwrap();
-
-                    let chunk = &rolling[first_frame_index..last_frame_index + 1];
-
-                    if chunk.len() > 4
-                        && chunk[0] == R200_FRAME_HEADER
-                        && chunk.last() == Some(&R200_FRAME_END)
-                    {
-                        // Extract type, command, and data
-                        let p = Packet::new(Vec::from(chunk));
-
-                        if p.is_valid() {
-                            debug!("{}", p.debug());
-                            output.push(p);
-                            if output.len() >= num_expected_responses.unwrap_or(100000) as usize {
-                                return Ok(Some(output));
-                            }
-                        } else {
-                            error!("Invalid packet: {:?}", chunk);
-                        }
-                    }
-
-                    rolling.drain(..last_frame_index + 1);
-
-                    if rolling.len() > 8192 {
-                        rolling.drain(..rolling.len() - 4096);
-                    }
-                }
-                Ok(_) => {
-                    // n == 0, nothing
-                    return Ok(None);
+            // Every attempt reads into a fresh buffer, so partial frames left over by
+            // the previous one are discarded
+            self.send_packet(command.clone())?;
+            match self.single_read_from_serial() {
+                Ok(None) | Err(ConnectorError::Timeout) if attempt < self.retries => {
+                    attempt += 1;
+                    warn!(
+                        "No response to [{command}], retry {attempt}/{}",
+                        self.retries
+                    );
+                    // A late answer to this attempt would be taken for the answer to the next
+                    self.port.clear_input()?;
                 }
-                Err(ref e) if e.kind() == io::ErrorKind::TimedOut => {
-                    // timeout: continue and read again
-                    if output.is_empty() {
-                        return Err(ConnectorError::Timeout);
+                Ok(Some(p)) => {
+                    if let Some(code) = p.as_error() {
+                        return Err(ConnectorError::Device(code));
                     }
-                    break;
-                }
-                Err(ref e) => {
-                    error!("Serial read error: {}", e);
-                    return Err(ConnectorError::SerialRead(e.to_string()));
+                    if p.command_code() != command.code() {
+                        // Best effort, the mismatch is the error to report
+                        let _ = self.resync();
+                        return Err(ConnectorError::UnexpectedResponse {
+                            expected: command.code(),
+                            got: p.command_code(),
+                        });
+                    }
+                    return Ok(Some(p));
                 }
+                other => return other,
             }
         }
-        Ok(Some(output))
+    }
+
+    fn read_from_serial(
+        &mut self,
+        num_expected_responses: Option<u32>,
+    ) -> Result<Option<Vec<Packet>>, ConnectorError> {
+        self.read_packets(num_expected_responses, self.route_notifications, |_| None)
     }
 
     /// Get the current regulatory working area configured on the device.
@@ -215,62 +774,220 @@ where
     /// - Err(ConnectorError::NoPacketReceived) if nothing is received.
     /// - Other ConnectorError variants on I/O failure or timeout.
     fn get_working_area(&mut self) -> Result<WorkingArea, ConnectorError> {
-        self.send_packet(Command::GetWorkingArea)?;
-        let p = self.single_read_from_serial()?;
-        if let Some(p) = p {
-            return Connector::<S>::parse_to_working_area(p);
-        }
-        Err(ConnectorError::NoPacketReceived)
+        let code = self.get_working_area_raw()?;
+        WorkingArea::from_code(code).ok_or(ConnectorError::InvalidWorkingArea)
     }
 
-    /// Get the current working RF channel as a frequency in MHz.
+    /// Get the region code of the working area configured on the device, 0x00 to 0x04 for
+    /// the areas of `WorkingArea`.
     ///
-    /// The raw channel index returned by the device is converted to MHz based on
-    /// the configured WorkingArea. Different regions use different spacing and base frequencies.
+    /// Unlike `get_working_area` a code unknown to the library is returned as is, e.g. a
+    /// region added by a newer firmware, and `Connector::working_area` is cleared.
     ///
     /// Returns
-    /// - Ok(f64) with the center frequency in MHz.
-    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
-    /// - Other ConnectorError variants on I/O failure, timeout, or unknown working area.
-    fn get_working_channel(&mut self) -> Result<f64, ConnectorError> {
-        self.send_packet(Command::GetWorkingChannel)?;
-        let p = self.single_read_from_serial()?;
-        if let Some(p) = p {
-            return Ok(self.get_working_area()?.packet_to_64(p));
-        }
-        Err(ConnectorError::NoPacketReceived)
+    /// - Ok(u8) with the region code reported by the device.
+    /// - Err(ConnectorError::InvalidResponse) if the response is empty.
+    /// - Err(ConnectorError::NoPacketReceived) if nothing is received.
+    /// - Other ConnectorError variants on I/O failure or timeout.
+    fn get_working_area_raw(&mut self) -> Result<u8, ConnectorError> {
+        let p = self
+            .send_and_receive(Command::GetWorkingArea)?
+            .ok_or(ConnectorError::NoPacketReceived)?;
+        let code = Connector::<S>::parse_working_area_code(p)?;
+        self.working_area = WorkingArea::from_code(code);
+        Ok(code)
     }
 
-    /// Read the current transmit power reported by the device.
-    ///
-    /// The device returns two bytes that represent the power value scaled by 100.
-    /// This method combines them and returns the value as f64.
+    /// Set the regulatory working area of the device.
     ///
     /// Returns
-    /// - Ok(f64) with the transmit power (device-specific units, typically dBm).
+    /// - Ok(()) when the device acknowledges the setting.
+    /// - Err(ConnectorError::Device) if the device answers with a non-zero status.
     /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
     /// - Other ConnectorError variants on I/O failure or timeout.
-    fn get_transmit_power(&mut self) -> Result<f64, ConnectorError> {
-        self.send_packet(Command::AcquireTransmitPower)?;
-        let p = self.single_read_from_serial()?;
-        if let Some(p) = p {
+    fn set_working_area(&mut self, area: WorkingArea) -> Result<(), ConnectorError> {
+        let p = self.send_and_receive(Command::SetWorkingArea(area))?;
+        Connector::<S>::_set_working_area(p, area)?;
+        self.working_area = Some(area);
+        Ok(())
+    }
+
+    /// Get the current working RF channel as a frequency in MHz.
+    ///
+    /// The raw channel index returned by the device is converted to MHz based on
+    /// the configured WorkingArea. Different regions use different spacing and base frequencies.
+    /// The area is read from the device only the first time, see `Connector::working_area`.
+    ///
+    /// Returns
+    /// - Ok(f64) with the center frequency in MHz.
+    /// - Err(ConnectorError::InvalidWorkingArea) if the region code of the device is unknown,
+    ///   its frequencies cannot be computed: use `get_working_area_raw` and
+    ///   `get_working_channel_index` instead.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    /// - Other ConnectorError variants on I/O failure or timeout.
+    fn get_working_channel(&mut self) -> Result<f64, ConnectorError> {
+        // The area is known before the channel is asked, so the response pairs with it
+        let area = match self.working_area {
+            Some(area) => area,
+            None => self.get_working_area()?,
+        };
+        Ok(area.channel_mhz(self.get_working_channel_index()?))
+    }
+
+    /// Every channel of the working area of the device, as (channel index, centre frequency in
+    /// MHz) pairs in channel order, e.g. for a site survey.
+    ///
+    /// The working area is read from the device, see `WorkingArea::frequency_plan`.
+    ///
+    /// Returns
+    /// - Ok(Vec<(u8, f64)>) with one entry per channel.
+    /// - Err(ConnectorError) as for get_working_area.
+    fn channel_plan(&mut self) -> Result<Vec<(u8, f64)>, ConnectorError> {
+        let plan = self.get_working_area()?.frequency_plan();
+        Ok(plan
+            .channels()
+            .enumerate()
+            .map(|(i, mhz)| (i as u8, mhz))
+            .collect())
+    }
+
+    /// Get the raw channel index of the working RF channel, as set with `set_working_channel`.
+    ///
+    /// No region math is involved, the working area is not read.
+    ///
+    /// Returns
+    /// - Ok(u8) with the channel index reported by the device.
+    /// - Err(ConnectorError::InvalidResponse) if the response is empty.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    /// - Other ConnectorError variants on I/O failure or timeout.
+    fn get_working_channel_index(&mut self) -> Result<u8, ConnectorError> {
+        match self.send_and_receive(Command::GetWorkingChannel)? {
+            Some(p) => Connector::<S>::parse_working_channel_index(p),
+            None => Err(ConnectorError::NoPacketReceived),
+        }
+    }
+
+    /// Set the working RF channel using the raw channel index of the current WorkingArea.
+    ///
+    /// Ineffective while automatic frequency hopping is enabled.
+    ///
+    /// Returns
+    /// - Ok(()) when the device acknowledges the setting.
+    /// - Err(ConnectorError::InvalidParameter) if the index is past the last channel of the
+    ///   WorkingArea, see `WorkingArea::channel_count`. The area is read from the device only
+    ///   the first time, see `Connector::working_area`.
+    /// - Err(ConnectorError::Device) with the status if the device refuses it.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    /// - Other ConnectorError variants as for get_working_area.
+    fn set_working_channel(&mut self, channel_index: u8) -> Result<(), ConnectorError> {
+        if self.frequency_hopping == Some(true) {
+            warn!("Automatic frequency hopping is enabled, the working channel will not stick");
+        }
+        let area = match self.working_area {
+            Some(area) => area,
+            None => self.get_working_area()?,
+        };
+        Connector::<S>::check_channel_index(area, channel_index)?;
+        let p = self.send_and_receive(Command::SetWorkingChannel(channel_index))?;
+        Connector::<S>::_set_working_channel(p, channel_index)
+    }
+
+    /// Set the working RF channel from a frequency in MHz.
+    ///
+    /// The channel index is computed from the WorkingArea of the device, read once and cached.
+    ///
+    /// Returns
+    /// - Ok(()) when the device acknowledges the setting.
+    /// - Err(ConnectorError::InvalidParameter) if the frequency has no channel in the area.
+    /// - Other ConnectorError variants as for set_working_channel.
+    fn set_working_channel_mhz(&mut self, mhz: f64) -> Result<(), ConnectorError> {
+        let area = match self.working_area {
+            Some(area) => area,
+            None => self.get_working_area()?,
+        };
+        let channel_index = area.channel_index(mhz).ok_or_else(|| {
+            ConnectorError::InvalidParameter(format!("{} MHz is not a channel of {:?}", mhz, area))
+        })?;
+        self.set_working_channel(channel_index)
+    }
+
+    /// Read the current transmit power reported by the device.
+    ///
+    /// The device returns two bytes that represent the power in dBm scaled by 100.
+    ///
+    /// Returns
+    /// - Ok(TransmitPower) with the transmit power in dBm, see `TransmitPower::milliwatts`.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    /// - Other ConnectorError variants on I/O failure or timeout.
+    fn get_transmit_power(&mut self) -> Result<TransmitPower, ConnectorError> {
+        let p = self.send_and_receive(Command::AcquireTransmitPower)?;
+        if let Some(p) = p {
             return calculate_transmit_power(p);
         }
         Err(ConnectorError::NoPacketReceived)
     }
 
+    /// Read the current transmit power in dBm.
+    ///
+    /// Returns
+    /// - Ok(f64) with `get_transmit_power().dbm`.
+    /// - Err(ConnectorError) as for get_transmit_power.
+    fn get_transmit_power_dbm(&mut self) -> Result<f64, ConnectorError> {
+        Ok(self.get_transmit_power()?.dbm)
+    }
+
+    /// Range of transmit power the module accepts, as (min, max) in dBm.
+    ///
+    /// The protocol has no command for it: the maximum is scraped from the rating in the
+    /// hardware version string (e.g. "M100 26dBm V1.0"), the minimum is assumed from the R200
+    /// datasheet. Without a rating `DEFAULT_POWER_RANGE` is returned. The range is read once
+    /// and then used by `set_transmission_power` to reject requests the module would clamp.
+    ///
+    /// Returns
+    /// - Ok((f64, f64)) with the minimum and maximum power.
+    /// - Other ConnectorError variants on I/O failure or timeout.
+    fn get_power_range(&mut self) -> Result<(f64, f64), ConnectorError> {
+        if let Some(range) = self.power_range {
+            return Ok(range);
+        }
+        let p = self.send_and_receive(Command::HardwareVersion)?;
+        let range = Connector::<S>::parse_power_range(p);
+        self.power_range = Some(range);
+        Ok(range)
+    }
+
     /// Set the transmitter output power.
     ///
     /// Parameters
-    /// - power: Desired transmit power in device-specific units (typically dBm).
+    /// - power: Desired transmit power in dBm, within `get_power_range`.
     ///
     /// Returns
     /// - Ok(()) when the device acknowledges the setting.
+    /// - Err(ConnectorError::PowerOutOfRange) if `power` is outside of the range of the module,
+    ///   read with `get_power_range` the first time; the power is not sent.
+    /// - Err(ConnectorError::Device) if the device answers with a non-zero status.
     /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
     /// - Other ConnectorError variants on I/O failure or timeout.
     fn set_transmission_power(&mut self, power: f64) -> Result<(), ConnectorError> {
-        self.send_packet(Command::SetTransmissionPower(power))?;
-        Connector::<S>::_set_transmission_power(self.single_read_from_serial()?, power)
+        let range = self.get_power_range()?;
+        Connector::<S>::check_power(range, power)?;
+        let p = self.send_and_receive(Command::SetTransmissionPower(power))?;
+        Connector::<S>::_set_transmission_power(p, power)
+    }
+
+    /// Set the transmitter output power and read back the power actually applied.
+    ///
+    /// The device may clamp the request to the range it supports without reporting it,
+    /// so a warning is logged when the applied power is more than 0.5 off the request.
+    ///
+    /// Returns
+    /// - Ok(f64) with the power reported by the device after the setting.
+    /// - Err(ConnectorError) as for set_transmission_power and get_transmit_power.
+    fn set_transmit_power_checked(&mut self, power: f64) -> Result<f64, ConnectorError> {
+        self.set_transmission_power(power)?;
+        let applied = self.get_transmit_power()?.dbm;
+        Connector::<S>::check_applied_power(power, applied);
+        Ok(applied)
     }
 
     /// Perform a single inventory (poll) and return the list of detected tags.
@@ -283,298 +1000,3305 @@ where
     /// - Err(ConnectorError::Timeout or other) on communication errors.
     fn single_polling_instruction(&mut self) -> Result<Vec<Rfid>, ConnectorError> {
         self.send_packet(Command::SinglePollingInstruction)?;
-        let response = self.read_from_serial(None)?;
+        let response = self.read_packets(None, false, |_| None)?;
         self.parse_rfid_packets(response)
     }
 
-    fn multi_polling_instruction(&mut self) -> Result<Vec<Rfid>, ConnectorError> {
-        self.send_packet(Command::MultiplePollingInstruction(100))?;
-        let response = self.read_from_serial(Some(100))?;
-        self.parse_rfid_packets(response)
+    /// Perform a single inventory like `single_polling_instruction`, pairing every tag with
+    /// the frame it came from.
+    ///
+    /// `Rfid::raw` only holds the tag payload, the frame bytes here run from the 0xAA header
+    /// to the 0xDD end byte, checksum included, to check another decoder against this one.
+    ///
+    /// Returns
+    /// - Ok(Vec<(Rfid, Vec<u8>)>) possibly empty if no tags are present.
+    /// - Err(ConnectorError::Timeout or other) on communication errors.
+    fn single_polling_instruction_raw(&mut self) -> Result<Vec<(Rfid, Vec<u8>)>, ConnectorError> {
+        self.send_packet(Command::SinglePollingInstruction)?;
+        let response = self.read_packets(None, false, |_| None)?;
+        let tags = self.parse_rfid_frames(response)?;
+        Ok(tags
+            .into_iter()
+            .map(|(rfid, p)| (rfid, p.as_bytes().to_vec()))
+            .collect())
     }
 
-    // Start Multi: AA 00 27 00 03 22 FF FF 4A DD
-    fn enable_multiple_polling_instructions(
+    /// Perform a single inventory like `single_polling_instruction`, with the time every
+    /// tag frame was read from the port, see `TimedRfid`.
+    ///
+    /// Returns
+    /// - Ok(Vec<TimedRfid>) possibly empty if no tags are present.
+    /// - Err(ConnectorError::Timeout or other) on communication errors.
+    #[cfg(feature = "std")]
+    fn single_polling_instruction_timed(&mut self) -> Result<Vec<TimedRfid>, ConnectorError> {
+        self.send_packet(Command::SinglePollingInstruction)?;
+        let response = self.read_stamped_packets(None, false, |_| None, |c| c.now())?;
+        self.parse_timed_frames(response)
+    }
+
+    #[cfg(feature = "std")]
+    fn single_polling_instruction_with_timeout(
         &mut self,
-        pool_times: u16,
-    ) -> Result<(), ConnectorError> {
-        self.send_packet(Command::MultiplePollingInstruction(pool_times))
+        deadline: core::time::Duration,
+    ) -> Result<Vec<Rfid>, ConnectorError> {
+        let end = self.now() + deadline;
+        self.send_packet(Command::SinglePollingInstruction)?;
+        let response = self.read_packets(None, false, |c| Some(end.saturating_sub(c.now())))?;
+        self.parse_rfid_packets(response)
     }
 
-    // Stop Multi: AA 00 28 00 00 28 DD
-    fn stop_multiple_polling_instructions(&mut self) -> Result<(), ConnectorError> {
-        self.send_packet(Command::StopMultiplePollingInstruction)?;
-        if let Some(p) = self.single_read_from_serial()? {
-            if matches!(p.command(), Ok(Command::StopMultiplePollingInstruction)) {
-                return Ok(());
-            } else {
-                return Err(ConnectorError::ErrorStopMultiPolling(
-                    "Wrong response from device".into(),
-                ));
+    /// Poll until a tag answers, for a turnstile or a badge reader.
+    ///
+    /// Single polls are repeated until one returns a tag or `timeout` has elapsed since the
+    /// call, the timeout is a deadline across all the polls.
+    ///
+    /// Returns
+    /// - Ok(Some(Rfid)) with the first tag seen.
+    /// - Ok(None) if no tag answered before the deadline.
+    /// - Err(ConnectorError) on communication errors other than a read timeout.
+    #[cfg(feature = "std")]
+    fn wait_for_tag(
+        &mut self,
+        timeout: core::time::Duration,
+    ) -> Result<Option<Rfid>, ConnectorError> {
+        let end = self.now() + timeout;
+        loop {
+            let remaining = end.saturating_sub(self.now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            match self.single_polling_instruction_with_timeout(remaining) {
+                Ok(tags) => {
+                    if let Some(tag) = tags.into_iter().next() {
+                        return Ok(Some(tag));
+                    }
+                }
+                Err(ConnectorError::Timeout) => {}
+                Err(e) => return Err(e),
             }
         }
-        Err(ConnectorError::ErrorStopMultiPolling(
-            "Generic comunication error".into(),
-        ))
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::io::{Read, Write};
-    use std::sync::{Arc, Mutex};
+    fn multi_polling_instruction(&mut self) -> Result<Vec<Rfid>, ConnectorError> {
+        let mut tags = Vec::new();
+        self.multi_polling_with(100, |tag| {
+            tags.push(tag);
+            ControlFlow::Continue(())
+        })?;
+        Ok(tags)
+    }
 
-    // Helper: build a device->PC frame with given command code and data bytes
-    // cmd: command code for the request
-    // param: optional parameter byte (e.g. channel code)
-    // data: response data
-    //
-    fn make_frame(cmd: u8, param: Option<Vec<u8>>, data: &[u8]) -> ResponseType {
-        let mut v = Vec::new();
-        v.push(R200_FRAME_HEADER);
-        v.push(0x01); // frame type: from device to PC (arbitrary for tests)
-        v.push(cmd);
-        let len = data.len() as u16;
-        v.push((len >> 8) as u8);
-        v.push((len & 0xFF) as u8);
-        v.extend_from_slice(data);
-        // checksum: sum of bytes starting at index 1 (type) to last data byte, low 8 bits
-        let sum: u16 = v[1..].iter().map(|&b| b as u16).sum();
-        v.push((sum & 0xFF) as u8);
-        v.push(R200_FRAME_END);
+    /// Run a multiple polling inventory of `max` rounds, calling `on_tag` for each tag as soon
+    /// as its frame is decoded.
+    ///
+    /// Returning `ControlFlow::Break` from `on_tag` stops the inventory on the device and
+    /// discards the frames still in flight. Otherwise the inventory ends when the serial port
+    /// times out without receiving anything.
+    ///
+    /// Returns
+    /// - Ok(()) when the inventory is over or has been stopped by `on_tag`.
+    /// - Err(ConnectorError::Device) if the device reports an error other than "no tag".
+    /// - Other ConnectorError variants on I/O failure.
+    fn multi_polling_with<F>(&mut self, max: u16, mut on_tag: F) -> Result<(), ConnectorError>
+    where
+        F: FnMut(Rfid) -> ControlFlow<()>,
+    {
+        // Dropping the stream, also on early return, stops the inventory on the device
+        for tag in self.inventory_stream(max)? {
+            if on_tag(tag?).is_break() {
+                break;
+            }
+        }
+        Ok(())
+    }
 
-        ResponseType::Ok(MockChat {
-            request: (cmd, param),
-            responses: Ok(v),
+    /// Run a multiple polling inventory of `max` rounds and keep the round statistics.
+    ///
+    /// Same inventory as `multi_polling_instruction`, with `max` rounds and the counters
+    /// needed for a throughput figure per burst.
+    ///
+    /// Returns
+    /// - Ok(InventoryResult) once the port goes quiet and the inventory has been stopped.
+    /// - Err(ConnectorError) as for multi_polling_with.
+    fn multi_polling_detailed(&mut self, max: u16) -> Result<InventoryResult, ConnectorError> {
+        let mut stream = self.inventory_stream(max)?;
+        let reads = stream.by_ref().collect::<Result<Vec<_>, _>>()?;
+        let unique_count = reads
+            .iter()
+            .map(|r| r.epc.as_str())
+            .collect::<BTreeSet<_>>()
+            .len();
+        Ok(InventoryResult {
+            rounds_completed: None,
+            empty_rounds: stream.empty_rounds,
+            unique_count,
+            reads,
         })
     }
 
-    fn make_error_frame(i: io::Error) -> ResponseType {
-        ResponseType::Error(i)
+    /// Run a multiple polling inventory of `max` rounds, sending each tag through `tx` as
+    /// soon as it is decoded.
+    ///
+    /// A full channel blocks the read loop until the receiver catches up, so a long burst
+    /// is never buffered in memory; the reader keeps the tags meanwhile. Dropping the
+    /// receiver stops the inventory on the device.
+    ///
+    /// Returns
+    /// - Ok(()) when the inventory is over or the receiver has been dropped.
+    /// - Err(ConnectorError) as for multi_polling_with.
+    #[cfg(feature = "std")]
+    fn multi_polling_streaming(
+        &mut self,
+        tx: std::sync::mpsc::SyncSender<Rfid>,
+        max: u16,
+    ) -> Result<(), ConnectorError> {
+        self.multi_polling_with(max, |tag| match tx.send(tag) {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(_) => {
+                debug!("Tag receiver dropped, stopping the inventory");
+                ControlFlow::Break(())
+            }
+        })
     }
 
-    enum ResponseType {
-        Ok(MockChat),
-        Error(io::Error),
-        Raw(Vec<u8>),
+    /// Run an inventory for `dwell`, for a reading station where tags pass by.
+    ///
+    /// The inventory is started without a round limit, tags are collected until `dwell` has
+    /// elapsed, port timeouts included, then the inventory is stopped and the frames still in
+    /// flight are discarded. The last read is cut short at the end of the dwell as in
+    /// `single_polling_instruction_with_timeout`. The stop is sent also when the collection
+    /// fails.
+    ///
+    /// Returns
+    /// - Ok(Vec<Rfid>) with every read, duplicates included, possibly empty.
+    /// - Err(ConnectorError::Device) if the device reports an error other than "no tag".
+    /// - Err(ConnectorError::ErrorStopMultiPolling) if the stop is not acknowledged.
+    /// - Other ConnectorError variants on I/O failure.
+    #[cfg(feature = "std")]
+    fn inventory_for(&mut self, dwell: core::time::Duration) -> Result<Vec<Rfid>, ConnectorError> {
+        let end = self.now() + dwell;
+        self.enable_multiple_polling_instructions(u16::MAX)?;
+        // No tag in the field right now, keep listening until the dwell is over
+        let collected = self.collect_inventory(true, |c| Some(end.saturating_sub(c.now())));
+        let stopped = self.stop_multiple_polling_instructions();
+        let tags = collected?;
+        stopped?;
+        Ok(tags)
     }
 
-    #[derive(Default)]
-    struct MockState {
-        writes: Vec<Vec<u8>>, // captured writes
-        // queue of reads to return on successive read() calls
-        chats: Vec<ResponseType>,
+    /// Run a multiple polling inventory of `max` rounds that can be cancelled from another
+    /// thread, e.g. a "stop scan" button.
+    ///
+    /// `cancel` is checked before every read of the port; once it is set the inventory is
+    /// stopped and the frames still in flight are discarded. Otherwise the inventory ends
+    /// when the serial port times out without receiving anything.
+    ///
+    /// Returns
+    /// - Ok(Vec<Rfid>) with the reads gathered until the end or the cancellation.
+    /// - Err(ConnectorError) as for inventory_for.
+    fn multi_polling_cancellable(
+        &mut self,
+        max: u16,
+        cancel: &AtomicBool,
+    ) -> Result<Vec<Rfid>, ConnectorError> {
+        self.enable_multiple_polling_instructions(max)?;
+        let collected = self.collect_inventory(false, |_| {
+            if cancel.load(Ordering::Relaxed) {
+                debug!("Inventory cancelled");
+                return Some(Duration::ZERO);
+            }
+            None
+        });
+        let stopped = self.stop_multiple_polling_instructions();
+        let tags = collected?;
+        stopped?;
+        Ok(tags)
     }
 
-    struct MockSerialPort {
-        state: Arc<Mutex<MockState>>,
+    /// Run a multiple polling inventory of `total_rounds` rounds, split in runs of at most
+    /// `u16::MAX` rounds, the limit of a single start.
+    ///
+    /// The R200 does not report how many rounds it ran, so an inventory the device ends
+    /// early, e.g. when throttling on temperature, cannot be detected: every run is taken
+    /// as complete and the rounds it skipped are not run again.
+    ///
+    /// Returns
+    /// - Ok(Vec<Rfid>) with every read of every run, duplicates included.
+    /// - Err(ConnectorError) as for multi_polling_with.
+    fn inventory_resilient(&mut self, total_rounds: u32) -> Result<Vec<Rfid>, ConnectorError> {
+        let mut tags = Vec::new();
+        let mut remaining = total_rounds;
+        while remaining > 0 {
+            let max = remaining.min(u16::MAX as u32) as u16;
+            let result = self.multi_polling_detailed(max)?;
+            tags.extend(result.reads);
+            remaining -= max as u32;
+        }
+        Ok(tags)
     }
 
-    struct MockChat {
-        request: (u8, Option<Vec<u8>>),
-        responses: io::Result<Vec<u8>>,
+    /// Run a multiple polling inventory of `rounds` rounds and group the tags by EPC.
+    ///
+    /// Returns
+    /// - Ok(Vec<TagObservation>) with one entry per EPC, in the order they were first seen.
+    /// - Err(ConnectorError) as for multi_polling_with.
+    fn inventory_unique(&mut self, rounds: u16) -> Result<Vec<TagObservation>, ConnectorError> {
+        let mut tags = Vec::new();
+        self.multi_polling_with(rounds, |tag| {
+            tags.push(tag);
+            ControlFlow::Continue(())
+        })?;
+        Ok(TagObservation::aggregate(&tags))
     }
 
-    impl MockSerialPort {
-        fn new(chats: Vec<ResponseType>) -> Self {
-            Self {
-                state: Arc::new(Mutex::new(MockState {
-                    writes: vec![],
-                    chats,
-                })),
-            }
-        }
+    /// Run a multiple polling inventory of `max` rounds with Impinj FastID enabled.
+    ///
+    /// FastID tags backscatter their TID with the EPC in the same reply, which is stored in
+    /// `Rfid::tid`. Select mode is on during the inventory, so tags without FastID do not
+    /// answer. The previous Select filter is restored afterwards, with the Select mode last
+    /// set through the connector (off if it was never set).
+    ///
+    /// Returns
+    /// - Ok(Vec<Rfid>) possibly empty if no tags are present.
+    /// - Err(ConnectorError::Device) if the device rejects the Select setup or restore.
+    /// - Err(ConnectorError) as for multi_polling_with.
+    fn inventory_with_tid(&mut self, max: u16) -> Result<Vec<Rfid>, ConnectorError> {
+        let previous = self.get_select()?;
+        let previous_mode = self.select_mode.unwrap_or(false);
+        self.set_select(
+            MemoryBank::Tid,
+            FASTID_SELECT_POINTER,
+            &FASTID_SELECT_MASK,
+            8,
+        )?;
+        self.set_select_mode(true)?;
+        let mut tags = Vec::new();
+        let result = self.multi_polling_with(max, |tag| {
+            tags.push(tag);
+            ControlFlow::Continue(())
+        });
+        let restored = self
+            .set_select(
+                previous.mask_bank,
+                previous.bit_pointer,
+                &previous.mask,
+                previous.mask_bit_len,
+            )
+            .and_then(|_| self.set_select_mode(previous_mode));
+        result?;
+        restored?;
+        Ok(tags)
     }
 
-    impl Read for MockSerialPort {
-        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-            let mut st = self.state.lock().unwrap();
-
-            let writes = st.writes.clone();
+    // Start Multi: AA 00 27 00 03 22 FF FF 4A DD
+    fn enable_multiple_polling_instructions(
+        &mut self,
+        pool_times: u16,
+    ) -> Result<(), ConnectorError> {
+        self.send_packet(Command::MultiplePollingInstruction(pool_times))
+    }
 
-            if st.chats.is_empty() {
-                // simulate timeout when no more data
-                return Err(io::Error::new(io::ErrorKind::TimedOut, "timeout"));
+    // Stop Multi: AA 00 28 00 00 28 DD
+    /// Stop a multiple polling inventory and wait for the device to confirm it.
+    ///
+    /// Tag frames still in flight are read and discarded up to the stop acknowledge, so the
+    /// next command cannot mistake one of them for its response. Safe to call when no
+    /// inventory is running, the device acknowledges the stop anyway.
+    ///
+    /// Returns
+    /// - Ok(usize) with the number of frames discarded before the acknowledge.
+    /// - Err(ConnectorError::ErrorStopMultiPolling) if no acknowledge arrives before the
+    ///   port times out, after the retries set with `Connector::set_command_retries`.
+    /// - Other ConnectorError variants on I/O failure.
+    fn stop_multiple_polling_instructions(&mut self) -> Result<usize, ConnectorError> {
+        let mut read_buf = vec![0u8; self.read_buf_size];
+        let mut drained = 0;
+        for attempt in 0..=self.retries {
+            if attempt > 0 {
+                warn!("No stop confirmation, retry {attempt}/{}", self.retries);
             }
-            let next = st.chats.remove(0);
-
-            match next {
-                ResponseType::Ok(n) => {
-                    if let Some(last_write) = writes.last() {
-                        let request_command = last_write[2];
-
-                        // check del parametro
-                        let parameter_is_valid: bool;
-
-                        if let Some(p) = n.request.1 {
-                            // controllo che sia impostato il valore 1 di lunghezza parametri (posizione 4) e
-                            // che il parametro sia impostato corettamente (posizione 5)
-                            let params = &last_write[5..5 + p.len()];
-                            if last_write[4] == (p.len() as u8) && p == params {
-                                parameter_is_valid = true;
-                            } else {
-                                parameter_is_valid = false;
-                            }
-                        } else {
-                            parameter_is_valid = true
-                        }
-
-                        if n.request.0 == request_command && parameter_is_valid {
-                            match n.responses {
-                                Ok(bytes) => {
-                                    let n = bytes.len().min(buf.len());
-                                    buf[..n].copy_from_slice(&bytes[..n]);
-                                    Ok(n)
-                                }
-                                Err(e) => Err(e),
-                            }
-                        } else {
-                            return Err(io::Error::new(
-                                io::ErrorKind::InvalidInput,
-                                "Sequenza di comandi non prevista",
-                            ));
-                        }
-                    } else {
-                        // nel caso non abbiamo ricevuto nessuno comando di scrittura vuol dire
-                        // che stiamo semplicemente leggendo una sequenza di frame
-                        let bytes = n.responses.unwrap();
-                        let n = bytes.len().min(buf.len());
-                        buf[..n].copy_from_slice(&bytes[..n]);
-                        Ok(n)
+            self.send_packet(Command::StopMultiplePollingInstruction)?;
+            let mut decoder = self.frame_decoder();
+            loop {
+                while let Some(p) = self.take_frame(&mut decoder) {
+                    if matches!(p.command(), Ok(Command::StopMultiplePollingInstruction)) {
+                        self.stop_on_drop = None;
+                        return Ok(drained);
                     }
+                    debug!("Discarding in-flight frame: {}", p.debug());
+                    drained += 1;
                 }
-                ResponseType::Error(e) => return Err(e),
-                ResponseType::Raw(bytes) => {
-                    let n = bytes.len().min(buf.len());
-                    buf[..n].copy_from_slice(&bytes[..n]);
-                    Ok(n)
+                match self.read_port(&mut read_buf) {
+                    Ok(n) if n > 0 => {
+                        decoder.push(&read_buf[..n]);
+                    }
+                    Ok(_) => break,
+                    Err(ConnectorError::Timeout) => break,
+                    Err(e) => return Err(e),
                 }
             }
         }
+        Err(ConnectorError::ErrorStopMultiPolling(
+            "No stop confirmation from device".into(),
+        ))
     }
 
-    impl Write for MockSerialPort {
-        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-            let mut st = self.state.lock().unwrap();
-            st.writes.push(buf.to_vec());
-            Ok(buf.len())
-        }
+    /// Get the Gen2 Query parameters used by the device during inventories.
+    ///
+    /// Returns
+    /// - Ok(QueryParameters) decoded from the device response.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    /// - Other ConnectorError variants on I/O failure or timeout.
+    fn get_query_parameters(&mut self) -> Result<QueryParameters, ConnectorError> {
+        if let Some(p) = self.send_and_receive(Command::GetQueryParameters)? {
+            return Connector::<S>::parse_query_parameters(p);
+        }
+        Err(ConnectorError::NoPacketReceived)
+    }
+
+    /// Set the Gen2 Query parameters used by the device during inventories.
+    ///
+    /// A higher `q` gives more slots per round, which helps with many tags in the field.
+    ///
+    /// Returns
+    /// - Ok(()) when the device acknowledges the setting.
+    /// - Err(ConnectorError::InvalidParameter) if `q` is greater than 15; nothing is sent.
+    /// - Err(ConnectorError::Device) if the device answers with a non-zero status.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    fn set_query_parameters(
+        &mut self,
+        q: u8,
+        session: Session,
+        target: Target,
+        sel: Sel,
+    ) -> Result<(), ConnectorError> {
+        Connector::<S>::check_query_parameters(q)?;
+        let params = QueryParameters {
+            sel,
+            session,
+            target,
+            q,
+        };
+        let p = self.send_and_receive(Command::SetQueryParameters(params))?;
+        Connector::<S>::_set_query_parameters(p, params)
+    }
+
+    /// Load the Query parameters of an inventory preset, see `InventoryMode` for what
+    /// each one sets.
+    ///
+    /// Returns
+    /// - Ok(()) when the device acknowledges the setting.
+    /// - Err(ConnectorError) as for set_query_parameters.
+    fn set_inventory_mode(&mut self, mode: InventoryMode) -> Result<(), ConnectorError> {
+        let params = mode.query_parameters();
+        self.set_query_parameters(params.q, params.session, params.target, params.sel)
+    }
+
+    /// Read the Select parameters currently loaded on the device.
+    ///
+    /// Returns
+    /// - Ok(SelectParameters) decoded from the device response.
+    /// - Err(ConnectorError::InvalidResponse) if the response is too short for its mask.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    fn get_select(&mut self) -> Result<SelectParameters, ConnectorError> {
+        if let Some(p) = self.send_and_receive(Command::GetSelect)? {
+            return Connector::<S>::parse_select_parameters(p);
+        }
+        Err(ConnectorError::NoPacketReceived)
+    }
+
+    /// Set the Select parameters used to single out tags before tag operations.
+    ///
+    /// The mask is bit addressed: `bit_pointer` is the first bit compared in `mask_bank`
+    /// (the EPC starts at bit 0x20 of the EPC bank, after CRC and PC) and only the first
+    /// `mask_bit_len` bits of `mask` are used. Enable it with `set_select_mode`.
+    ///
+    /// Returns
+    /// - Ok(()) when the device acknowledges the setting.
+    /// - Err(ConnectorError::InvalidParameter) if `mask` holds fewer than `mask_bit_len`
+    ///   bits or the mask is longer than 255 bits; nothing is sent.
+    /// - Err(ConnectorError::Device) if the device answers with a non-zero status.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    fn set_select(
+        &mut self,
+        mask_bank: MemoryBank,
+        bit_pointer: u32,
+        mask: &[u8],
+        mask_bit_len: u16,
+    ) -> Result<(), ConnectorError> {
+        Connector::<S>::check_select_mask(mask, mask_bit_len)?;
+        let p = self.send_and_receive(Command::SetSelect {
+            bank: mask_bank,
+            bit_pointer,
+            mask: mask.to_vec(),
+            mask_bit_len: mask_bit_len as u8,
+        })?;
+        Connector::<S>::_set_select(p, mask_bank)
+    }
+
+    /// Set the Select parameters from a `SelectMask`, see `set_select`.
+    ///
+    /// Returns
+    /// - Ok(()) when the device acknowledges the setting.
+    /// - Err(ConnectorError::InvalidParameter) if the mask bytes do not hold exactly
+    ///   `bit_len` bits rounded up to whole bytes, or the mask is longer than 255 bits;
+    ///   nothing is sent.
+    /// - Err(ConnectorError::Device) if the device answers with a non-zero status.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    fn set_select_mask(&mut self, select: &SelectMask) -> Result<(), ConnectorError> {
+        Connector::<S>::check_select_mask_bytes(select)?;
+        self.set_select(
+            select.bank,
+            select.bit_pointer,
+            &select.mask,
+            select.bit_len,
+        )
+    }
+
+    /// Enable or disable the Select filter set with `set_select`.
+    ///
+    /// When enabled the device sends Select before every tag operation, polling
+    /// included, so only matching tags answer.
+    ///
+    /// Returns
+    /// - Ok(()) when the device acknowledges the setting.
+    /// - Err(ConnectorError::Device) if the device answers with a non-zero status.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    fn set_select_mode(&mut self, enabled: bool) -> Result<(), ConnectorError> {
+        let p = self.send_and_receive(Command::SetSelectMode(enabled))?;
+        Connector::<S>::_set_select_mode(p, enabled)?;
+        self.select_mode = Some(enabled);
+        Ok(())
+    }
+
+    /// Enable or disable automatic frequency hopping.
+    ///
+    /// While hopping is enabled the device picks the channel on its own, so
+    /// `set_working_channel` has no lasting effect.
+    ///
+    /// Returns
+    /// - Ok(()) when the device acknowledges the setting.
+    /// - Err(ConnectorError::Device) if the device answers with a non-zero status.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    fn set_auto_frequency_hopping(&mut self, enabled: bool) -> Result<(), ConnectorError> {
+        let p = self.send_and_receive(Command::SetAutoFrequencyHopping(enabled))?;
+        Connector::<S>::_set_auto_frequency_hopping(p, enabled)?;
+        self.frequency_hopping = Some(enabled);
+        Ok(())
+    }
+
+    /// Restrict automatic frequency hopping to a list of channel indexes of the WorkingArea,
+    /// e.g. to keep away from congested sub-bands. An empty list clears it, and the device
+    /// hops over every channel again.
+    ///
+    /// The device cannot report the list, the one set last is kept in
+    /// `Connector::hopping_channels`.
+    ///
+    /// Returns
+    /// - Ok(()) when the device acknowledges the list.
+    /// - Err(ConnectorError::InvalidParameter) if an index is past the last channel of the
+    ///   WorkingArea, read as for set_working_channel, or the list has more than 255 entries.
+    /// - Err(ConnectorError::Device) with the status if the device refuses it.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    /// - Other ConnectorError variants as for get_working_area.
+    fn set_hopping_channels(&mut self, channels: &[u8]) -> Result<(), ConnectorError> {
+        let area = match self.working_area {
+            Some(area) => area,
+            None => self.get_working_area()?,
+        };
+        Connector::<S>::check_hopping_channels(area, channels)?;
+        let p = self.send_and_receive(Command::InsertWorkingChannel(channels.to_vec()))?;
+        Connector::<S>::_set_hopping_channels(p, channels)?;
+        self.hopping_channels = (!channels.is_empty()).then(|| channels.to_vec());
+        Ok(())
+    }
+
+    /// Send any command code with its parameters and return the first response frame.
+    ///
+    /// Length and checksum are computed for you. Error frames (command code 0xFF)
+    /// are returned as they are, so they can be inspected.
+    ///
+    /// Returns
+    /// - Ok(Packet) with the first valid frame received.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    /// - Other ConnectorError variants on I/O failure or timeout.
+    fn send_raw(&mut self, command_code: u8, params: &[u8]) -> Result<Packet, ConnectorError> {
+        self.send_packet(Command::Raw {
+            code: command_code,
+            params: params.to_vec(),
+        })?;
+        self.single_read_from_serial()?
+            .ok_or(ConnectorError::NoPacketReceived)
+    }
+
+    /// Read and discard bytes until the stream is back on a frame boundary, e.g. after a USB
+    /// hiccup cut a frame in half.
+    ///
+    /// Stops when a read ends exactly after a complete frame, when the port times out, or
+    /// once the rolling cap set with `Connector::set_buffer_sizes` has been discarded, so a
+    /// reader still streaming tags cannot keep it going. Everything read, complete frames
+    /// included, is dropped. `send_and_receive` calls it after an `UnexpectedResponse`, since
+    /// the rest of the foreign answer is still queued.
+    ///
+    /// Returns
+    /// - Ok(usize) with the number of bytes discarded.
+    /// - Err(ConnectorError) on I/O failures other than a timeout.
+    fn resync(&mut self) -> Result<usize, ConnectorError> {
+        let mut read_buf = vec![0u8; self.read_buf_size];
+        let mut decoder = self.frame_decoder();
+        let mut discarded = 0;
+        loop {
+            match self.read_port(&mut read_buf) {
+                Ok(0) | Err(ConnectorError::Timeout) => break,
+                Ok(n) => {
+                    discarded += n;
+                    decoder.push(&read_buf[..n]);
+                    let mut frames = 0;
+                    while decoder.next_packet().is_some() {
+                        frames += 1;
+                    }
+                    if frames > 0 && decoder.pending().is_empty() {
+                        break;
+                    }
+                    if discarded >= self.rolling_cap {
+                        warn!("Still no frame boundary after {} bytes", discarded);
+                        break;
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        if discarded > 0 {
+            warn!("Resync discarded {} bytes", discarded);
+        }
+        Ok(discarded)
+    }
+
+    /// Permanently kill the selected tag.
+    ///
+    /// Select the tag first with `set_select`, otherwise any tag in the field may be killed.
+    /// A tag with an all zero kill password cannot be killed.
+    ///
+    /// Returns
+    /// - Ok(()) when the tag confirms the kill.
+    /// - Err(ConnectorError::TagNotFound) if no tag answered. A tag given the wrong kill
+    ///   password does not answer either, so this is also how a wrong password shows up.
+    /// - Err(ConnectorError::TagAccessFailed) for any other device error code.
+    fn kill_tag(&mut self, kill_password: u32) -> Result<(), ConnectorError> {
+        if let Some(p) = self
+            .send_and_receive(Command::KillTag(kill_password))
+            .map_err(Connector::<S>::tag_access_error)?
+        {
+            return Connector::<S>::parse_tag_status(p);
+        }
+        Err(ConnectorError::NoPacketReceived)
+    }
+
+    /// Lock or unlock areas of the selected tag.
+    ///
+    /// Select the tag first with `set_select`. Areas not set in `lock_mask` keep their state.
+    /// Without `access_password` the one set with `Connector::set_access_password` is used.
+    ///
+    /// Returns
+    /// - Ok(()) when the tag confirms the lock.
+    /// - Err(ConnectorError::InvalidParameter) if `lock_mask` does not change any area.
+    /// - Err(ConnectorError::TagNotFound) if no tag answered.
+    /// - Err(ConnectorError::WrongPassword) if the tag refused the access password.
+    /// - Err(ConnectorError::TagWriteProtected) if an area is permalocked.
+    /// - Err(ConnectorError::TagAccessFailed) for any other device error code.
+    fn lock_tag(
+        &mut self,
+        access_password: Option<u32>,
+        lock_mask: LockMask,
+    ) -> Result<(), ConnectorError> {
+        let access_password = access_password.unwrap_or(self.access_password);
+        Connector::<S>::check_lock_mask(&lock_mask)?;
+        if let Some(p) = self
+            .send_and_receive(Command::LockTag {
+                access_password,
+                lock_mask,
+            })
+            .map_err(Connector::<S>::tag_access_error)?
+        {
+            return Connector::<S>::parse_tag_status(p);
+        }
+        Err(ConnectorError::NoPacketReceived)
+    }
+
+    /// Reset the module by putting it to sleep and waking it up again.
+    ///
+    /// The R200 has no dedicated reset command, but sleeping powers the chip down and waking
+    /// it reloads the firmware. Power, channel, frequency hopping, Select and demodulator
+    /// settings go back to their defaults, so the caller has to configure them again.
+    /// Anything left in the serial buffers is discarded while the module restarts.
+    ///
+    /// Returns
+    /// - Ok(()) once the module has been woken up.
+    /// - Err(ConnectorError::Device) if the module refuses to sleep.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    /// - Other ConnectorError variants on I/O failure.
+    fn reset_module(&mut self) -> Result<(), ConnectorError> {
+        self.set_power_mode(PowerMode::Sleep)?;
+        self.set_power_mode(PowerMode::Active)
+    }
+
+    /// Switch the module to a power mode.
+    ///
+    /// - Idle powers the RF part off and keeps every setting, the next tag operation
+    ///   brings the module back to Active by itself.
+    /// - Sleep powers the chip down. Waking it up (Active) reloads the firmware, so power,
+    ///   channel, frequency hopping, Select and demodulator settings go back to their defaults.
+    ///
+    /// Returns
+    /// - Ok(()) when the device acknowledges the mode, or has been woken up from Sleep.
+    /// - Err(ConnectorError::Device) if the device answers with a non-zero status.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    /// - Other ConnectorError variants on I/O failure.
+    fn set_power_mode(&mut self, mode: PowerMode) -> Result<(), ConnectorError> {
+        match mode {
+            PowerMode::Sleep => {
+                let p = self.send_and_receive(Command::ModuleSleep)?;
+                Connector::<S>::_set_power_mode(p, mode)?;
+            }
+            PowerMode::Idle => {
+                let p = self.send_and_receive(Command::IdleMode(true))?;
+                Connector::<S>::_set_power_mode(p, mode)?;
+            }
+            PowerMode::Active if self.power_mode == Some(PowerMode::Sleep) => {
+                self.port.write_all(&[WAKE_UP_BYTE])?;
+                self.port.flush()?;
+                // Drop whatever arrives until the port times out, the firmware reloads meanwhile
+                match self.read_from_serial(None) {
+                    Ok(_) | Err(ConnectorError::Timeout) => {}
+                    Err(e) => return Err(e),
+                }
+                // Settings are back to their defaults after the restart
+                self.frequency_hopping = None;
+                self.working_area = None;
+                self.hopping_channels = None;
+                self.select_mode = None;
+            }
+            PowerMode::Active => {
+                let p = self.send_and_receive(Command::IdleMode(false))?;
+                Connector::<S>::_set_power_mode(p, mode)?;
+            }
+        }
+        self.power_mode = Some(mode);
+        Ok(())
+    }
+
+    /// Start or stop transmitting an unmodulated carrier on the current channel and power,
+    /// for bench measurements such as antenna tuning.
+    ///
+    /// Once started the reader transmits continuously until it is stopped, which may break
+    /// radio regulations and heats the module. Prefer `start_rf_carrier`, which stops it
+    /// when the guard is dropped.
+    ///
+    /// Returns
+    /// - Ok(()) when the device acknowledges the setting.
+    /// - Err(ConnectorError::Device) if the device answers with a non-zero status.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    fn set_rf_carrier(&mut self, on: bool) -> Result<(), ConnectorError> {
+        let p = self.send_and_receive(Command::SetRfCarrier(on))?;
+        Connector::<S>::_set_rf_carrier(p, on)
+    }
+
+    /// Start transmitting an unmodulated carrier, stopped again when the guard is dropped.
+    ///
+    /// Returns
+    /// - Ok(CarrierGuard) once the device acknowledges the carrier.
+    /// - Err(ConnectorError) as for set_rf_carrier.
+    fn start_rf_carrier(&mut self) -> Result<CarrierGuard<'_, S>, ConnectorError> {
+        self.set_rf_carrier(true)?;
+        Ok(CarrierGuard {
+            connector: self,
+            active: true,
+        })
+    }
+
+    /// Configure a GPIO pin (1 to `GPIO_PIN_COUNT`) as output or input.
+    ///
+    /// Returns
+    /// - Ok(()) when the device confirms the configuration.
+    /// - Err(ConnectorError::InvalidParameter) if the pin does not exist.
+    /// - Err(ConnectorError::FailedSetting) if the device reports a failure.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    fn set_gpio_direction(&mut self, pin: u8, output: bool) -> Result<(), ConnectorError> {
+        Connector::<S>::check_gpio_pin(pin)?;
+        let p = self.send_and_receive(Command::SetGpioDirection { pin, output })?;
+        Connector::<S>::_set_gpio(p, pin, "direction")
+    }
+
+    /// Drive a GPIO output pin (1 to `GPIO_PIN_COUNT`) high or low.
+    ///
+    /// The pin must be configured as output with `set_gpio_direction` first.
+    ///
+    /// Returns
+    /// - Ok(()) when the device confirms the level.
+    /// - Err(ConnectorError::InvalidParameter) if the pin does not exist.
+    /// - Err(ConnectorError::FailedSetting) if the device reports a failure.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    fn set_gpio(&mut self, pin: u8, level: bool) -> Result<(), ConnectorError> {
+        Connector::<S>::check_gpio_pin(pin)?;
+        let p = self.send_and_receive(Command::SetGpioLevel { pin, high: level })?;
+        Connector::<S>::_set_gpio(p, pin, "level")
+    }
+
+    /// Read the level of every GPIO pin.
+    ///
+    /// Returns
+    /// - Ok(GpioState) with the level of each pin, inputs and outputs alike.
+    /// - Err(ConnectorError::InvalidResponse) if a response does not match the pin read.
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    fn get_gpio(&mut self) -> Result<GpioState, ConnectorError> {
+        let mut levels = [false; GPIO_PIN_COUNT as usize];
+        for (pin, level) in (1..=GPIO_PIN_COUNT).zip(levels.iter_mut()) {
+            let p = self.send_and_receive(Command::ReadGpioLevel(pin))?;
+            *level = Connector::<S>::parse_gpio_result(p, pin)?;
+        }
+        Ok(GpioState { levels })
+    }
+
+    /// Start a multiple polling inventory of `max` rounds and yield tags as they arrive.
+    ///
+    /// The stream ends when the serial port times out without receiving anything.
+    /// Dropping it stops the inventory on the device and discards the frames still in flight.
+    ///
+    /// Returns
+    /// - Ok(InventoryStream) once the start command has been sent.
+    /// - Err(ConnectorError) if the command cannot be written.
+    fn inventory_stream(&mut self, max: u16) -> Result<InventoryStream<'_, S>, ConnectorError> {
+        self.send_packet(Command::MultiplePollingInstruction(max))?;
+        let decoder = self.frame_decoder();
+        Ok(InventoryStream {
+            connector: self,
+            decoder,
+            finished: false,
+            empty_rounds: 0,
+        })
+    }
+
+    /// Read `word_count` words starting at `word_addr` from a memory bank of the selected tag.
+    ///
+    /// Without `access_password` the one set with `Connector::set_access_password` is used,
+    /// 0 skips the access step on the tag.
+    ///
+    /// Returns
+    /// - Ok(Vec<u8>) with the bytes read (two per word).
+    /// - Err(ConnectorError::InvalidParameter) if `word_count` is above `MAX_READ_WORDS`;
+    ///   nothing is sent.
+    /// - Err(ConnectorError::TagNotFound) if no tag answered (0x09).
+    /// - Err(ConnectorError::WrongPassword) if the tag refused the access password (0x16).
+    /// - Err(ConnectorError::TagAccessFailed) with the device error code when the tag
+    ///   replied with a Gen2 error (0xA0 | code).
+    /// - Err(ConnectorError::NoPacketReceived) if no response is obtained.
+    fn read_tag_memory(
+        &mut self,
+        bank: MemoryBank,
+        word_addr: u16,
+        word_count: u16,
+        access_password: Option<u32>,
+    ) -> Result<Vec<u8>, ConnectorError> {
+        Connector::<S>::check_words_to_read(word_count)?;
+        let access_password = access_password.unwrap_or(self.access_password);
+        if let Some(p) = self
+            .send_and_receive(Command::ReadTagMemory {
+                bank,
+                word_addr,
+                word_count,
+                access_password,
+            })
+            .map_err(Connector::<S>::tag_access_error)?
+        {
+            return Connector::<S>::parse_tag_memory(p);
+        }
+        Err(ConnectorError::NoPacketReceived)
+    }
+
+    /// Read a password protected tag: select it with `select`, then read `words` words starting
+    /// at `addr` of `bank` with `access_password`.
+    ///
+    /// The read command of the firmware sends Access before Read in the same device
+    /// transaction, so the only gap is between the Select setup and the read. Select mode is
+    /// disabled again afterwards, the Select filter is left as `select`.
+    ///
+    /// Returns
+    /// - Ok(Vec<u8>) with the bytes read (two per word).
+    /// - Err(ConnectorError::InvalidParameter) if the mask is rejected by `set_select_mask`.
+    /// - Err(ConnectorError::Device) if the device rejects the Select setup.
+    /// - Err(ConnectorError::TagAccessFailed) as for read_tag_memory.
+    fn access_and_read(
+        &mut self,
+        select: SelectMask,
+        access_password: u32,
+        bank: MemoryBank,
+        addr: u16,
+        words: u16,
+    ) -> Result<Vec<u8>, ConnectorError> {
+        self.set_select_mask(&select)?;
+        self.set_select_mode(true)?;
+        let result = self.read_tag_memory(bank, addr, words, Some(access_password));
+        let restored = self.set_select_mode(false);
+        let data = result?;
+        restored?;
+        Ok(data)
+    }
+
+    /// Write `words` starting at `word_addr` into a memory bank of the selected tag.
+    ///
+    /// At most 32 words can be written with a single command. Without `access_password`
+    /// the one set with `Connector::set_access_password` is used, 0 skips the access step.
+    ///
+    /// Returns
+    /// - Ok(()) when the tag confirms the write.
+    /// - Err(ConnectorError::InvalidParameter) if `words` is empty or too long; nothing is sent.
+    /// - Err(ConnectorError::TagNotFound) if no tag answered.
+    /// - Err(ConnectorError::TagWriteProtected) if the bank is locked.
+    /// - Err(ConnectorError::TagAccessFailed) for any other device error code.
+    fn write_tag_memory(
+        &mut self,
+        bank: MemoryBank,
+        word_addr: u16,
+        words: &[u16],
+        access_password: Option<u32>,
+    ) -> Result<(), ConnectorError> {
+        let access_password = access_password.unwrap_or(self.access_password);
+        Connector::<S>::check_words_to_write(words)?;
+        if let Some(p) = self
+            .send_and_receive(Command::WriteTagMemory {
+                bank,
+                word_addr,
+                words: words.to_vec(),
+                access_password,
+            })
+            .map_err(Connector::<S>::tag_access_error)?
+        {
+            return Connector::<S>::parse_tag_status(p);
+        }
+        Err(ConnectorError::NoPacketReceived)
+    }
+
+    /// Program a new EPC into the selected tag, for tag commissioning.
+    ///
+    /// The PC word is read first and written back with the EPC length of `new_epc`, its other
+    /// fields unchanged, followed by the EPC from word 2 of the EPC bank.
+    ///
+    /// Returns
+    /// - Ok(()) when the tag confirms the write.
+    /// - Err(ConnectorError::InvalidParameter) if `new_epc` is empty, an odd number of bytes or
+    ///   longer than 62 bytes; nothing is sent.
+    /// - Err(ConnectorError) as for read_tag_memory and write_tag_memory.
+    fn write_epc(&mut self, new_epc: &[u8], access_password: u32) -> Result<(), ConnectorError> {
+        Connector::<S>::check_epc_to_write(new_epc)?;
+        let pc = self.read_tag_memory(MemoryBank::Epc, 1, 1, Some(access_password))?;
+        let words = Connector::<S>::epc_words(&pc, new_epc)?;
+        self.write_tag_memory(MemoryBank::Epc, 1, &words, Some(access_password))
+    }
+}
+
+impl<S> Connector<S>
+where
+    S: Transport,
+{
+    /// Collect the tags of a running inventory until the time left given by `remaining`,
+    /// checked before every read and bounding it, is zero, or nothing more is coming: the port
+    /// is closed, or times out unless `wait_on_timeout`. The inventory is left running.
+    fn collect_inventory(
+        &mut self,
+        wait_on_timeout: bool,
+        mut remaining: impl FnMut(&Self) -> Option<Duration>,
+    ) -> Result<Vec<Rfid>, ConnectorError> {
+        let mut read_buf = vec![0u8; self.read_buf_size];
+        let mut decoder = self.frame_decoder();
+        let mut tags = Vec::new();
+        loop {
+            while let Some(p) = self.take_frame(&mut decoder) {
+                match p.as_error() {
+                    // No tag answered during this round
+                    Some(0x15) => continue,
+                    Some(code) => return Err(ConnectorError::Device(code)),
+                    None => {}
+                }
+                match self.parse_tag(p.get_data()) {
+                    Some(rfid) if !self.rssi_accepted(&rfid) => {}
+                    Some(rfid) => tags.push(rfid),
+                    None => warn!("Skipping malformed packet during inventory: {}", p.debug()),
+                }
+            }
+            let left = remaining(self);
+            if left.is_some_and(|left| left.is_zero()) {
+                return Ok(tags);
+            }
+            match self.read_port_within(&mut read_buf, left) {
+                // The port is closed, nothing more is coming
+                Ok(0) => return Ok(tags),
+                Ok(n) => {
+                    decoder.push(&read_buf[..n]);
+                    hexdump_line("[RAW] ", decoder.pending());
+                }
+                Err(ConnectorError::Timeout) if wait_on_timeout => {}
+                Err(ConnectorError::Timeout) => return Ok(tags),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Write the stop of a multiple polling inventory, ignoring errors, for `Drop`
+    fn write_stop(port: &mut S) {
+        let frame = Frame::new(&Command::StopMultiplePollingInstruction).to_bytes();
+        if port.write_all(&frame).is_ok() {
+            let _ = port.flush();
+        }
+    }
+
+    /// Read from the port, reporting the bytes read or the timeout to the event sink
+    fn read_port(&mut self, buf: &mut [u8]) -> Result<usize, ConnectorError> {
+        self.read_port_within(buf, None)
+    }
+
+    /// `read_port` giving up after `timeout`, when the port can bound a read
+    fn read_port_within(
+        &mut self,
+        buf: &mut [u8],
+        timeout: Option<Duration>,
+    ) -> Result<usize, ConnectorError> {
+        let result = match timeout {
+            None => self.port.read(buf),
+            Some(timeout) => self.port.read_with_timeout(buf, timeout),
+        };
+        match result {
+            Ok(n) if n > 0 => {
+                self.record_rx(&buf[..n]);
+                self.emit(|| ReaderEvent::RawBytes(buf[..n].to_vec()));
+            }
+            Err(ConnectorError::Timeout) => self.emit(|| ReaderEvent::Timeout),
+            _ => {}
+        }
+        result
+    }
+
+    /// Read frames until `num_expected_responses` are decoded, the port times out, or the
+    /// time left given by `remaining` is zero; every read is bounded by it. With
+    /// `route_notifications` notifications go to the event sink and do not count as responses.
+    fn read_packets(
+        &mut self,
+        num_expected_responses: Option<u32>,
+        route_notifications: bool,
+        remaining: impl FnMut(&Self) -> Option<Duration>,
+    ) -> Result<Option<Vec<Packet>>, ConnectorError> {
+        let output = self.read_stamped_packets(
+            num_expected_responses,
+            route_notifications,
+            remaining,
+            |_| (),
+        )?;
+        Ok(output.map(|ps| ps.into_iter().map(|(p, ())| p).collect()))
+    }
+
+    /// `read_packets`, pairing every frame with the value of `stamp` taken when the read
+    /// returning it completes: frames of the same read share it
+    fn read_stamped_packets<T: Clone>(
+        &mut self,
+        num_expected_responses: Option<u32>,
+        route_notifications: bool,
+        mut remaining: impl FnMut(&Self) -> Option<Duration>,
+        mut stamp: impl FnMut(&Self) -> T,
+    ) -> Result<Option<Vec<(Packet, T)>>, ConnectorError> {
+        let mut read_buf = vec![0u8; self.read_buf_size];
+        let mut decoder = self.frame_decoder();
+
+        let mut output: Vec<(Packet, T)> = Vec::new();
+        let mut empty_reads = 0;
+
+        loop {
+            let left = remaining(self);
+            if left.is_some_and(|left| left.is_zero()) {
+                break;
+            }
+            let raw_data_size = self.read_port_within(&mut read_buf, left);
+            debug!("raw_data_size: {:?}", raw_data_size);
+            match raw_data_size {
+                Ok(n) if n > 0 => {
+                    empty_reads = 0;
+                    let read_at = stamp(self);
+                    decoder.push(&read_buf[..n]);
+
+                    // print raw for debug
+                    hexdump_line("[RAW] ", decoder.pending());
+
+                    // Complete frames are consumed, a partial trailing frame stays in the
+                    // buffer until the next read completes it
+                    while let Some(p) = self.take_frame(&mut decoder) {
+                        debug!("{}", p.debug());
+                        if route_notifications && p.is_notification() {
+                            self.emit(|| ReaderEvent::Notification(p));
+                            continue;
+                        }
+                        output.push((p, read_at.clone()));
+                        if output.len() >= num_expected_responses.unwrap_or(100000) as usize {
+                            return Ok(Some(output));
+                        }
+                    }
+                }
+                Ok(_) => {
+                    // n == 0: keep waiting unless it happens repeatedly
+                    empty_reads += 1;
+                    if empty_reads < MAX_EMPTY_READS {
+                        continue;
+                    }
+                    if output.is_empty() {
+                        return Ok(None);
+                    }
+                    break;
+                }
+                Err(ConnectorError::Timeout) => {
+                    // timeout: continue and read again
+                    if output.is_empty() {
+                        return Err(ConnectorError::Timeout);
+                    }
+                    break;
+                }
+                Err(e) => {
+                    error!("Serial read error: {}", e);
+                    return Err(e);
+                }
+            }
+        }
+        Ok(Some(output))
+    }
+}
+
+/// Tags read by a running multiple polling inventory, see [`SyncIO::inventory_stream`].
+///
+/// Every notification is yielded as it is received, duplicates included. The stream borrows
+/// the connector, so no other command can be sent until it is dropped, which stops the
+/// inventory:
+///
+/// ```no_run
+/// # use r200_uhf::connector::{Connector, ConnectorError, IoPort, sync::SyncIO};
+/// # fn run(connector: &mut Connector<IoPort<std::fs::File>>) -> Result<(), ConnectorError> {
+/// let mut stream = connector.inventory_stream(100)?;
+/// for tag in &mut stream {
+///     println!("{}", tag?);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct InventoryStream<'a, S>
+where
+    S: Transport,
+{
+    connector: &'a mut Connector<S>,
+    decoder: FrameDecoder,
+    finished: bool,
+    empty_rounds: u16,
+}
+
+impl<S> InventoryStream<'_, S>
+where
+    S: Transport,
+{
+    /// Read the rest of the inventory and group the tags by EPC, then stop it.
+    ///
+    /// Tags already taken from the stream are not included.
+    ///
+    /// Returns
+    /// - Ok(Vec<TagObservation>) with one entry per EPC, in the order they were first seen.
+    /// - Err(ConnectorError) on the first error of the stream.
+    pub fn into_unique(mut self) -> Result<Vec<TagObservation>, ConnectorError> {
+        let reads = self.by_ref().collect::<Result<Vec<_>, _>>()?;
+        Ok(TagObservation::aggregate(&reads))
+    }
+
+    fn next_packet(&mut self) -> Option<Packet> {
+        self.connector.take_frame(&mut self.decoder)
+    }
+
+    /// Read once from the port into the buffer, Ok(false) when nothing more is coming
+    fn fill(&mut self) -> Result<bool, ConnectorError> {
+        let mut read_buf = vec![0u8; self.connector.read_buf_size];
+        match self.connector.read_port(&mut read_buf) {
+            Ok(0) => Ok(false),
+            Ok(n) => {
+                self.decoder.push(&read_buf[..n]);
+                hexdump_line("[RAW] ", self.decoder.pending());
+                Ok(true)
+            }
+            Err(ConnectorError::Timeout) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl<S> Iterator for InventoryStream<'_, S>
+where
+    S: Transport,
+{
+    type Item = Result<Rfid, ConnectorError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.finished {
+            let Some(p) = self.next_packet() else {
+                match self.fill() {
+                    Ok(true) => continue,
+                    Ok(false) => self.finished = true,
+                    Err(e) => {
+                        self.finished = true;
+                        return Some(Err(e));
+                    }
+                }
+                continue;
+            };
+            match p.as_error() {
+                // No tag answered during this round
+                Some(0x15) => {
+                    self.empty_rounds = self.empty_rounds.saturating_add(1);
+                    continue;
+                }
+                Some(code) => return Some(Err(ConnectorError::Device(code))),
+                None => {}
+            }
+            match self.connector.parse_tag(p.get_data()) {
+                Some(rfid) if !self.connector.rssi_accepted(&rfid) => {}
+                Some(rfid) => return Some(Ok(rfid)),
+                None => warn!("Skipping malformed packet during inventory: {}", p.debug()),
+            }
+        }
+        None
+    }
+}
+
+impl<S> Drop for InventoryStream<'_, S>
+where
+    S: Transport,
+{
+    fn drop(&mut self) {
+        // Discards the tags still in flight up to the stop acknowledge
+        if let Err(e) = self.connector.stop_multiple_polling_instructions() {
+            warn!("Unable to stop multiple polling: {e}");
+        }
+    }
+}
+
+/// Continuous carrier started by `SyncIO::start_rf_carrier`, stopped on drop.
+///
+/// Use `stop` to find out whether stopping it succeeded.
+pub struct CarrierGuard<'a, S>
+where
+    S: Transport,
+{
+    connector: &'a mut Connector<S>,
+    active: bool,
+}
+
+impl<S> CarrierGuard<'_, S>
+where
+    S: Transport,
+{
+    /// Stop the carrier now
+    pub fn stop(mut self) -> Result<(), ConnectorError> {
+        self.active = false;
+        self.connector.set_rf_carrier(false)
+    }
+}
+
+impl<S> Drop for CarrierGuard<'_, S>
+where
+    S: Transport,
+{
+    fn drop(&mut self) {
+        if !self.active {
+            return;
+        }
+        if let Err(e) = self.connector.set_rf_carrier(false) {
+            error!("Unable to stop the continuous carrier, the reader may still transmit: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connector::transport::io_read;
+    use crate::connector::{
+        Clock, IoPort, LockAction, MAX_READ_WORDS, MIN_READ_BUF_SIZE, MIN_ROLLING_CAP,
+        clear_non_ascii,
+    };
+    use crate::frame::{R200_FRAME_END, R200_FRAME_HEADER};
+    use std::io::{self, Read, Write};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    // Helper: build a device->PC frame with given command code and data bytes
+    // cmd: command code for the request
+    // param: optional parameter byte (e.g. channel code)
+    // data: response data
+    //
+    fn make_frame(cmd: u8, param: Option<Vec<u8>>, data: &[u8]) -> ResponseType {
+        make_response(cmd, param, cmd, data)
+    }
+
+    // Helper: answer to the hardware version query, e.g. with a power rating
+    fn make_hardware_version(version: &[u8]) -> ResponseType {
+        make_frame(0x03, Some(vec![0x00]), &[&[0x00][..], version].concat())
+    }
+
+    // Helper: device answers the request `cmd` with an execution failure frame (code 0xFF)
+    fn make_device_error(cmd: u8, param: Option<Vec<u8>>, data: &[u8]) -> ResponseType {
+        make_response(cmd, param, 0xFF, data)
+    }
+
+    fn make_response(
+        cmd: u8,
+        param: Option<Vec<u8>>,
+        response_cmd: u8,
+        data: &[u8],
+    ) -> ResponseType {
+        ResponseType::Ok(MockChat {
+            request: (cmd, param),
+            responses: Ok(frame_bytes(response_cmd, data)),
+        })
+    }
+
+    // Helper: bytes of a device->PC frame, for reads not tied to a request
+    fn frame_bytes(response_cmd: u8, data: &[u8]) -> Vec<u8> {
+        let mut v = Vec::new();
+        v.push(R200_FRAME_HEADER);
+        v.push(0x01); // frame type: from device to PC (arbitrary for tests)
+        v.push(response_cmd);
+        let len = data.len() as u16;
+        v.push((len >> 8) as u8);
+        v.push((len & 0xFF) as u8);
+        v.extend_from_slice(data);
+        // checksum: sum of bytes starting at index 1 (type) to last data byte, low 8 bits
+        let sum: u16 = v[1..].iter().map(|&b| b as u16).sum();
+        v.push((sum & 0xFF) as u8);
+        v.push(R200_FRAME_END);
+        v
+    }
+
+    // Helper: tag payload with a 96 bit EPC of 0xE2 bytes ending with `epc_last`
+    fn tag_payload(rssi: u8, epc_last: u8) -> Vec<u8> {
+        let mut data = vec![rssi, 0x30, 0x00];
+        data.extend([0xE2; 11]);
+        data.extend([epc_last, 0x12, 0x34]);
+        data
+    }
+
+    fn make_error_frame(i: io::Error) -> ResponseType {
+        ResponseType::Error(i)
+    }
+
+    enum ResponseType {
+        Ok(MockChat),
+        Error(io::Error),
+        Raw(Vec<u8>),
+        // Port silent for the given time, then timed out
+        Silence(Duration),
+    }
+
+    #[derive(Default)]
+    struct MockState {
+        writes: Vec<Vec<u8>>, // captured writes
+        clears: usize,        // calls to clear_input
+        // queue of reads to return on successive read() calls
+        chats: Vec<ResponseType>,
+    }
+
+    struct MockSerialPort {
+        state: Arc<Mutex<MockState>>,
+    }
+
+    struct MockChat {
+        request: (u8, Option<Vec<u8>>),
+        responses: io::Result<Vec<u8>>,
+    }
+
+    impl MockSerialPort {
+        fn new(chats: Vec<ResponseType>) -> Self {
+            Self {
+                state: Arc::new(Mutex::new(MockState {
+                    writes: vec![],
+                    clears: 0,
+                    chats,
+                })),
+            }
+        }
+    }
+
+    impl Read for MockSerialPort {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let mut st = self.state.lock().unwrap();
+
+            let writes = st.writes.clone();
+
+            if st.chats.is_empty() {
+                // simulate timeout when no more data
+                return Err(io::Error::new(io::ErrorKind::TimedOut, "timeout"));
+            }
+            let next = st.chats.remove(0);
+
+            match next {
+                ResponseType::Ok(n) => {
+                    if let Some(last_write) = writes.last() {
+                        let request_command = last_write[2];
+
+                        // check del parametro
+                        let parameter_is_valid: bool;
+
+                        if let Some(p) = n.request.1 {
+                            // controllo che sia impostato il valore 1 di lunghezza parametri (posizione 4) e
+                            // che il parametro sia impostato corettamente (posizione 5)
+                            let params = &last_write[5..5 + p.len()];
+                            parameter_is_valid = last_write[4] == (p.len() as u8) && p == params;
+                        } else {
+                            parameter_is_valid = true
+                        }
+
+                        if n.request.0 == request_command && parameter_is_valid {
+                            match n.responses {
+                                Ok(bytes) => {
+                                    let n = bytes.len().min(buf.len());
+                                    buf[..n].copy_from_slice(&bytes[..n]);
+                                    Ok(n)
+                                }
+                                Err(e) => Err(e),
+                            }
+                        } else {
+                            Err(io::Error::new(
+                                io::ErrorKind::InvalidInput,
+                                "Sequenza di comandi non prevista",
+                            ))
+                        }
+                    } else {
+                        // nel caso non abbiamo ricevuto nessuno comando di scrittura vuol dire
+                        // che stiamo semplicemente leggendo una sequenza di frame
+                        let bytes = n.responses.unwrap();
+                        let n = bytes.len().min(buf.len());
+                        buf[..n].copy_from_slice(&bytes[..n]);
+                        Ok(n)
+                    }
+                }
+                ResponseType::Error(e) => Err(e),
+                ResponseType::Raw(bytes) => {
+                    let n = bytes.len().min(buf.len());
+                    buf[..n].copy_from_slice(&bytes[..n]);
+                    Ok(n)
+                }
+                ResponseType::Silence(d) => {
+                    drop(st);
+                    std::thread::sleep(d);
+                    Err(io::Error::new(io::ErrorKind::TimedOut, "timeout"))
+                }
+            }
+        }
+    }
+
+    impl Write for MockSerialPort {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let mut st = self.state.lock().unwrap();
+            st.writes.push(buf.to_vec());
+            Ok(buf.len())
+        }
         fn flush(&mut self) -> io::Result<()> {
             Ok(())
         }
     }
 
-    // ----- Tests -----
+    impl Transport for MockSerialPort {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, ConnectorError> {
+            io_read(self, buf)
+        }
+
+        fn write_all(&mut self, buf: &[u8]) -> Result<(), ConnectorError> {
+            Ok(Write::write_all(self, buf)?)
+        }
+
+        fn clear_input(&mut self) -> Result<(), ConnectorError> {
+            self.state.lock().unwrap().clears += 1;
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), ConnectorError> {
+            Ok(Write::flush(self)?)
+        }
+    }
+
+    // ----- Tests -----
+
+    #[test]
+    fn test_get_module_info() {
+        let hw = make_frame(0x03, Some(vec![0x00]), b"HW1.0");
+        let sw = make_frame(0x03, Some(vec![0x01]), b"SW2.0");
+        let mf = make_frame(0x03, Some(vec![0x02]), b"ACME");
+        let mock = MockSerialPort::new(vec![hw, sw, mf]);
+        let mut connector = Connector::new(mock);
+
+        let info = connector.get_module_info().unwrap();
+        assert!(info.contains("Hardware: HW1.0"));
+        assert!(info.contains("Software: SW2.0"));
+        assert!(info.contains("Manufacturer: ACME"));
+    }
+
+    #[test]
+    fn test_get_module_info_struct() {
+        let hw = make_frame(0x03, Some(vec![0x00]), b"\x00M100 26dBm V1.0");
+        let sw = make_frame(0x03, Some(vec![0x01]), b"V1.0.7\0");
+        let mf = make_frame(0x03, Some(vec![0x02]), b"MagicRF");
+        let mock = MockSerialPort::new(vec![hw, sw, mf]);
+        let mut connector = Connector::new(mock);
+
+        let info = connector.get_module_info_struct().unwrap();
+        assert_eq!(
+            info,
+            ModuleInfo {
+                hardware: "M100 26dBm V1.0".into(),
+                software: "V1.0.7".into(),
+                manufacturer: "MagicRF".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_channel_plan() {
+        let area = make_frame(0x08, None, &[0x03]);
+        let mut connector = Connector::new(MockSerialPort::new(vec![area]));
+        let plan = connector.channel_plan().unwrap();
+        assert_eq!(plan.len(), 15);
+        assert_eq!(plan[0], (0, 865.1));
+        assert_eq!(plan[14].0, 14);
+        assert!((plan[14].1 - 867.9).abs() < 1e-9);
+        assert_eq!(connector.working_area(), Some(WorkingArea::EU));
+    }
+
+    #[test]
+    fn test_get_working_area_mapping() {
+        for (code, expected) in [
+            (0, WorkingArea::China900Mhz),
+            (1, WorkingArea::China800Mhz),
+            (2, WorkingArea::US),
+            (3, WorkingArea::EU),
+            (4, WorkingArea::Korea),
+        ] {
+            let frame = make_frame(0x08, None, &[code]);
+            let mock = MockSerialPort::new(vec![frame]);
+            let mut connector = Connector::new(mock);
+            let area = connector.get_working_area().unwrap();
+            // Compare by variant name via debug
+            assert_eq!(format!("{:?}", area), format!("{:?}", expected));
+        }
+    }
+
+    #[test]
+    fn test_get_working_area_unknown_code() {
+        let eu = make_frame(0x08, None, &[0x03]);
+        let unknown = || make_frame(0x08, None, &[0x07]);
+        let mock = MockSerialPort::new(vec![eu, unknown(), unknown()]);
+        let mut connector = Connector::new(mock);
+        assert_eq!(connector.get_working_area().unwrap(), WorkingArea::EU);
+        assert!(matches!(
+            connector.get_working_area(),
+            Err(ConnectorError::InvalidWorkingArea)
+        ));
+        // The EU channels no longer apply
+        assert_eq!(connector.working_area(), None);
+        assert_eq!(connector.get_working_area_raw().unwrap(), 0x07);
+    }
+
+    #[test]
+    fn test_set_working_area_ack() {
+        let frame = make_frame(0x07, Some(vec![0x03]), &[0x00]);
+        let mock = MockSerialPort::new(vec![frame]);
+        let mut connector = Connector::new(mock);
+        connector.set_working_area(WorkingArea::EU).unwrap();
+    }
+
+    #[test]
+    fn test_set_working_area_rejected() {
+        let frame = make_frame(0x07, Some(vec![0x02]), &[0x01]);
+        let mock = MockSerialPort::new(vec![frame]);
+        let mut connector = Connector::new(mock);
+        let err = connector.set_working_area(WorkingArea::US).unwrap_err();
+        assert!(matches!(err, ConnectorError::Device(0x01)));
+        assert!(err.to_string().contains("0x01"));
+    }
+
+    #[test]
+    fn test_get_working_channel_uses_area() {
+        // Channel index 4 -> depends on area. We'll test EU mapping: 0.2 MHz step + 865.1
+        // The area (code 3, EU) is read first, then the channel index
+        let area = make_frame(0x08, None, &[3]);
+        let chan = make_frame(0xAA, None, &[4]);
+        let mock = MockSerialPort::new(vec![area, chan]);
+        let mut connector = Connector::new(mock);
+        let freq = connector.get_working_channel().unwrap();
+        assert!((freq - (4.0 * 0.2 + 865.1)).abs() < 1e-6);
+        assert_eq!(connector.working_area(), Some(WorkingArea::EU));
+    }
+
+    #[test]
+    fn test_get_working_channel_single_round_trip_with_known_area() {
+        let mock = MockSerialPort::new(vec![
+            make_frame(0x07, Some(vec![0x02]), &[0x00]),
+            make_frame(0xAA, None, &[4]),
+        ]);
+        let state = mock.state.clone();
+        let mut connector = Connector::new(mock);
+        connector.set_working_area(WorkingArea::US).unwrap();
+        let before = state.lock().unwrap().writes.len();
+        let freq = connector.get_working_channel().unwrap();
+        assert!((freq - (4.0 * 0.5 + 902.25)).abs() < 1e-6);
+        let writes = &state.lock().unwrap().writes;
+        assert_eq!(writes.len(), before + 1);
+        assert_eq!(
+            writes.last().unwrap(),
+            &vec![0xAA, 0x00, 0xAA, 0x00, 0x00, 0xAA, 0xDD]
+        );
+    }
+
+    #[test]
+    fn test_get_working_channel_index_skips_area() {
+        let mock = MockSerialPort::new(vec![make_frame(0xAA, None, &[7])]);
+        let state = mock.state.clone();
+        let mut connector = Connector::new(mock);
+        assert_eq!(connector.get_working_channel_index().unwrap(), 7);
+        assert_eq!(state.lock().unwrap().writes.len(), 1);
+        assert_eq!(connector.working_area(), None);
+
+        let mock = MockSerialPort::new(vec![make_frame(0xAA, None, &[])]);
+        let mut connector = Connector::new(mock);
+        assert!(matches!(
+            connector.get_working_channel_index(),
+            Err(ConnectorError::InvalidResponse(_))
+        ));
+    }
+
+    #[test]
+    fn test_set_working_channel_ack() {
+        let area = make_frame(0x08, None, &[3]);
+        let frame = make_frame(0xAB, Some(vec![0x01]), &[0x00]);
+        let mock = MockSerialPort::new(vec![area, frame]);
+        let mut connector = Connector::new(mock);
+        connector.set_working_channel(1).unwrap();
+    }
+
+    #[test]
+    fn test_set_working_channel_rejected_reports_status() {
+        let area = make_frame(0x08, None, &[3]);
+        let frame = make_frame(0xAB, Some(vec![0x05]), &[0x17]);
+        let mock = MockSerialPort::new(vec![area, frame]);
+        let mut connector = Connector::new(mock);
+        let err = connector.set_working_channel(0x05).unwrap_err();
+        assert!(err.to_string().contains("0x17"));
+    }
+
+    #[test]
+    fn test_set_working_channel_out_of_range() {
+        let area = make_frame(0x08, None, &[3]);
+        let mock = MockSerialPort::new(vec![area]);
+        let state = mock.state.clone();
+        let mut connector = Connector::new(mock);
+        assert_eq!(WorkingArea::EU.channel_count(), 15);
+        // The area is not known yet: it is read before checking the index
+        assert!(matches!(
+            connector.set_working_channel(15),
+            Err(ConnectorError::InvalidParameter(_))
+        ));
+        // Nothing sent past the working area request
+        assert_eq!(state.lock().unwrap().writes.len(), 1);
+        assert_eq!(connector.working_area(), Some(WorkingArea::EU));
+    }
+
+    #[test]
+    fn test_set_working_channel_mhz_uses_area() {
+        // EU: 865.9 MHz -> (865.9 - 865.1) / 0.2 = channel 4
+        let area = make_frame(0x08, None, &[3]);
+        let ack = make_frame(0xAB, Some(vec![0x04]), &[0x00]);
+        let mock = MockSerialPort::new(vec![area, ack]);
+        let state = mock.state.clone();
+        let mut connector = Connector::new(mock);
+        connector.set_working_channel_mhz(865.9).unwrap();
+
+        // Outside of the EU band: refused before anything is sent
+        assert!(matches!(
+            connector.set_working_channel_mhz(915.0),
+            Err(ConnectorError::InvalidParameter(_))
+        ));
+        assert_eq!(state.lock().unwrap().writes.len(), 2);
+    }
+
+    #[test]
+    fn test_channel_index_round_trip() {
+        assert_eq!(WorkingArea::China900Mhz.channel_index(920.375), Some(1));
+        assert_eq!(WorkingArea::US.channel_index(902.25), Some(0));
+        assert_eq!(WorkingArea::EU.channel_index(800.0), None);
+        // Past the last EU channel (867.9 MHz)
+        assert_eq!(WorkingArea::EU.channel_index(867.9), Some(14));
+        assert_eq!(WorkingArea::EU.channel_index(868.1), None);
+    }
+
+    #[test]
+    fn test_frequency_plan() {
+        let plan = WorkingArea::EU.frequency_plan();
+        assert!((plan.base_mhz - 865.1).abs() < 1e-9);
+        assert!((plan.step_mhz - 0.2).abs() < 1e-9);
+        let channels: Vec<f64> = plan.channels().collect();
+        assert_eq!(channels.len(), plan.channel_count as usize);
+        assert!((channels[1] - 865.3).abs() < 1e-9);
+        assert_eq!(plan.frequency(plan.channel_count), None);
+        for area in [
+            WorkingArea::China900Mhz,
+            WorkingArea::US,
+            WorkingArea::Korea,
+        ] {
+            let plan = area.frequency_plan();
+            for (i, mhz) in plan.channels().enumerate() {
+                assert_eq!(area.channel_index(mhz), Some(i as u8));
+            }
+        }
+    }
+
+    #[test]
+    fn test_working_area_codes_and_display() {
+        for code in 0..5 {
+            assert_eq!(WorkingArea::from_code(code).unwrap().to_code(), code);
+        }
+        assert_eq!(WorkingArea::from_code(5), None);
+        assert_eq!(WorkingArea::EU.to_string(), "EU (865.1–867.9 MHz)");
+        assert_eq!(WorkingArea::US.to_string(), "US (902.25–927.75 MHz)");
+        assert_eq!(
+            WorkingArea::China900Mhz.to_string(),
+            "China 900 MHz (920.125–924.875 MHz)"
+        );
+    }
+
+    #[test]
+    fn test_get_transmit_power() {
+        // 27.50 -> 2750 -> 0x0A BE (for example 0x0A, 0xBE => 2750)
+        let frame = make_frame(0xB7, None, &[0x0A, 0xBE]);
+        let mock = MockSerialPort::new(vec![frame]);
+        let mut connector = Connector::new(mock);
+        let p = connector.get_transmit_power().unwrap();
+        assert!((p.dbm - 27.50).abs() < 1e-6);
+        // 27.5 dBm is about 562 mW
+        assert!((p.milliwatts() - 562.34).abs() < 0.01);
+        assert_eq!(p.to_string(), "27.50 dBm (562.3 mW)");
+    }
+
+    #[test]
+    fn test_set_transmission_power_ack() {
+        // ACK byte 0x00
+        let frame = make_frame(0xB6, Some(vec![0x07, 0xD0]), &[0x00]);
+        let mock = MockSerialPort::new(vec![make_hardware_version(b"M100 26dBm V1.0"), frame]);
+        let mut connector = Connector::new(mock);
+        connector.set_transmission_power(20.0).unwrap();
+    }
+
+    #[test]
+    fn test_power_range() {
+        let mock = MockSerialPort::new(vec![make_hardware_version(b"M100 26dBm V1.0")]);
+        let state = mock.state.clone();
+        let mut connector = Connector::new(mock);
+        let err = connector.set_transmission_power(27.0).unwrap_err();
+        assert!(matches!(
+            err,
+            ConnectorError::PowerOutOfRange { max, .. } if max == 26.0
+        ));
+        // Only the hardware version was asked
+        assert_eq!(state.lock().unwrap().writes.len(), 1);
+
+        // The range is read by the setter itself
+        let hw = make_hardware_version(b"M100 30dBm V1.0");
+        let set = make_frame(0xB6, Some(vec![0x0A, 0xF0]), &[0x00]);
+        let mut connector = Connector::new(MockSerialPort::new(vec![hw, set]));
+        connector.set_transmission_power(28.0).unwrap();
+        // Cached, nothing more is sent
+        assert_eq!(connector.get_power_range().unwrap(), (15.0, 30.0));
+    }
+
+    #[test]
+    fn test_set_transmit_power_checked_returns_applied() {
+        let mock = MockSerialPort::new(vec![
+            make_hardware_version(b"M100 26dBm V1.0"),
+            make_frame(0xB6, Some(vec![0x09, 0x2E]), &[0x00]),
+            make_frame(0xB7, None, &[0x0A, 0x28]),
+        ]);
+        let mut connector = Connector::new(mock);
+        let applied = connector.set_transmit_power_checked(23.5).unwrap();
+        assert!((applied - 26.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_single_polling_instruction_parses_tags() {
+        // Build two tag frames then a timeout to end collection
+        let tag1 = {
+            let data = vec![
+                55, // RSSI
+                0x30, 0x12, // PC = 0x3012
+                0xDE, 0xAD, 0xBE, 0xEF, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
+                0x08, // padding to reach index 15
+                0xAB, 0xCD, // CRC bytes at 15,16
+            ];
+            make_frame(0x22, None, &data)
+        };
+        let tag2 = {
+            let data = vec![
+                60, 0x30, 0x34, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xAA, 0xBB,
+                0xCC, 0x12, 0x34,
+            ];
+            make_frame(0x22, None, &data)
+        };
+        let timeout = make_error_frame(io::Error::new(io::ErrorKind::TimedOut, "done"));
+        let mock = MockSerialPort::new(vec![tag1, tag2, timeout]);
+        let mut connector = Connector::new(mock);
+        let tags = connector.single_polling_instruction().unwrap();
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags[0].uid(), "DEADBEEF0102030405060708");
+        assert_eq!(tags[0].pc, "3012");
+        assert_eq!(tags[0].crc, "ABCD");
+        assert_eq!(tags[1].uid(), tags[1].epc);
+        assert_eq!(tags[1].uid(), "112233445566778899AABBCC");
+    }
+
+    #[test]
+    fn test_single_polling_instruction_raw() {
+        let tag = make_frame(0x22, None, &tag_payload(0xC9, 0xE2));
+        let ResponseType::Ok(chat) = &tag else {
+            unreachable!()
+        };
+        let frame = chat.responses.as_ref().unwrap().clone();
+        let timeout = make_error_frame(io::Error::new(io::ErrorKind::TimedOut, "done"));
+        let mock = MockSerialPort::new(vec![tag, timeout]);
+        let mut connector = Connector::new(mock);
+        let tags = connector.single_polling_instruction_raw().unwrap();
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].0.uid(), "E2E2E2E2E2E2E2E2E2E2E2E2");
+        assert_eq!(tags[0].1, frame);
+        assert_eq!(tags[0].1.first(), Some(&0xAA));
+        assert_eq!(tags[0].1.last(), Some(&0xDD));
+    }
+
+    #[test]
+    fn test_single_polling_instruction_timed() {
+        // The second read returns two frames at once
+        let mut burst = frame_bytes(0x22, &tag_payload(0xC9, 2));
+        burst.extend(frame_bytes(0x22, &tag_payload(0xC9, 3)));
+        let timeout = make_error_frame(io::Error::new(io::ErrorKind::TimedOut, "done"));
+        let mock = MockSerialPort::new(vec![
+            make_frame(0x22, None, &tag_payload(0xC9, 1)),
+            ResponseType::Raw(burst),
+            timeout,
+        ]);
+        let mut connector = Connector::new(mock);
+        connector.set_clock(Box::new(StepClock {
+            ticks: Default::default(),
+            step: Duration::from_millis(10),
+        }));
+        let tags = connector.single_polling_instruction_timed().unwrap();
+        assert_eq!(tags.len(), 3);
+        assert_eq!(tags[0].rfid.uid(), "E2E2E2E2E2E2E2E2E2E2E201");
+        assert_eq!(tags[2].rfid.uid(), "E2E2E2E2E2E2E2E2E2E2E203");
+        // One reading of the clock per read of the port
+        assert_eq!(tags[0].timestamp, Duration::from_millis(10));
+        assert_eq!(tags[1].timestamp, Duration::from_millis(20));
+        assert_eq!(tags[2].timestamp, Duration::from_millis(20));
+    }
+
+    #[test]
+    fn test_rssi_filter_drops_weak_tags() {
+        let tag = |rssi, last| make_frame(0x22, None, &tag_payload(rssi, last));
+        let timeout = make_error_frame(io::Error::new(io::ErrorKind::TimedOut, "done"));
+        // -55 dBm, -70 dBm and -60 dBm against a -60 dBm floor
+        let mock = MockSerialPort::new(vec![tag(0xC9, 1), tag(0xBA, 2), tag(0xC4, 3), timeout]);
+        let mut connector = Connector::new(mock);
+        connector.set_rssi_filter(Some(0xC4));
+        let tags = connector.single_polling_instruction().unwrap();
+        let epcs: Vec<String> = tags.iter().map(|t| t.uid()).collect();
+        assert_eq!(
+            epcs,
+            ["E2E2E2E2E2E2E2E2E2E2E201", "E2E2E2E2E2E2E2E2E2E2E203"]
+        );
+    }
+
+    #[test]
+    fn test_single_polling_instruction_with_timeout() {
+        let data = vec![
+            0xC9, 0x30, 0x00, 0xE2, 0x80, 0x68, 0x90, 0x00, 0x00, 0x50, 0x0E, 0x88, 0xC6, 0xA4,
+            0xA7, 0x11, 0x9B,
+        ];
+        let timeout = || make_error_frame(io::Error::new(io::ErrorKind::TimedOut, "done"));
+
+        // An elapsed deadline returns before reading anything
+        let mock = MockSerialPort::new(vec![make_frame(0x22, None, &data), timeout()]);
+        let mut connector = Connector::new(mock);
+        let tags = connector
+            .single_polling_instruction_with_timeout(Duration::ZERO)
+            .unwrap();
+        assert!(tags.is_empty());
+
+        let mock = MockSerialPort::new(vec![make_frame(0x22, None, &data), timeout()]);
+        let mut connector = Connector::new(mock);
+        let tags = connector
+            .single_polling_instruction_with_timeout(Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].epc, "E28068900000500E88C6A4A7");
+    }
+
+    /// Transport answering one tag, then timing out every read after the timeout it is given
+    struct TimedPort {
+        reads: Vec<Vec<u8>>,
+        timeouts: Vec<Duration>,
+    }
+
+    impl Transport for TimedPort {
+        fn read(&mut self, _buf: &mut [u8]) -> Result<usize, ConnectorError> {
+            panic!("read without a timeout");
+        }
+
+        fn read_with_timeout(
+            &mut self,
+            buf: &mut [u8],
+            timeout: Duration,
+        ) -> Result<usize, ConnectorError> {
+            self.timeouts.push(timeout);
+            if self.reads.is_empty() {
+                return Err(ConnectorError::Timeout);
+            }
+            let bytes = self.reads.remove(0);
+            buf[..bytes.len()].copy_from_slice(&bytes);
+            Ok(bytes.len())
+        }
+
+        fn write_all(&mut self, _buf: &[u8]) -> Result<(), ConnectorError> {
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), ConnectorError> {
+            Ok(())
+        }
+    }
+
+    /// Clock standing still
+    struct FrozenClock;
+
+    impl Clock for FrozenClock {
+        fn now(&self) -> Duration {
+            Duration::from_secs(1)
+        }
+    }
+
+    #[test]
+    fn test_single_polling_instruction_with_timeout_bounds_reads() {
+        let data = [
+            0xC9, 0x30, 0x00, 0xE2, 0x80, 0x68, 0x90, 0x00, 0x00, 0x50, 0x0E, 0x88, 0xC6, 0xA4,
+            0xA7, 0x11, 0x9B,
+        ];
+        let mut frame = vec![0xAA, 0x02, 0x22, 0x00, data.len() as u8];
+        frame.extend(data);
+        let sum: u16 = frame[1..].iter().map(|&b| b as u16).sum();
+        frame.extend([(sum & 0xFF) as u8, 0xDD]);
+        let mut connector = Connector::new(TimedPort {
+            reads: vec![frame],
+            timeouts: Vec::new(),
+        });
+        connector.set_clock(Box::new(FrozenClock));
+        let tags = connector
+            .single_polling_instruction_with_timeout(Duration::from_millis(50))
+            .unwrap();
+        assert_eq!(tags.len(), 1);
+        // Both the read of the tag and the last, empty read are bounded by the deadline
+        assert_eq!(connector.port.timeouts, [Duration::from_millis(50); 2]);
+    }
+
+    #[test]
+    fn test_event_sink() {
+        let data = vec![
+            0xC9, 0x30, 0x00, 0xE2, 0x80, 0x68, 0x90, 0x00, 0x00, 0x50, 0x0E, 0x88, 0xC6, 0xA4,
+            0xA7, 0x11, 0x9B,
+        ];
+        // Same frame with a wrong checksum, then the good one
+        let mut corrupted = vec![0xAA, 0x02, 0x22, 0x00, data.len() as u8];
+        corrupted.extend(&data);
+        corrupted.extend([0x00, 0xDD]);
+        let mock = MockSerialPort::new(vec![
+            ResponseType::Raw(corrupted.clone()),
+            make_frame(0x22, None, &data),
+        ]);
+        let mut connector = Connector::new(mock);
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        connector.set_event_sink(Box::new(move |e| sink.lock().unwrap().push(e)));
+
+        assert_eq!(connector.single_polling_instruction().unwrap().len(), 1);
+        let events = events.lock().unwrap();
+        assert!(matches!(&events[0], ReaderEvent::FrameSent(f) if f[2] == 0x22));
+        assert!(matches!(&events[1], ReaderEvent::RawBytes(b) if *b == corrupted));
+        assert!(matches!(&events[2], ReaderEvent::ChecksumFailed(b) if *b == corrupted));
+        assert!(matches!(&events[3], ReaderEvent::RawBytes(_)));
+        assert!(matches!(&events[4], ReaderEvent::PacketDecoded(p) if p.command_code() == 0x22));
+        assert!(matches!(events[5], ReaderEvent::Timeout));
+        assert_eq!(events.len(), 6);
+    }
+
+    #[test]
+    fn test_wait_for_tag() {
+        let tag = [
+            0xC9, 0x30, 0x00, 0xE2, 0x80, 0x68, 0x94, 0x00, 0x00, 0x50, 0x1D, 0x63, 0xE2, 0x78,
+            0x07, 0x4F, 0xB0,
+        ];
+        let timeout = || make_error_frame(io::Error::new(io::ErrorKind::TimedOut, "done"));
+        let mock = MockSerialPort::new(vec![
+            make_device_error(0x22, None, &[0x15]),
+            timeout(),
+            timeout(),
+            make_frame(0x22, None, &tag),
+            timeout(),
+        ]);
+        let state = mock.state.clone();
+        let mut connector = Connector::new(mock);
+        let tag = connector.wait_for_tag(Duration::from_secs(5)).unwrap();
+        assert_eq!(tag.unwrap().epc, "E28068940000501D63E27807");
+        assert_eq!(state.lock().unwrap().writes.len(), 3);
+
+        let mut connector = Connector::new(MockSerialPort::new(vec![]));
+        assert!(
+            connector
+                .wait_for_tag(Duration::from_millis(20))
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    /// Clock moving forward by `step` every time it is read
+    struct StepClock {
+        ticks: std::sync::atomic::AtomicU32,
+        step: Duration,
+    }
+
+    impl Clock for StepClock {
+        fn now(&self) -> Duration {
+            let ticks = self
+                .ticks
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            self.step * (ticks + 1)
+        }
+    }
+
+    #[test]
+    fn test_wait_for_tag_with_clock() {
+        let mock = MockSerialPort::new(vec![]);
+        let state = mock.state.clone();
+        let mut connector = Connector::new(mock);
+        connector.set_clock(Box::new(StepClock {
+            ticks: Default::default(),
+            step: Duration::from_secs(600),
+        }));
+        // An hour goes by in a few reads of the clock, two polls fit in it
+        let tag = connector.wait_for_tag(Duration::from_secs(3600)).unwrap();
+        assert!(tag.is_none());
+        assert_eq!(state.lock().unwrap().writes.len(), 2);
+    }
+
+    #[test]
+    fn test_single_polling_instruction_128bit_epc() {
+        // PC 0x4000 announces 8 EPC words, so the CRC follows 16 bytes of EPC
+        let mut data = vec![0xC9, 0x40, 0x00];
+        data.extend(0x10..0x20);
+        data.extend([0x3A, 0x76]);
+        let tag = make_frame(0x22, None, &data);
+        let timeout = make_error_frame(io::Error::new(io::ErrorKind::TimedOut, "done"));
+        let mock = MockSerialPort::new(vec![tag, timeout]);
+        let mut connector = Connector::new(mock);
+        let tags = connector.single_polling_instruction().unwrap();
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].uid(), "101112131415161718191A1B1C1D1E1F");
+        assert_eq!(tags[0].crc, "3A76");
+    }
+
+    #[test]
+    fn test_read_from_serial_noise_and_multiple_frames() {
+        // Noise bytes, then two frames in one read, then timeout to finish
+        let noise = vec![0x00, 0xFF, 0x13, 0x37];
+        let f1 = make_frame(0x08, None, &[2]);
+        let f2 = make_frame(0xAA, None, &[7]);
+        let mock = MockSerialPort::new(vec![
+            ResponseType::Raw(noise),
+            f1,
+            f2,
+            make_error_frame(io::Error::new(io::ErrorKind::TimedOut, "t")),
+        ]);
+        let mut connector = Connector::new(mock);
+        let out = connector.read_from_serial(None).unwrap().unwrap();
+        assert_eq!(out.len(), 2);
+        assert_eq!(out[0].get_data(), vec![2]);
+        assert_eq!(out[1].get_data(), vec![7]);
+    }
+
+    #[test]
+    fn test_read_from_serial_frame_split_byte_by_byte() {
+        let bytes = match make_frame(0xB7, None, &[0x07, 0xD0]) {
+            ResponseType::Ok(chat) => chat.responses.unwrap(),
+            _ => unreachable!(),
+        };
+        let mut chats: Vec<ResponseType> =
+            bytes.iter().map(|&b| ResponseType::Raw(vec![b])).collect();
+        chats.push(make_error_frame(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "t",
+        )));
+        let mock = MockSerialPort::new(chats);
+        let mut connector = Connector::new(mock);
+        let out = connector.read_from_serial(None).unwrap().unwrap();
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].command_code(), 0xB7);
+        assert_eq!(out[0].get_data(), vec![0x07, 0xD0]);
+    }
+
+    #[test]
+    fn test_set_buffer_sizes() {
+        let tags: Vec<ResponseType> = (1..=3).map(make_tag_notification).collect();
+        let mut chats = tags;
+        chats.push(make_error_frame(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "t",
+        )));
+        let mut connector = Connector::new(MockSerialPort::new(chats));
+        connector.set_buffer_sizes(1, 1);
+        assert_eq!(connector.read_buf_size, MIN_READ_BUF_SIZE);
+        assert_eq!(connector.rolling_cap, MIN_ROLLING_CAP);
+        connector.set_buffer_sizes(16384, 65536);
+        assert_eq!(connector.read_buf_size, 16384);
+        let out = connector.read_from_serial(None).unwrap().unwrap();
+        assert_eq!(out.len(), 3);
+    }
+
+    #[test]
+    fn test_read_from_serial_drops_wrong_checksum() {
+        let mut corrupted = match make_frame(0x08, None, &[2]) {
+            ResponseType::Ok(chat) => chat.responses.unwrap(),
+            _ => unreachable!(),
+        };
+        let cs_pos = corrupted.len() - 2;
+        corrupted[cs_pos] = corrupted[cs_pos].wrapping_add(1);
+        let good = make_frame(0xAA, None, &[7]);
+        let mock = MockSerialPort::new(vec![
+            ResponseType::Raw(corrupted),
+            good,
+            make_error_frame(io::Error::new(io::ErrorKind::TimedOut, "t")),
+        ]);
+        let mut connector = Connector::new(mock);
+        let out = connector.read_from_serial(None).unwrap().unwrap();
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].get_data(), vec![7]);
+    }
+
+    #[test]
+    fn test_read_from_serial_skips_echoed_command() {
+        let echo = Frame::new(&Command::AcquireTransmitPower).to_bytes();
+        let reply = make_frame(0xB7, None, &[0x07, 0xD0]);
+        let mock = MockSerialPort::new(vec![
+            ResponseType::Raw(echo),
+            reply,
+            make_error_frame(io::Error::new(io::ErrorKind::TimedOut, "t")),
+        ]);
+        let mut connector = Connector::new(mock);
+        connector
+            .send_packet(Command::AcquireTransmitPower)
+            .unwrap();
+        let out = connector.read_from_serial(None).unwrap().unwrap();
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].frame_type(), 0x01);
+        assert_eq!(out[0].get_data(), vec![0x07, 0xD0]);
+    }
+
+    const READ_USER_PARAMS: [u8; 9] = [0x00, 0x00, 0xFF, 0xFF, 0x03, 0x00, 0x00, 0x00, 0x02];
+
+    #[test]
+    fn test_read_tag_memory_default_password() {
+        let mut data = vec![0x0E, 0x34, 0x00];
+        data.extend([0xE2; 12]);
+        data.extend([0x12, 0x34, 0x56, 0x78]);
+        let frame = make_frame(0x39, Some(READ_USER_PARAMS.to_vec()), &data);
+        let mock = MockSerialPort::new(vec![frame]);
+        let mut connector = Connector::new(mock);
+        connector.set_access_password(0x0000FFFF);
+        let out = connector
+            .read_tag_memory(MemoryBank::User, 0, 2, None)
+            .unwrap();
+        assert_eq!(out, vec![0x12, 0x34, 0x56, 0x78]);
+    }
+
+    #[test]
+    fn test_read_tag_memory() {
+        // UL, PC, EPC and then the data read, as in the protocol manual example
+        let mut data = vec![0x0E, 0x34, 0x00];
+        data.extend([
+            0x30, 0x75, 0x1F, 0xEB, 0x70, 0x5C, 0x59, 0x04, 0xE3, 0xD5, 0x0D, 0x70,
+        ]);
+        data.extend([0x12, 0x34, 0x56, 0x78]);
+        let frame = make_frame(0x39, Some(READ_USER_PARAMS.to_vec()), &data);
+        let mock = MockSerialPort::new(vec![frame]);
+        let mut connector = Connector::new(mock);
+        let out = connector
+            .read_tag_memory(MemoryBank::User, 0, 2, Some(0x0000FFFF))
+            .unwrap();
+        assert_eq!(out, vec![0x12, 0x34, 0x56, 0x78]);
+    }
+
+    #[test]
+    fn test_read_tag_memory_errors() {
+        let no_tag = make_device_error(0x39, Some(READ_USER_PARAMS.to_vec()), &[0x09]);
+        let password = make_device_error(0x39, Some(READ_USER_PARAMS.to_vec()), &[0x16]);
+        let gen2 = make_device_error(0x39, Some(READ_USER_PARAMS.to_vec()), &[0xA3]);
+        let mock = MockSerialPort::new(vec![no_tag, password, gen2]);
+        let mut connector = Connector::new(mock);
+        let mut read = || {
+            connector
+                .read_tag_memory(MemoryBank::User, 0, 2, Some(0x0000FFFF))
+                .unwrap_err()
+        };
+        assert!(matches!(read(), ConnectorError::TagNotFound));
+        assert!(matches!(read(), ConnectorError::WrongPassword));
+        assert!(matches!(read(), ConnectorError::TagAccessFailed(0xA3)));
+    }
+
+    #[test]
+    fn test_read_tag_memory_longest() {
+        // The longest PC + EPC a tag reports, then MAX_READ_WORDS words
+        let mut data = vec![68];
+        data.extend([0xE2; 68]);
+        data.extend(vec![0x5A; 2 * MAX_READ_WORDS as usize]);
+        let mut params = READ_USER_PARAMS[..7].to_vec();
+        params.extend(MAX_READ_WORDS.to_be_bytes());
+        let mock = MockSerialPort::new(vec![make_frame(0x39, Some(params), &data)]);
+        let state = mock.state.clone();
+        let mut connector = Connector::new(mock);
+        let out = connector
+            .read_tag_memory(MemoryBank::User, 0, MAX_READ_WORDS, Some(0x0000FFFF))
+            .unwrap();
+        assert_eq!(out.len(), 2 * MAX_READ_WORDS as usize);
+        // A longer answer would not fit in a frame, rejected before sending
+        assert!(matches!(
+            connector.read_tag_memory(MemoryBank::User, 0, MAX_READ_WORDS + 1, None),
+            Err(ConnectorError::InvalidParameter(_))
+        ));
+        assert_eq!(state.lock().unwrap().writes.len(), 1);
+    }
+
+    const WRITE_USER_PARAMS: [u8; 13] = [
+        0x00, 0x00, 0xFF, 0xFF, 0x03, 0x00, 0x00, 0x00, 0x02, 0x12, 0x34, 0x56, 0x78,
+    ];
+
+    #[test]
+    fn test_write_tag_memory() {
+        let mut data = vec![0x0E, 0x34, 0x00];
+        data.extend([
+            0x30, 0x75, 0x1F, 0xEB, 0x70, 0x5C, 0x59, 0x04, 0xE3, 0xD5, 0x0D, 0x70,
+        ]);
+        data.push(0x00);
+        let frame = make_frame(0x49, Some(WRITE_USER_PARAMS.to_vec()), &data);
+        let mock = MockSerialPort::new(vec![frame]);
+        let mut connector = Connector::new(mock);
+        connector
+            .write_tag_memory(MemoryBank::User, 0, &[0x1234, 0x5678], Some(0x0000FFFF))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_write_tag_memory_errors() {
+        let no_tag = make_device_error(0x49, Some(WRITE_USER_PARAMS.to_vec()), &[0x10]);
+        let locked = make_device_error(0x49, Some(WRITE_USER_PARAMS.to_vec()), &[0xB4]);
+        let mock = MockSerialPort::new(vec![no_tag, locked]);
+        let mut connector = Connector::new(mock);
+        let err = connector
+            .write_tag_memory(MemoryBank::User, 0, &[0x1234, 0x5678], Some(0x0000FFFF))
+            .unwrap_err();
+        assert!(matches!(err, ConnectorError::TagNotFound));
+        let err = connector
+            .write_tag_memory(MemoryBank::User, 0, &[0x1234, 0x5678], Some(0x0000FFFF))
+            .unwrap_err();
+        assert!(matches!(err, ConnectorError::TagWriteProtected));
+    }
+
+    #[test]
+    fn test_write_epc() {
+        let mut ul_pc_epc = vec![0x0E, 0x34, 0x00];
+        ul_pc_epc.extend([0xE2; 12]);
+        // Current PC 0x3400: 6 EPC words and the UMI bit, which is kept
+        let mut data = ul_pc_epc.clone();
+        data.extend([0x34, 0x00]);
+        let read_pc = make_frame(
+            0x39,
+            Some(vec![0x00, 0x00, 0xFF, 0xFF, 0x01, 0x00, 0x01, 0x00, 0x01]),
+            &data,
+        );
+        let mut data = ul_pc_epc;
+        data.push(0x00);
+        let write = make_frame(
+            0x49,
+            Some(vec![
+                0x00, 0x00, 0xFF, 0xFF, 0x01, 0x00, 0x01, 0x00, 0x03, 0x14, 0x00, 0x30, 0x75, 0x1F,
+                0xEB,
+            ]),
+            &data,
+        );
+        let mock = MockSerialPort::new(vec![read_pc, write]);
+        let mut connector = Connector::new(mock);
+        connector
+            .write_epc(&[0x30, 0x75, 0x1F, 0xEB], 0x0000FFFF)
+            .unwrap();
+
+        let mut connector = Connector::new(MockSerialPort::new(vec![]));
+        let err = connector.write_epc(&[0x30, 0x75, 0x1F], 0).unwrap_err();
+        assert!(matches!(err, ConnectorError::InvalidParameter(_)));
+    }
+
+    #[test]
+    fn test_write_tag_memory_rejects_empty_words() {
+        let mock = MockSerialPort::new(vec![]);
+        let state = mock.state.clone();
+        let mut connector = Connector::new(mock);
+        let err = connector
+            .write_tag_memory(MemoryBank::Epc, 2, &[], None)
+            .unwrap_err();
+        assert!(matches!(err, ConnectorError::InvalidParameter(_)));
+        assert!(state.lock().unwrap().writes.is_empty());
+    }
+
+    fn make_tag_notification(epc_last: u8) -> ResponseType {
+        let data = [
+            0xC9, 0x30, 0x00, 0xE2, 0x80, 0x68, 0x94, 0x00, 0x00, 0x50, 0x1D, 0x63, 0xE2, 0x78,
+            epc_last, 0x4F, 0xB0,
+        ];
+        make_response(0x27, None, 0x22, &data)
+    }
+
+    #[test]
+    fn test_inventory_stream_yields_tags() {
+        let no_tag = make_device_error(0x27, None, &[0x15]);
+        let stop_ack = make_frame(0x28, None, &[0x00]);
+        let mock = MockSerialPort::new(vec![
+            make_tag_notification(0x01),
+            no_tag,
+            make_tag_notification(0x02),
+            make_error_frame(io::Error::new(io::ErrorKind::TimedOut, "done")),
+            stop_ack,
+        ]);
+        let state = mock.state.clone();
+        let mut connector = Connector::new(mock);
+        let tags: Vec<Rfid> = connector
+            .inventory_stream(10)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags[0].uid(), "E28068940000501D63E27801");
+        assert_eq!(tags[1].uid(), "E28068940000501D63E27802");
+
+        let writes = &state.lock().unwrap().writes;
+        assert_eq!(
+            writes[0],
+            vec![0xAA, 0x00, 0x27, 0x00, 0x02, 0x00, 0x0A, 0x33, 0xDD]
+        );
+        assert_eq!(writes[1], vec![0xAA, 0x00, 0x28, 0x00, 0x00, 0x28, 0xDD]);
+    }
+
+    #[test]
+    fn test_multi_polling_with_stops_early() {
+        let mut leftover = Vec::new();
+        for tag in [make_tag_notification(0x02), make_tag_notification(0x03)] {
+            if let ResponseType::Ok(chat) = tag {
+                leftover.extend(chat.responses.unwrap());
+            }
+        }
+        let mock = MockSerialPort::new(vec![
+            make_tag_notification(0x01),
+            ResponseType::Raw(leftover),
+            make_frame(0x28, None, &[0x00]),
+        ]);
+        let state = mock.state.clone();
+        let mut connector = Connector::new(mock);
+        let mut seen = Vec::new();
+        connector
+            .multi_polling_with(100, |tag| {
+                seen.push(tag.uid());
+                ControlFlow::Break(())
+            })
+            .unwrap();
+        assert_eq!(seen, vec!["E28068940000501D63E27801".to_string()]);
+        let writes = &state.lock().unwrap().writes;
+        assert_eq!(writes.len(), 2);
+        assert_eq!(writes[1], vec![0xAA, 0x00, 0x28, 0x00, 0x00, 0x28, 0xDD]);
+    }
+
+    #[test]
+    fn test_multi_polling_instruction_collects_tags() {
+        let mock = MockSerialPort::new(vec![
+            make_tag_notification(0x01),
+            make_device_error(0x27, None, &[0x15]),
+            make_tag_notification(0x02),
+            make_error_frame(io::Error::new(io::ErrorKind::TimedOut, "done")),
+            make_frame(0x28, None, &[0x00]),
+        ]);
+        let mut connector = Connector::new(mock);
+        let tags = connector.multi_polling_instruction().unwrap();
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags[1].uid(), "E28068940000501D63E27802");
+    }
+
+    #[test]
+    fn test_multi_antenna_tags() {
+        let tag = |port: u8| {
+            let data = [&[port][..], &tag_payload(0xC9, 0xE2)].concat();
+            make_response(0x27, None, 0x22, &data)
+        };
+        let mock = MockSerialPort::new(vec![
+            tag(0x01),
+            tag(0x04),
+            make_error_frame(io::Error::new(io::ErrorKind::TimedOut, "done")),
+            make_frame(0x28, None, &[0x00]),
+        ]);
+        let mut connector = Connector::new(mock);
+        connector.set_multi_antenna(true);
+        let tags = connector.multi_polling_instruction().unwrap();
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags[0].antenna, Some(1));
+        assert_eq!(tags[1].antenna, Some(4));
+        assert_eq!(tags[1].uid(), "E2E2E2E2E2E2E2E2E2E2E2E2");
+    }
+
+    #[test]
+    fn test_stop_multiple_polling_drains_in_flight_frames() {
+        let mut in_flight = Vec::new();
+        for tag in [make_tag_notification(0x01), make_tag_notification(0x02)] {
+            if let ResponseType::Ok(chat) = tag {
+                in_flight.extend(chat.responses.unwrap());
+            }
+        }
+        if let ResponseType::Ok(chat) = make_frame(0x28, None, &[0x00]) {
+            in_flight.extend(chat.responses.unwrap());
+        }
+        let mock = MockSerialPort::new(vec![
+            ResponseType::Raw(in_flight),
+            make_frame(0xB7, None, &[0x07, 0xD0]),
+        ]);
+        let mut connector = Connector::new(mock);
+        assert_eq!(connector.stop_multiple_polling_instructions().unwrap(), 2);
+        // The next command gets its own response
+        assert!((connector.get_transmit_power().unwrap().dbm - 20.0).abs() < 1e-6);
+
+        let mock = MockSerialPort::new(vec![make_error_frame(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "silent",
+        ))]);
+        let mut connector = Connector::new(mock);
+        assert!(matches!(
+            connector.stop_multiple_polling_instructions(),
+            Err(ConnectorError::ErrorStopMultiPolling(_))
+        ));
+    }
+
+    #[test]
+    fn test_inventory_unique() {
+        let mock = MockSerialPort::new(vec![
+            make_tag_notification(0x01),
+            make_tag_notification(0x02),
+            make_tag_notification(0x01),
+            make_error_frame(io::Error::new(io::ErrorKind::TimedOut, "done")),
+            make_frame(0x28, None, &[0x00]),
+        ]);
+        let mut connector = Connector::new(mock);
+        let observations = connector.inventory_unique(10).unwrap();
+        assert_eq!(observations.len(), 2);
+        assert_eq!(observations[0].epc, "E28068940000501D63E27801");
+        assert_eq!(observations[0].reads, 2);
+        assert_eq!(observations[1].reads, 1);
+    }
+
+    #[test]
+    fn test_multi_polling_streaming() {
+        let mock = MockSerialPort::new(vec![
+            make_tag_notification(0x01),
+            make_tag_notification(0x02),
+            make_error_frame(io::Error::new(io::ErrorKind::TimedOut, "done")),
+            make_frame(0x28, None, &[0x00]),
+        ]);
+        let mut connector = Connector::new(mock);
+        let (tx, rx) = std::sync::mpsc::sync_channel(1);
+        let reader = std::thread::spawn(move || rx.iter().map(|t: Rfid| t.uid()).collect());
+        connector.multi_polling_streaming(tx, 10).unwrap();
+        let uids: Vec<String> = reader.join().unwrap();
+        assert_eq!(
+            uids,
+            ["E28068940000501D63E27801", "E28068940000501D63E27802"]
+        );
+    }
+
+    #[test]
+    fn test_multi_polling_streaming_stops_without_receiver() {
+        let mock = MockSerialPort::new(vec![
+            make_tag_notification(0x01),
+            make_frame(0x28, None, &[0x00]),
+        ]);
+        let state = mock.state.clone();
+        let mut connector = Connector::new(mock);
+        let (tx, rx) = std::sync::mpsc::sync_channel(1);
+        drop(rx);
+        connector.multi_polling_streaming(tx, 10).unwrap();
+        let writes = &state.lock().unwrap().writes;
+        assert_eq!(writes.last().unwrap()[2], 0x28);
+    }
+
+    #[test]
+    fn test_inventory_for_collects_until_dwell() {
+        let mock = MockSerialPort::new(vec![
+            make_tag_notification(0x01),
+            // A timeout before the dwell is over does not end the inventory
+            make_error_frame(io::Error::new(io::ErrorKind::TimedOut, "quiet")),
+            make_tag_notification(0x02),
+            ResponseType::Silence(Duration::from_millis(60)),
+            make_frame(0x28, None, &[0x00]),
+        ]);
+        let state = mock.state.clone();
+        let mut connector = Connector::new(mock);
+        let tags = connector.inventory_for(Duration::from_millis(50)).unwrap();
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags[1].uid(), "E28068940000501D63E27802");
+        let writes = &state.lock().unwrap().writes;
+        assert_eq!(writes[0][2..7], [0x27, 0x00, 0x02, 0xFF, 0xFF]);
+        assert_eq!(writes[1], [0xAA, 0x00, 0x28, 0x00, 0x00, 0x28, 0xDD]);
+    }
+
+    #[test]
+    fn test_multi_polling_cancellable() {
+        let ResponseType::Ok(in_flight) = make_tag_notification(0x02) else {
+            unreachable!()
+        };
+        let mock = MockSerialPort::new(vec![
+            make_tag_notification(0x01),
+            // Still in flight when the inventory is cancelled, discarded by the stop
+            ResponseType::Raw(in_flight.responses.unwrap()),
+            make_frame(0x28, None, &[0x00]),
+        ]);
+        let state = mock.state.clone();
+        let mut connector = Connector::new(mock);
+        let cancel = Arc::new(AtomicBool::new(false));
+        let flag = cancel.clone();
+        // Cancel as soon as the first tag is decoded, as a GUI thread would
+        connector.set_event_sink(Box::new(move |event| {
+            if matches!(event, ReaderEvent::PacketDecoded(_)) {
+                flag.store(true, Ordering::Relaxed);
+            }
+        }));
+        let tags = connector.multi_polling_cancellable(100, &cancel).unwrap();
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].uid(), "E28068940000501D63E27801");
+        let writes = &state.lock().unwrap().writes;
+        assert_eq!(writes.len(), 2);
+        assert_eq!(writes[1][2], 0x28);
+    }
+
+    #[test]
+    fn test_inventory_for_stops_on_error() {
+        let mock = MockSerialPort::new(vec![
+            make_tag_notification(0x01),
+            make_error_frame(io::Error::new(io::ErrorKind::BrokenPipe, "unplugged")),
+            make_frame(0x28, None, &[0x00]),
+        ]);
+        let state = mock.state.clone();
+        let mut connector = Connector::new(mock);
+        assert!(connector.inventory_for(Duration::from_secs(5)).is_err());
+        let writes = &state.lock().unwrap().writes;
+        assert_eq!(writes.len(), 2);
+        assert_eq!(writes[1][2], 0x28);
+    }
+
+    #[test]
+    fn test_multi_polling_detailed() {
+        let mock = MockSerialPort::new(vec![
+            make_tag_notification(0x01),
+            make_device_error(0x27, None, &[0x15]),
+            make_tag_notification(0x02),
+            make_tag_notification(0x01),
+            make_device_error(0x27, None, &[0x15]),
+            make_error_frame(io::Error::new(io::ErrorKind::TimedOut, "done")),
+            make_frame(0x28, None, &[0x00]),
+        ]);
+        let mut connector = Connector::new(mock);
+        let result = connector.multi_polling_detailed(5).unwrap();
+        assert_eq!(result.reads.len(), 3);
+        assert_eq!(result.unique_count, 2);
+        assert_eq!(result.empty_rounds, 2);
+        assert_eq!(result.rounds_completed, None);
+    }
+
+    #[test]
+    fn test_inventory_resilient_splits_long_runs() {
+        let mock = MockSerialPort::new(vec![
+            make_tag_notification(0x01),
+            make_error_frame(io::Error::new(io::ErrorKind::TimedOut, "done")),
+            make_frame(0x28, None, &[0x00]),
+            make_tag_notification(0x02),
+            make_error_frame(io::Error::new(io::ErrorKind::TimedOut, "done")),
+            make_frame(0x28, None, &[0x00]),
+        ]);
+        let state = mock.state.clone();
+        let mut connector = Connector::new(mock);
+        let tags = connector.inventory_resilient(70_000).unwrap();
+        assert_eq!(tags.len(), 2);
+
+        let writes = &state.lock().unwrap().writes;
+        let starts: Vec<_> = writes.iter().filter(|w| w[2] == 0x27).collect();
+        assert_eq!(starts.len(), 2);
+        assert_eq!(&starts[0][5..7], &[0xFF, 0xFF]);
+        // 70000 - 65535 = 4465
+        assert_eq!(&starts[1][5..7], &[0x11, 0x71]);
+    }
 
     #[test]
-    fn test_get_module_info() {
-        let hw = make_frame(0x03, Some(vec![0x00]), b"HW1.0");
-        let sw = make_frame(0x03, Some(vec![0x01]), b"SW2.0");
-        let mf = make_frame(0x03, Some(vec![0x02]), b"ACME");
-        let mock = MockSerialPort::new(vec![hw, sw, mf]);
+    fn test_inventory_with_tid() {
+        let mut data = vec![0xC9, 0x30, 0x00];
+        data.extend([0xE2; 12]);
+        data.extend([
+            0xE2, 0x80, 0x11, 0x05, 0x20, 0x00, 0x71, 0x2A, 0x9B, 0x3C, 0x08, 0x01,
+        ]);
+        data.extend([0x12, 0x34]);
+        // The user filter: 16 bits of the EPC bank from bit 0x20
+        let user_select = vec![0x01, 0x00, 0x00, 0x00, 0x20, 0x10, 0x00, 0xE2, 0x80];
+        let mock = MockSerialPort::new(vec![
+            make_frame(0x12, Some(vec![0x00]), &[0x00]),
+            make_frame(0x0B, None, &user_select),
+            make_frame(
+                0x0C,
+                Some(vec![0x02, 0x00, 0x00, 0x03, 0xD0, 0x08, 0x00, 0x02]),
+                &[0x00],
+            ),
+            make_frame(0x12, Some(vec![0x00]), &[0x00]),
+            make_response(0x27, None, 0x22, &data),
+            make_error_frame(io::Error::new(io::ErrorKind::TimedOut, "done")),
+            make_frame(0x28, None, &[0x00]),
+            make_frame(0x0C, Some(user_select), &[0x00]),
+            make_frame(0x12, Some(vec![0x00]), &[0x00]),
+        ]);
+        let state = mock.state.clone();
         let mut connector = Connector::new(mock);
+        connector.set_select_mode(true).unwrap();
+        let tags = connector.inventory_with_tid(10).unwrap();
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].tid.as_deref(), Some("E28011052000712A9B3C0801"));
+        assert_eq!(tags[0].crc, "1234");
+        // The user filter is set again and Select mode stays on as it was
+        let writes = &state.lock().unwrap().writes;
+        assert_eq!(
+            writes.last().unwrap(),
+            &vec![0xAA, 0x00, 0x12, 0x00, 0x01, 0x00, 0x13, 0xDD]
+        );
+    }
 
-        let info = connector.get_module_info().unwrap();
-        assert!(info.contains("Hardware: HW1.0"));
-        assert!(info.contains("Software: SW2.0"));
-        assert!(info.contains("Manufacturer: ACME"));
+    #[test]
+    fn test_inventory_stream_into_unique() {
+        let mock = MockSerialPort::new(vec![
+            make_tag_notification(0x01),
+            make_tag_notification(0x02),
+            make_tag_notification(0x01),
+            make_tag_notification(0x03),
+            make_error_frame(io::Error::new(io::ErrorKind::TimedOut, "done")),
+            make_frame(0x28, None, &[0x00]),
+        ]);
+        let state = mock.state.clone();
+        let mut connector = Connector::new(mock);
+        let mut stream = connector.inventory_stream(10).unwrap();
+        let first = stream.next().unwrap().unwrap();
+        assert_eq!(first.uid(), "E28068940000501D63E27801");
+        let unique = stream.into_unique().unwrap();
+        assert_eq!(unique.len(), 3);
+        assert_eq!(unique[0].epc, "E28068940000501D63E27802");
+        // The read taken before is not counted
+        assert_eq!(unique[1].reads, 1);
+        // The stream stopped the inventory when consumed
+        assert_eq!(state.lock().unwrap().writes.last().unwrap()[2], 0x28);
     }
 
     #[test]
-    fn test_get_working_area_mapping() {
-        for (code, expected) in [
-            (0, WorkingArea::China900Mhz),
-            (1, WorkingArea::China800Mhz),
-            (2, WorkingArea::US),
-            (3, WorkingArea::EU),
-            (4, WorkingArea::Korea),
-        ] {
-            let frame = make_frame(0x08, None, &[code]);
-            let mock = MockSerialPort::new(vec![frame]);
-            let mut connector = Connector::new(mock);
-            let area = connector.get_working_area().unwrap();
-            // Compare by variant name via debug
-            assert_eq!(format!("{:?}", area), format!("{:?}", expected));
+    fn test_inventory_stream_drop_drains_serial() {
+        let mut leftover = Vec::new();
+        // Two tags in a single read, still in flight when the stream is dropped
+        for tag in [make_tag_notification(0x02), make_tag_notification(0x03)] {
+            if let ResponseType::Ok(chat) = tag {
+                leftover.extend(chat.responses.unwrap());
+            }
+        }
+        let mock = MockSerialPort::new(vec![
+            make_tag_notification(0x01),
+            ResponseType::Raw(leftover),
+            make_frame(0x28, None, &[0x00]),
+            make_frame(0xB7, None, &[0x07, 0xD0]),
+        ]);
+        let mut connector = Connector::new(mock);
+        {
+            let mut stream = connector.inventory_stream(100).unwrap();
+            let first = stream.next().unwrap().unwrap();
+            assert_eq!(first.uid(), "E28068940000501D63E27801");
         }
+        let p = connector.get_transmit_power().unwrap();
+        assert!((p.dbm - 20.0).abs() < 1e-6);
     }
 
     #[test]
-    fn test_get_working_channel_uses_area() {
-        // Channel index 4 -> depends on area. We'll test EU mapping: 0.2 MHz step + 865.1
-        // First response: channel index, Second: area code 3 (EU)
-        let chan = make_frame(0xAA, None, &[4]);
+    fn test_command_retries_after_timeout() {
+        let chats = || {
+            vec![
+                make_error_frame(io::Error::new(io::ErrorKind::TimedOut, "lost")),
+                make_frame(0xB7, None, &[0x07, 0xD0]),
+            ]
+        };
+        let mut connector = Connector::new(MockSerialPort::new(chats()));
+        assert!(matches!(
+            connector.get_transmit_power(),
+            Err(ConnectorError::Timeout)
+        ));
+
+        let mock = MockSerialPort::new(chats());
+        let state = mock.state.clone();
+        let mut connector = Connector::new(mock);
+        connector.set_command_retries(2);
+        let p = connector.get_transmit_power().unwrap();
+        assert!((p.dbm - 20.0).abs() < 1e-6);
+        assert_eq!(state.lock().unwrap().writes.len(), 2);
+        // Input dropped once, before the resend
+        assert_eq!(state.lock().unwrap().clears, 1);
+    }
+
+    #[test]
+    fn test_io_port_transport() {
+        let mock = MockSerialPort::new(vec![make_frame(0xB7, None, &[0x07, 0xD0])]);
+        let mut connector = Connector::new(IoPort::new(mock));
+        let p = connector.get_transmit_power().unwrap();
+        assert!((p.dbm - 20.0).abs() < 1e-6);
+        assert_eq!(
+            connector.port.get_ref().state.lock().unwrap().writes.len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_device_error_frames() {
+        let rejected = make_device_error(0xB6, None, &[0x17]);
+        let mock = MockSerialPort::new(vec![make_hardware_version(b"M100 26dBm V1.0"), rejected]);
+        let mut connector = Connector::new(mock);
+        let err = connector.set_transmission_power(20.0).unwrap_err();
+        assert!(matches!(err, ConnectorError::Device(0x17)));
+
+        let no_tag = make_device_error(0x22, None, &[0x15]);
+        let mock = MockSerialPort::new(vec![no_tag]);
+        let mut connector = Connector::new(mock);
+        assert!(connector.single_polling_instruction().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_query_parameters() {
+        let get = make_frame(0x0D, None, &[0x10, 0x20]);
+        let set = make_frame(0x0E, Some(vec![0x11, 0x38]), &[0x00]);
+        let mock = MockSerialPort::new(vec![get, set]);
+        let mut connector = Connector::new(mock);
+        let params = connector.get_query_parameters().unwrap();
+        assert_eq!(params.q, 4);
+        assert_eq!(params.session, Session::S0);
+        assert_eq!(params.target, Target::A);
+        assert_eq!(params.sel, Sel::All);
+        connector
+            .set_query_parameters(7, Session::S1, Target::A, Sel::All)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_get_select() {
+        // Response from the manual: EPC bank, bit 0x20, 96 bit mask, no truncation
+        let manual = make_frame(
+            0x0B,
+            None,
+            &[
+                0x01, 0x00, 0x00, 0x00, 0x20, 0x60, 0x00, 0x30, 0x75, 0x1F, 0xEB, 0x70, 0x5C, 0x59,
+                0x04, 0xE3, 0xD5, 0x0D, 0x70,
+            ],
+        );
+        let tid = make_frame(
+            0x0B,
+            None,
+            &[0x46, 0x00, 0x00, 0x03, 0xD0, 0x08, 0x80, 0x02],
+        );
+        let short = make_frame(
+            0x0B,
+            None,
+            &[0x01, 0x00, 0x00, 0x00, 0x20, 0x10, 0x00, 0xE2],
+        );
+        let mock = MockSerialPort::new(vec![manual, tid, short]);
+        let mut connector = Connector::new(mock);
+
+        let select = connector.get_select().unwrap();
+        assert_eq!(select.mask_bank, MemoryBank::Epc);
+        assert_eq!(select.bit_pointer, 0x20);
+        assert_eq!(select.mask_bit_len, 96);
+        assert!(!select.truncate);
+        assert_eq!(select.mask.len(), 12);
+        assert_eq!(select.mask[0], 0x30);
+        assert_eq!((select.target, select.action), (0, 0));
+
+        let select = connector.get_select().unwrap();
+        assert_eq!(select.mask_bank, MemoryBank::Tid);
+        assert_eq!((select.target, select.action), (2, 1));
+        assert_eq!(select.bit_pointer, 0x3D0);
+        assert!(select.truncate);
+        assert_eq!(select.mask, vec![0x02]);
+
+        assert!(matches!(
+            connector.get_select(),
+            Err(ConnectorError::InvalidResponse(_))
+        ));
+    }
+
+    #[test]
+    fn test_set_query_parameters_rejects_large_q() {
+        let mock = MockSerialPort::new(vec![]);
+        let mut connector = Connector::new(mock);
+        let err = connector
+            .set_query_parameters(16, Session::S0, Target::A, Sel::All)
+            .unwrap_err();
+        assert!(matches!(err, ConnectorError::InvalidParameter(_)));
+    }
+
+    #[test]
+    fn test_set_select_and_mode() {
+        // 16 bit company prefix at the start of the EPC, the trailing mask byte is not sent
+        let select = make_frame(
+            0x0C,
+            Some(vec![0x01, 0x00, 0x00, 0x00, 0x20, 0x10, 0x00, 0xE2, 0x80]),
+            &[0x00],
+        );
+        let mode = make_frame(0x12, Some(vec![0x00]), &[0x00]);
+        let mock = MockSerialPort::new(vec![select, mode]);
+        let mut connector = Connector::new(mock);
+        connector
+            .set_select(MemoryBank::Epc, 0x20, &[0xE2, 0x80, 0xFF], 16)
+            .unwrap();
+        connector.set_select_mode(true).unwrap();
+    }
+
+    #[test]
+    fn test_access_and_read() {
+        let epc = [0xE2, 0x80, 0x11, 0x22];
+        let select = make_frame(
+            0x0C,
+            Some(vec![
+                0x01, 0x00, 0x00, 0x00, 0x20, 0x20, 0x00, 0xE2, 0x80, 0x11, 0x22,
+            ]),
+            &[0x00],
+        );
+        let mode_on = make_frame(0x12, Some(vec![0x00]), &[0x00]);
+        let mut data = vec![0x06, 0x14, 0x00];
+        data.extend(epc);
+        data.extend([0x12, 0x34, 0x56, 0x78]);
+        let read = make_frame(0x39, Some(READ_USER_PARAMS.to_vec()), &data);
+        let mode_off = make_frame(0x12, Some(vec![0x01]), &[0x00]);
+        let mock = MockSerialPort::new(vec![select, mode_on, read, mode_off]);
+        let mut connector = Connector::new(mock);
+        let out = connector
+            .access_and_read(
+                SelectMask::matching_epc(&epc),
+                0x0000FFFF,
+                MemoryBank::User,
+                0,
+                2,
+            )
+            .unwrap();
+        assert_eq!(out, vec![0x12, 0x34, 0x56, 0x78]);
+    }
+
+    #[test]
+    fn test_set_select_mask() {
+        let select = make_frame(
+            0x0C,
+            Some(vec![0x01, 0x00, 0x00, 0x00, 0x20, 0x10, 0x00, 0x30, 0x00]),
+            &[0x00],
+        );
+        let mock = MockSerialPort::new(vec![select]);
+        let state = mock.state.clone();
+        let mut connector = Connector::new(mock);
+        connector
+            .set_select_mask(&SelectMask::epc().match_bytes(&[0x30, 0x00]))
+            .unwrap();
+        // 12 bits do not need a third byte
+        let err = connector
+            .set_select_mask(&SelectMask::epc().match_bits(&[0x30, 0x00, 0x00], 12))
+            .unwrap_err();
+        assert!(matches!(err, ConnectorError::InvalidParameter(_)));
+        assert_eq!(state.lock().unwrap().writes.len(), 1);
+    }
+
+    #[test]
+    fn test_set_select_rejects_short_mask() {
+        let mock = MockSerialPort::new(vec![]);
+        let mut connector = Connector::new(mock);
+        let err = connector
+            .set_select(MemoryBank::Epc, 0x20, &[0xE2], 16)
+            .unwrap_err();
+        assert!(matches!(err, ConnectorError::InvalidParameter(_)));
+    }
+
+    #[test]
+    fn test_set_auto_frequency_hopping() {
+        let on = make_frame(0xAD, Some(vec![0xFF]), &[0x00]);
+        let off = make_device_error(0xAD, Some(vec![0x00]), &[0x17]);
+        let mock = MockSerialPort::new(vec![on, off]);
+        let mut connector = Connector::new(mock);
+        assert_eq!(connector.auto_frequency_hopping(), None);
+        connector.set_auto_frequency_hopping(true).unwrap();
+        assert_eq!(connector.auto_frequency_hopping(), Some(true));
+        assert!(connector.set_auto_frequency_hopping(false).is_err());
+        assert_eq!(connector.auto_frequency_hopping(), Some(true));
+    }
+
+    #[test]
+    fn test_set_hopping_channels() {
         let area = make_frame(0x08, None, &[3]);
-        let mock = MockSerialPort::new(vec![chan, area]);
+        let set = make_frame(0xA9, Some(vec![0x03, 0x01, 0x02, 0x03]), &[0x00]);
+        let clear = make_frame(0xA9, Some(vec![0x00]), &[0x00]);
+        let mock = MockSerialPort::new(vec![area, set, clear]);
+        let state = mock.state.clone();
         let mut connector = Connector::new(mock);
-        let freq = connector.get_working_channel().unwrap();
-        assert!((freq - (4.0 * 0.2 + 865.1)).abs() < 1e-6);
+        assert!(matches!(
+            connector.set_hopping_channels(&[1, 15]),
+            Err(ConnectorError::InvalidParameter(_))
+        ));
+        assert_eq!(state.lock().unwrap().writes.len(), 1);
+        assert_eq!(connector.hopping_channels(), None);
+        connector.set_hopping_channels(&[1, 2, 3]).unwrap();
+        assert_eq!(connector.hopping_channels(), Some(&[1u8, 2, 3][..]));
+        connector.set_hopping_channels(&[]).unwrap();
+        assert_eq!(connector.hopping_channels(), None);
     }
 
     #[test]
-    fn test_get_transmit_power() {
-        // 27.50 -> 2750 -> 0x0A BE (for example 0x0A, 0xBE => 2750)
-        let frame = make_frame(0xB7, None, &[0x0A, 0xBE]);
-        let mock = MockSerialPort::new(vec![frame]);
+    fn test_send_raw() {
+        let query = make_frame(0x0D, None, &[0x10, 0x20]);
+        let failure = make_device_error(0xF3, None, &[0x17]);
+        let mock = MockSerialPort::new(vec![query, failure]);
         let mut connector = Connector::new(mock);
-        let p = connector.get_transmit_power().unwrap();
-        assert!((p - 27.50).abs() < 1e-6);
+        let p = connector.send_raw(0x0D, &[]).unwrap();
+        assert_eq!(p.command_code(), 0x0D);
+        assert_eq!(p.frame_type(), 0x01);
+        assert_eq!(p.get_data(), vec![0x10, 0x20]);
+        let p = connector.send_raw(0xF3, &[]).unwrap();
+        assert_eq!(p.as_error(), Some(0x17));
     }
 
     #[test]
-    fn test_set_transmission_power_ack() {
-        // ACK byte 0x00
-        let frame = make_frame(0xB6, Some(vec![0x07, 0xD0]), &[0x00]);
-        let mock = MockSerialPort::new(vec![frame]);
+    fn test_single_polling_instruction_short_payload() {
+        let short = make_frame(0x22, None, &[0xC9, 0x30, 0x00, 0xE2, 0x80, 0x68]);
+        let timeout = make_error_frame(io::Error::new(io::ErrorKind::TimedOut, "done"));
+        let mock = MockSerialPort::new(vec![short, timeout]);
         let mut connector = Connector::new(mock);
-        connector.set_transmission_power(20.0).unwrap();
+        assert!(connector.single_polling_instruction().unwrap().is_empty());
     }
 
     #[test]
-    fn test_single_polling_instruction_parses_tags() {
-        // Build two tag frames then a timeout to end collection
-        let tag1 = {
-            let data = vec![
-                55, // RSSI
-                0x30, 0x12, // PC = 0x3012
-                0xDE, 0xAD, 0xBE, 0xEF, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
-                0x08, // padding to reach index 15
-                0xAB, 0xCD, // CRC bytes at 15,16
-            ];
-            make_frame(0x22, None, &data)
-        };
-        let tag2 = {
-            let data = vec![
-                60, 0x20, 0x34, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xAA, 0xBB,
-                0xCC, 0x12, 0x34,
-            ];
-            make_frame(0x22, None, &data)
+    fn test_kill_tag() {
+        let password = Some(vec![0x00, 0x00, 0xFF, 0xFF]);
+        let mut data = vec![0x0E, 0x34, 0x00];
+        data.extend([
+            0x30, 0x75, 0x1F, 0xEB, 0x70, 0x5C, 0x59, 0x04, 0xE3, 0xD5, 0x0D, 0x70,
+        ]);
+        data.push(0x00);
+        let killed = make_frame(0x65, password.clone(), &data);
+        let no_tag = make_device_error(0x65, password.clone(), &[0x12]);
+        // Kill error with Gen2 code 0 (other error)
+        let refused = make_device_error(0x65, password, &[0xD0, 0x0E, 0x34, 0x00]);
+        let mock = MockSerialPort::new(vec![killed, no_tag, refused]);
+        let mut connector = Connector::new(mock);
+        connector.kill_tag(0x0000FFFF).unwrap();
+        assert!(matches!(
+            connector.kill_tag(0x0000FFFF),
+            Err(ConnectorError::TagNotFound)
+        ));
+        assert!(matches!(
+            connector.kill_tag(0x0000FFFF),
+            Err(ConnectorError::TagAccessFailed(0xD0))
+        ));
+    }
+
+    #[test]
+    fn test_lock_tag() {
+        let params = Some(vec![0x00, 0x00, 0xFF, 0xFF, 0x03, 0x00, 0x80]);
+        let mut data = vec![0x0E, 0x34, 0x00];
+        data.extend([
+            0x30, 0x75, 0x1F, 0xEB, 0x70, 0x5C, 0x59, 0x04, 0xE3, 0xD5, 0x0D, 0x70,
+        ]);
+        data.push(0x00);
+        let locked = make_frame(0x82, params.clone(), &data);
+        let no_tag = make_device_error(0x82, params.clone(), &[0x13]);
+        let permalocked = make_device_error(0x82, params, &[0xC4, 0x0E, 0x34, 0x00]);
+        let mock = MockSerialPort::new(vec![locked, no_tag, permalocked]);
+        let state = mock.state.clone();
+        let mut connector = Connector::new(mock);
+        let mask = LockMask::new().access_password(LockAction::Lock);
+        connector.lock_tag(Some(0x0000FFFF), mask).unwrap();
+        assert_eq!(
+            state.lock().unwrap().writes[0],
+            vec![
+                0xAA, 0x00, 0x82, 0x00, 0x07, 0x00, 0x00, 0xFF, 0xFF, 0x03, 0x00, 0x80, 0x0A, 0xDD
+            ]
+        );
+        assert!(matches!(
+            connector.lock_tag(Some(0x0000FFFF), mask),
+            Err(ConnectorError::TagNotFound)
+        ));
+        assert!(matches!(
+            connector.lock_tag(Some(0x0000FFFF), mask),
+            Err(ConnectorError::TagWriteProtected)
+        ));
+        // Nothing to change: rejected before anything is sent
+        assert!(matches!(
+            connector.lock_tag(Some(0x0000FFFF), LockMask::new()),
+            Err(ConnectorError::InvalidParameter(_))
+        ));
+        assert_eq!(state.lock().unwrap().writes.len(), 3);
+    }
+
+    #[cfg(feature = "serialport")]
+    #[test]
+    fn test_check_baud_rate() {
+        assert!(Connector::<MockSerialPort>::check_baud_rate(921600).is_ok());
+        assert!(matches!(
+            Connector::<MockSerialPort>::check_baud_rate(250000),
+            Err(ConnectorError::UnsupportedBaud(250000))
+        ));
+    }
+
+    #[cfg(feature = "serialport")]
+    #[test]
+    fn test_port_info_is_probably_r200() {
+        let port = |vid, pid| crate::connector::PortInfo {
+            name: "/dev/ttyUSB0".into(),
+            vid,
+            pid,
+            product: None,
+            serial_number: None,
         };
+        assert!(port(Some(0x1A86), Some(0x7523)).is_probably_r200());
+        assert!(port(Some(0x10C4), Some(0xEA60)).is_probably_r200());
+        assert!(!port(Some(0x0403), Some(0x6001)).is_probably_r200());
+        assert!(!port(None, None).is_probably_r200());
+    }
+
+    #[test]
+    fn test_reset_module() {
+        let mock = MockSerialPort::new(vec![
+            make_frame(0x08, None, &[3]),
+            make_frame(0xA9, Some(vec![0x02, 0x01, 0x02]), &[0x00]),
+            make_frame(0x17, None, &[0x00]),
+            make_error_frame(io::Error::new(io::ErrorKind::TimedOut, "rebooting")),
+        ]);
+        let state = mock.state.clone();
+        let mut connector = Connector::new(mock);
+        connector.set_hopping_channels(&[1, 2]).unwrap();
+        connector.reset_module().unwrap();
+        assert_eq!(connector.power_mode(), Some(PowerMode::Active));
+        // The restart brings back the default settings
+        assert_eq!(connector.working_area(), None);
+        assert_eq!(connector.hopping_channels(), None);
+        let writes = &state.lock().unwrap().writes;
+        assert_eq!(writes[2], vec![0xAA, 0x00, 0x17, 0x00, 0x00, 0x17, 0xDD]);
+        assert_eq!(writes[3], vec![WAKE_UP_BYTE]);
+    }
+
+    #[test]
+    fn test_set_power_mode_idle_and_back() {
+        let mock = MockSerialPort::new(vec![
+            make_frame(0x04, Some(vec![0x01, 0x01, 0x03]), &[0x00]),
+            make_frame(0x04, Some(vec![0x00, 0x01, 0x03]), &[0x00]),
+            make_frame(0x04, Some(vec![0x01, 0x01, 0x03]), &[0x17]),
+        ]);
+        let mut connector = Connector::new(mock);
+        assert_eq!(connector.power_mode(), None);
+        connector.set_power_mode(PowerMode::Idle).unwrap();
+        assert_eq!(connector.power_mode(), Some(PowerMode::Idle));
+        connector.set_power_mode(PowerMode::Active).unwrap();
+        assert_eq!(connector.power_mode(), Some(PowerMode::Active));
+        assert!(matches!(
+            connector.set_power_mode(PowerMode::Idle),
+            Err(ConnectorError::Device(0x17))
+        ));
+        assert_eq!(connector.power_mode(), Some(PowerMode::Active));
+    }
+
+    #[test]
+    fn test_rf_carrier_guard_stops_on_drop() {
+        let mock = MockSerialPort::new(vec![
+            make_frame(0xB0, Some(vec![0xFF]), &[0x00]),
+            make_frame(0xB0, Some(vec![0x00]), &[0x00]),
+            make_frame(0xB0, Some(vec![0xFF]), &[0x00]),
+            make_frame(0xB0, Some(vec![0x00]), &[0x01]),
+        ]);
+        let state = mock.state.clone();
+        let mut connector = Connector::new(mock);
+        {
+            let _carrier = connector.start_rf_carrier().unwrap();
+        }
+        assert_eq!(state.lock().unwrap().writes.len(), 2);
+        let carrier = connector.start_rf_carrier().unwrap();
+        assert!(matches!(carrier.stop(), Err(ConnectorError::Device(0x01))));
+        // stop() already tried, drop does not send again
+        assert_eq!(state.lock().unwrap().writes.len(), 4);
+    }
+
+    #[test]
+    fn test_gpio() {
+        let read =
+            |pin: u8, level: u8| make_frame(0x1A, Some(vec![0x02, pin, 0x00]), &[0x02, pin, level]);
+        let mock = MockSerialPort::new(vec![
+            make_frame(0x1A, Some(vec![0x00, 0x04, 0x01]), &[0x00, 0x04, 0x01]),
+            make_frame(0x1A, Some(vec![0x01, 0x04, 0x01]), &[0x01, 0x04, 0x01]),
+            make_frame(0x1A, Some(vec![0x01, 0x03, 0x00]), &[0x01, 0x03, 0x00]),
+            read(1, 0),
+            read(2, 1),
+            read(3, 0),
+            read(4, 1),
+        ]);
+        let mut connector = Connector::new(mock);
+        connector.set_gpio_direction(4, true).unwrap();
+        connector.set_gpio(4, true).unwrap();
+        assert!(matches!(
+            connector.set_gpio(3, false),
+            Err(ConnectorError::FailedSetting(_))
+        ));
+        assert!(matches!(
+            connector.set_gpio(5, true),
+            Err(ConnectorError::InvalidParameter(_))
+        ));
+        let state = connector.get_gpio().unwrap();
+        assert_eq!(state.levels, [false, true, false, true]);
+        assert_eq!(state.level(4), Some(true));
+        assert_eq!(state.level(0), None);
+        assert_eq!(state.level(5), None);
+    }
+
+    /// A Transport that is not std::io, as a board UART driver would provide
+    struct BufferTransport {
+        rx: Vec<u8>,
+        tx: Vec<u8>,
+    }
+
+    impl Transport for BufferTransport {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, ConnectorError> {
+            if self.rx.is_empty() {
+                return Err(ConnectorError::Timeout);
+            }
+            let n = buf.len().min(self.rx.len());
+            buf[..n].copy_from_slice(&self.rx[..n]);
+            self.rx.drain(..n);
+            Ok(n)
+        }
+
+        fn write_all(&mut self, buf: &[u8]) -> Result<(), ConnectorError> {
+            self.tx.extend_from_slice(buf);
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), ConnectorError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_unexpected_response_code() {
+        // A tag left over by an inventory instead of the power response
+        let tag = [
+            0xC9, 0x30, 0x00, 0xE2, 0x80, 0x68, 0x94, 0x00, 0x00, 0x50, 0x1D, 0x63, 0xE2, 0x78,
+            0x01, 0x4F, 0xB0,
+        ];
+        // The port fails while resyncing, the mismatch is still what is reported
+        let unplugged = make_error_frame(io::Error::new(io::ErrorKind::BrokenPipe, "unplugged"));
+        let mock = MockSerialPort::new(vec![make_response(0xB7, None, 0x22, &tag), unplugged]);
+        let mut connector = Connector::new(mock);
+        assert!(matches!(
+            connector.get_transmit_power(),
+            Err(ConnectorError::UnexpectedResponse {
+                expected: 0xB7,
+                got: 0x22
+            })
+        ));
+    }
+
+    #[test]
+    fn test_unexpected_response_resyncs() {
+        let tag = [
+            0xC9, 0x30, 0x00, 0xE2, 0x80, 0x68, 0x94, 0x00, 0x00, 0x50, 0x1D, 0x63, 0xE2, 0x78,
+            0x01, 0x4F, 0xB0,
+        ];
+        // Half of the next tag frame is still queued when the foreign answer is read
+        let rest = ResponseType::Raw(vec![0xAA, 0x02, 0x22, 0x00, 0x11, 0xC9, 0x30]);
         let timeout = make_error_frame(io::Error::new(io::ErrorKind::TimedOut, "done"));
-        let mock = MockSerialPort::new(vec![tag1, tag2, timeout]);
+        let power = make_frame(0xB7, None, &[0x07, 0xD0]);
+        let mock = MockSerialPort::new(vec![
+            make_response(0xB7, None, 0x22, &tag),
+            rest,
+            timeout,
+            power,
+        ]);
+        let mut connector = Connector::new(mock);
+        assert!(matches!(
+            connector.get_transmit_power(),
+            Err(ConnectorError::UnexpectedResponse { .. })
+        ));
+        assert_eq!(connector.get_transmit_power().unwrap().dbm, 20.0);
+    }
+
+    #[test]
+    fn test_resync_stops_on_frame_boundary() {
+        let partial = ResponseType::Raw(vec![0x02, 0x22, 0x00]);
+        let ack = ResponseType::Raw(vec![0xAA, 0x01, 0xAD, 0x00, 0x01, 0x00, 0xAF, 0xDD]);
+        let next = ResponseType::Raw(vec![0xAA]);
+        let mock = MockSerialPort::new(vec![partial, ack, next]);
+        let mut connector = Connector::new(mock);
+        assert_eq!(connector.resync().unwrap(), 11);
+        // The byte after the boundary is left for the next read
+        assert_eq!(connector.resync().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_resync_gives_up_on_endless_stream() {
+        // Noise that never lands on a frame boundary
+        let mut reads: Vec<ResponseType> = (0..10)
+            .map(|_| ResponseType::Raw(vec![0x13; 100]))
+            .collect();
+        reads.push(ResponseType::Raw(vec![0xAA]));
+        let mock = MockSerialPort::new(reads);
+        let mut connector = Connector::new(mock);
+        connector.set_buffer_sizes(1024, MIN_ROLLING_CAP);
+        assert_eq!(connector.resync().unwrap(), 600);
+        assert_eq!(connector.resync().unwrap(), 401);
+    }
+
+    #[test]
+    fn test_empty_read_before_response() {
+        let empty = || ResponseType::Raw(vec![]);
+        let power = make_frame(0xB7, None, &[0x07, 0xD0]);
+        let mock = MockSerialPort::new(vec![empty(), empty(), power, empty(), empty(), empty()]);
+        let mut connector = Connector::new(mock);
+        assert_eq!(connector.get_transmit_power().unwrap().dbm, 20.0);
+        // A port that keeps returning nothing still gives up
+        assert!(matches!(connector.read_from_serial(None), Ok(None)));
+    }
+
+    #[test]
+    fn test_set_inventory_mode() {
+        // S2, target A, Q=4: 0x1000 | 2 << 8 | 4 << 3
+        let ack = make_frame(0x0E, Some(vec![0x12, 0x20]), &[0x00]);
+        let mock = MockSerialPort::new(vec![ack]);
+        let mut connector = Connector::new(mock);
+        connector
+            .set_inventory_mode(InventoryMode::PortalAntiRepeat)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_last_tx_and_rx() {
+        let noise = ResponseType::Raw(vec![0x00, 0x13]);
+        let power = make_frame(0xB7, None, &[0x07, 0xD0]);
+        let mock = MockSerialPort::new(vec![noise, power]);
         let mut connector = Connector::new(mock);
+        assert!(connector.last_tx().is_none());
+        connector.get_transmit_power().unwrap();
+        assert_eq!(
+            connector.last_tx().unwrap(),
+            [0xAA, 0x00, 0xB7, 0x00, 0x00, 0xB7, 0xDD]
+        );
+        assert_eq!(
+            connector.last_rx().unwrap(),
+            [
+                0x00, 0x13, 0xAA, 0x01, 0xB7, 0x00, 0x02, 0x07, 0xD0, 0x91, 0xDD
+            ]
+        );
+    }
+
+    #[test]
+    fn test_custom_transport() {
+        let mut connector = Connector::new(BufferTransport {
+            rx: vec![0xAA, 0x01, 0xB7, 0x00, 0x02, 0x07, 0xD0, 0x91, 0xDD],
+            tx: Vec::new(),
+        });
+        let power = connector.get_transmit_power().unwrap();
+        assert!((power.dbm - 20.0).abs() < 1e-6);
+        assert_eq!(
+            connector.port.tx,
+            vec![0xAA, 0x00, 0xB7, 0x00, 0x00, 0xB7, 0xDD]
+        );
+    }
+
+    #[test]
+    fn test_from_reader_replays_capture() {
+        let capture = [
+            0xAA, 0x02, 0x22, 0x00, 0x11, 0xC9, 0x34, 0x00, 0x30, 0x75, 0x1F, 0xEB, 0x70, 0x5C,
+            0x59, 0x04, 0xE3, 0xD5, 0x0D, 0x70, 0x3A, 0x76, 0xEF, 0xDD, 0xAA, 0x01, 0xFF, 0x00,
+            0x01, 0x15, 0x16, 0xDD,
+        ];
+        let mut connector = Connector::from_reader(Box::new(io::Cursor::new(capture)));
         let tags = connector.single_polling_instruction().unwrap();
-        assert_eq!(tags.len(), 2);
-        assert_eq!(tags[0].uid(), "DEADBEEF0102030405060708");
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].uid(), "30751FEB705C5904E3D50D70");
+        assert_eq!(
+            connector.replay_port().written(),
+            [0xAA, 0x00, 0x22, 0x00, 0x00, 0x22, 0xDD]
+        );
     }
 
     #[test]
-    fn test_read_from_serial_noise_and_multiple_frames() {
-        // Noise bytes, then two frames in one read, then timeout to finish
-        let noise = vec![0x00, 0xFF, 0x13, 0x37];
-        let f1 = make_frame(0x08, None, &[2]);
-        let f2 = make_frame(0xAA, None, &[7]);
+    fn test_drop_stops_running_inventory() {
+        let stop = [0xAA, 0x00, 0x28, 0x00, 0x00, 0x28, 0xDD];
+        let mock = MockSerialPort::new(vec![]);
+        let state = mock.state.clone();
+        let mut connector = Connector::new(mock);
+        connector.enable_multiple_polling_instructions(100).unwrap();
+        drop(connector);
+        let writes = &state.lock().unwrap().writes;
+        assert_eq!(writes.len(), 2);
+        assert_eq!(writes[1], stop);
+
+        // Nothing to stop once the device confirmed it
+        let mock = MockSerialPort::new(vec![make_frame(0x28, None, &[0x00])]);
+        let state = mock.state.clone();
+        let mut connector = Connector::new(mock);
+        connector.enable_multiple_polling_instructions(100).unwrap();
+        connector.stop_multiple_polling_instructions().unwrap();
+        drop(connector);
+        assert_eq!(state.lock().unwrap().writes.len(), 2);
+    }
+
+    #[test]
+    fn test_route_notifications_to_event_sink() {
+        // A tag notification left over by an inventory, then the power response
+        let mut tag = vec![0xAA, 0x02, 0x22, 0x00, 0x11, 0xC9, 0x34, 0x00];
+        tag.extend([
+            0x30, 0x75, 0x1F, 0xEB, 0x70, 0x5C, 0x59, 0x04, 0xE3, 0xD5, 0x0D, 0x70, 0x3A, 0x76,
+            0xEF, 0xDD,
+        ]);
         let mock = MockSerialPort::new(vec![
-            ResponseType::Raw(noise),
-            f1,
-            f2,
-            make_error_frame(io::Error::new(io::ErrorKind::TimedOut, "t")),
+            ResponseType::Raw(tag),
+            make_frame(0xB7, None, &[0x07, 0xD0]),
         ]);
+        let notifications = Arc::new(Mutex::new(Vec::new()));
+        let seen = notifications.clone();
         let mut connector = Connector::new(mock);
-        let out = connector.read_from_serial(None).unwrap().unwrap();
-        assert_eq!(out.len(), 2);
-        assert_eq!(out[0].get_data(), vec![2]);
-        assert_eq!(out[1].get_data(), vec![7]);
+        connector.set_route_notifications(true);
+        connector.set_event_sink(Box::new(move |event| {
+            if let ReaderEvent::Notification(p) = event {
+                seen.lock().unwrap().push(p.command_code());
+            }
+        }));
+        let power = connector.get_transmit_power().unwrap();
+        assert!((power.dbm - 20.0).abs() < 1e-6);
+        assert_eq!(*notifications.lock().unwrap(), [0x22]);
     }
 
     // ---- clear_non_ascii tests ----
@@ -0,0 +1,200 @@
+use crate::connector::ConnectorError;
+use core::time::Duration;
+
+/// Byte link to the module used by the blocking connector (`SyncIO`).
+///
+/// Implemented for `Box<dyn serialport::SerialPort>`; wrap any other `std::io::Read + Write`
+/// in an `IoPort`. Without the `std` feature implement it for the UART of your board.
+pub trait Transport {
+    /// Read the bytes available into `buf`.
+    ///
+    /// The end of a response is detected by the absence of data: return Ok(0) or
+    /// Err(ConnectorError::Timeout) when nothing arrives within a few character times.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, ConnectorError>;
+    /// Read like `read`, giving up with Err(ConnectorError::Timeout) after at most `timeout`.
+    ///
+    /// Lets the deadlines of `SyncIO` (e.g. `single_polling_instruction_with_timeout`) cut a
+    /// read short of the usual timeout of the link. The default ignores `timeout`, the
+    /// deadline is then only checked between reads.
+    fn read_with_timeout(
+        &mut self,
+        buf: &mut [u8],
+        timeout: Duration,
+    ) -> Result<usize, ConnectorError> {
+        let _ = timeout;
+        self.read(buf)
+    }
+    /// Discard the bytes received and not read yet, e.g. the late answer to a command that
+    /// is about to be sent again.
+    ///
+    /// The default does nothing.
+    fn clear_input(&mut self) -> Result<(), ConnectorError> {
+        Ok(())
+    }
+    /// Write the whole buffer
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), ConnectorError>;
+    /// Wait until the written bytes are sent
+    fn flush(&mut self) -> Result<(), ConnectorError>;
+}
+
+/// Read from a `std::io::Read`, a timeout of the reader becoming Err(ConnectorError::Timeout)
+#[cfg(feature = "std")]
+pub(crate) fn io_read(
+    reader: &mut impl std::io::Read,
+    buf: &mut [u8],
+) -> Result<usize, ConnectorError> {
+    match reader.read(buf) {
+        Ok(n) => Ok(n),
+        Err(e) if e.kind() == std::io::ErrorKind::TimedOut => Err(ConnectorError::Timeout),
+        Err(e) => Err(ConnectorError::SerialRead(e.to_string())),
+    }
+}
+
+#[cfg(feature = "serialport")]
+impl Transport for Box<dyn serialport::SerialPort> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, ConnectorError> {
+        io_read(self, buf)
+    }
+
+    /// Read with the port timeout lowered to `timeout`, then put back
+    fn read_with_timeout(
+        &mut self,
+        buf: &mut [u8],
+        timeout: Duration,
+    ) -> Result<usize, ConnectorError> {
+        let previous = self.timeout();
+        self.set_timeout(timeout.min(previous))?;
+        let result = io_read(self, buf);
+        self.set_timeout(previous)?;
+        result
+    }
+
+    fn clear_input(&mut self) -> Result<(), ConnectorError> {
+        Ok(self.clear(serialport::ClearBuffer::Input)?)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), ConnectorError> {
+        Ok(std::io::Write::write_all(self, buf)?)
+    }
+
+    fn flush(&mut self) -> Result<(), ConnectorError> {
+        Ok(std::io::Write::flush(self)?)
+    }
+}
+
+/// Any `std::io::Read + Write` as a Transport, e.g. a TCP bridge to the module.
+///
+/// A timeout of the reader ends a response. `std::io` cannot bound a single read nor
+/// drop pending input, so deadlines are only checked between reads and nothing is
+/// discarded before a retry: prefer the `Transport` of `Box<dyn SerialPort>` for a
+/// serial port.
+#[cfg(feature = "std")]
+pub struct IoPort<T>(T);
+
+#[cfg(feature = "std")]
+impl<T> IoPort<T> {
+    /// Wrap `inner`, a stream already connected to the module
+    pub fn new(inner: T) -> Self {
+        IoPort(inner)
+    }
+
+    /// The wrapped stream
+    pub fn get_ref(&self) -> &T {
+        &self.0
+    }
+
+    /// The wrapped stream, e.g. to change its settings
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+
+    /// Unwrap the stream
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Transport for IoPort<T>
+where
+    T: std::io::Read + std::io::Write,
+{
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, ConnectorError> {
+        io_read(&mut self.0, buf)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), ConnectorError> {
+        Ok(self.0.write_all(buf)?)
+    }
+
+    fn flush(&mut self) -> Result<(), ConnectorError> {
+        Ok(self.0.flush()?)
+    }
+}
+
+/// Recorded bytes read back as if they came from the module, see `Connector::from_reader`.
+///
+/// Written commands are captured instead of sent. The end of the recording reads as a
+/// timeout, like a module that has nothing more to say.
+#[cfg(feature = "std")]
+pub struct ReplayPort {
+    reader: Box<dyn std::io::Read>,
+    written: Vec<u8>,
+}
+
+#[cfg(feature = "std")]
+impl ReplayPort {
+    /// Replay the bytes of `reader`, a file or a `std::io::Cursor` over a capture
+    pub fn new(reader: Box<dyn std::io::Read>) -> Self {
+        ReplayPort {
+            reader,
+            written: Vec::new(),
+        }
+    }
+
+    /// Every byte written so far, commands back to back
+    pub fn written(&self) -> &[u8] {
+        &self.written
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::io::Read for ReplayPort {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self.reader.read(buf)? {
+            0 if !buf.is_empty() => Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                "end of recording",
+            )),
+            n => Ok(n),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Transport for ReplayPort {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, ConnectorError> {
+        io_read(self, buf)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), ConnectorError> {
+        self.written.extend_from_slice(buf);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), ConnectorError> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::io::Write for ReplayPort {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.written.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
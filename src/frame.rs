@@ -1,4 +1,9 @@
-use std::fmt::{Display, Formatter};
+use crate::connector::{LockMask, MemoryBank, QueryParameters, WorkingArea};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
 
 /// Known R200 constants
 pub const R200_FRAME_HEADER: u8 = 0xAA;
@@ -6,26 +11,61 @@ pub const R200_FRAME_END: u8 = 0xDD;
 
 /// Frame type:
 const FRAME_TYPE_SEND_COMMAND: u8 = 0x00; // from PC to R200
+const FRAME_TYPE_RESPONSE: u8 = 0x01; // from R200 to PC, answer to a command
+const FRAME_TYPE_NOTIFICATION: u8 = 0x02; // from R200 to PC, e.g. a tag read during inventory
 const INSTRUCTION_READER_WRITER_MODULE_INFO: u8 = 0x03; // Get reader/writer module information
 
+/// Type byte of a frame, telling a command from its response or from a notification
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameType {
+    /// Sent by the host
+    Command,
+    /// Answer of the device to a command
+    Response,
+    /// Sent by the device on its own, such as the tags read during an inventory
+    Notification,
+    Unknown(u8),
+}
+
+impl FrameType {
+    pub fn from_code(code: u8) -> FrameType {
+        match code {
+            FRAME_TYPE_SEND_COMMAND => FrameType::Command,
+            FRAME_TYPE_RESPONSE => FrameType::Response,
+            FRAME_TYPE_NOTIFICATION => FrameType::Notification,
+            other => FrameType::Unknown(other),
+        }
+    }
+}
+
+/// Error decoding a command from its bytes
 #[derive(Debug)]
 pub enum FrameError {
     InvalidCommand(String),
 }
 
 impl Display for FrameError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             FrameError::InvalidCommand(msg) => write!(f, "Invalid command: {}", msg),
         }
     }
 }
 
-impl std::error::Error for FrameError {}
+impl core::error::Error for FrameError {}
 
+/// Commands the host sends to the reader, one per instruction of the protocol manual.
+///
+/// The connector builds them for you; use them directly with [`Frame`] to encode frames
+/// yourself, and `Command::Raw` for an instruction without its own variant.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
 pub enum Command {
     GetWorkingChannel,
+    SetWorkingChannel(u8),
     GetWorkingArea,
+    SetWorkingArea(WorkingArea),
     AcquireTransmitPower,
     SetTransmissionPower(f64),
     HardwareVersion,
@@ -34,16 +74,73 @@ pub enum Command {
     SinglePollingInstruction,
     MultiplePollingInstruction(u16),
     StopMultiplePollingInstruction,
+    ReadTagMemory {
+        bank: MemoryBank,
+        word_addr: u16,
+        word_count: u16,
+        access_password: u32,
+    },
+    WriteTagMemory {
+        bank: MemoryBank,
+        word_addr: u16,
+        words: Vec<u16>,
+        access_password: u32,
+    },
+    GetQueryParameters,
+    SetQueryParameters(QueryParameters),
+    GetSelect,
+    SetSelect {
+        bank: MemoryBank,
+        bit_pointer: u32,
+        mask: Vec<u8>,
+        mask_bit_len: u8,
+    },
+    SetSelectMode(bool),
+    SetAutoFrequencyHopping(bool),
+    /// Channel indexes the automatic hopping picks from, an empty list clears it
+    InsertWorkingChannel(Vec<u8>),
+    KillTag(u32),
+    /// UART baud rate, sent as baud / 100
+    SetBaudRate(u32),
+    /// Power down the chip until the next byte is received
+    ModuleSleep,
+    /// Enter (true) or leave (false) the IDLE mode, with the RF part powered off
+    IdleMode(bool),
+    /// Start (true) or stop (false) transmitting an unmodulated carrier
+    SetRfCarrier(bool),
+    /// Configure a GPIO pin (1 to 4) as output (true) or input (false)
+    SetGpioDirection {
+        pin: u8,
+        output: bool,
+    },
+    /// Drive a GPIO output pin high (true) or low (false)
+    SetGpioLevel {
+        pin: u8,
+        high: bool,
+    },
+    /// Read the level of a GPIO pin
+    ReadGpioLevel(u8),
+    LockTag {
+        access_password: u32,
+        lock_mask: LockMask,
+    },
+    /// Any command code with its parameters, sent as is
+    Raw {
+        code: u8,
+        params: Vec<u8>,
+    },
 }
 
 impl Display for Command {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Command::HardwareVersion => write!(f, "Hardware Version"),
             Command::SoftwareVersion => write!(f, "Software Version"),
             Command::Manufacturer => write!(f, "Manufacturer"),
             Command::GetWorkingChannel => write!(f, "Get Working Channel"),
+            Command::SetWorkingChannel(index) => write!(f, "Set Working Channel to {index}"),
             Command::GetWorkingArea => write!(f, "Get Working Area"),
+            Command::SetWorkingArea(area) => write!(f, "Set Working Area to {area}"),
             Command::AcquireTransmitPower => write!(f, "Acquire transmit power"),
             Command::SetTransmissionPower(power) => {
                 write!(f, "Set transmission power to {}", power)
@@ -55,18 +152,91 @@ impl Display for Command {
             Command::StopMultiplePollingInstruction => {
                 write!(f, "Stop Multiple Polling Instruction")
             }
+            Command::ReadTagMemory {
+                bank,
+                word_addr,
+                word_count,
+                ..
+            } => write!(
+                f,
+                "Read Tag Memory [bank: {bank:?}, address: {word_addr}, words: {word_count}]"
+            ),
+            Command::WriteTagMemory {
+                bank,
+                word_addr,
+                words,
+                ..
+            } => write!(
+                f,
+                "Write Tag Memory [bank: {bank:?}, address: {word_addr}, words: {}]",
+                words.len()
+            ),
+            Command::GetQueryParameters => write!(f, "Get Query Parameters"),
+            Command::SetQueryParameters(params) => {
+                write!(f, "Set Query Parameters to {:?}", params)
+            }
+            Command::GetSelect => write!(f, "Get Select"),
+            Command::SetSelect {
+                bank,
+                bit_pointer,
+                mask_bit_len,
+                ..
+            } => write!(
+                f,
+                "Set Select [bank: {bank:?}, pointer: {bit_pointer}, mask bits: {mask_bit_len}]"
+            ),
+            Command::SetSelectMode(enabled) => write!(f, "Set Select Mode to {enabled}"),
+            Command::SetAutoFrequencyHopping(enabled) => {
+                write!(f, "Set Automatic Frequency Hopping to {enabled}")
+            }
+            Command::InsertWorkingChannel(channels) => {
+                write!(f, "Insert Working Channel {channels:?}")
+            }
+            Command::KillTag(_) => write!(f, "Kill Tag"),
+            Command::SetBaudRate(baud) => write!(f, "Set Baud Rate to {baud}"),
+            Command::ModuleSleep => write!(f, "Module Sleep"),
+            Command::IdleMode(enter) => write!(f, "Idle Mode {enter}"),
+            Command::SetRfCarrier(on) => write!(f, "Set Continuous Carrier to {on}"),
+            Command::SetGpioDirection { pin, output } => {
+                write!(f, "Set GPIO {pin} direction [output: {output}]")
+            }
+            Command::SetGpioLevel { pin, high } => write!(f, "Set GPIO {pin} level [high: {high}]"),
+            Command::ReadGpioLevel(pin) => write!(f, "Read GPIO {pin} level"),
+            Command::LockTag { lock_mask, .. } => {
+                write!(f, "Lock Tag [payload: 0x{:05X}]", lock_mask.payload())
+            }
+            Command::Raw { code, params } => {
+                write!(f, "Raw command 0x{code:02X} [{} bytes]", params.len())
+            }
         }
     }
 }
 
-/// Trait for serializable commands
-pub(crate) trait SerializableCommand {
+impl Command {
+    /// Command code of the frame, also found in the response to it
+    pub fn code(&self) -> u8 {
+        self.to_bytes().0[0]
+    }
+}
+
+/// Encoding of a command into the bytes of a frame, implement it to send a command of
+/// your own through [`Frame`]
+pub trait SerializableCommand {
     /// Returns a tuple of bytes (command, parameters)
     /// Parameters may be empty if not present
     fn to_bytes(&self) -> (Vec<u8>, Vec<u8>);
+    /// Decode a command from its (command, parameters) bytes, as found in a frame.
+    ///
+    /// Only needed to decode frames back, the default refuses every tuple.
     fn from_tuple(tuple: (Vec<u8>, Vec<u8>)) -> Result<Self, FrameError>
     where
-        Self: Sized;
+        Self: Sized,
+    {
+        Err(FrameError::InvalidCommand(format!(
+            "Cannot decode command {:02X?}",
+            tuple.0
+        )))
+    }
 }
 
 const READ_WRITE_INFO_HARDWARE_VERSION: u8 = 0x00;
@@ -89,91 +259,212 @@ impl SerializableCommand for Command {
                 vec![READ_WRITE_INFO_MANUFACTURER],
             ), //Command::Manufacturer
             Command::GetWorkingChannel => (vec![0xAA], vec![]),
+            Command::SetWorkingChannel(index) => (vec![0xAB], vec![*index]),
             Command::GetWorkingArea => (vec![0x08], vec![]),
+            Command::SetWorkingArea(area) => (vec![0x07], vec![area.to_code()]),
             Command::AcquireTransmitPower => (vec![0xB7], vec![]),
-            Command::SetTransmissionPower(p) => {
-                let power = (p * 100.0) as u16;
-                let mut v = Vec::new();
-                v.push((power >> 8) as u8);
-                v.push((power & 0xFF) as u8);
-                (vec![0xB6], v)
-            }
+            Command::SetTransmissionPower(p) => (vec![0xB6], dbm_to_raw(*p).to_vec()),
             Command::SinglePollingInstruction => (vec![0x22], vec![]),
             Command::MultiplePollingInstruction(max) => {
-                let mut v = Vec::new();
-                v.push((max >> 8) as u8);
-                v.push((max & 0xFF) as u8);
-                (vec![0x27], v)
+                (vec![0x27], vec![(max >> 8) as u8, (max & 0xFF) as u8])
             }
             Command::StopMultiplePollingInstruction => (vec![0x28], vec![]),
+            Command::ReadTagMemory {
+                bank,
+                word_addr,
+                word_count,
+                access_password,
+            } => {
+                let mut v = access_password.to_be_bytes().to_vec();
+                v.push(*bank as u8);
+                v.extend(word_addr.to_be_bytes());
+                v.extend(word_count.to_be_bytes());
+                (vec![0x39], v)
+            }
+            Command::WriteTagMemory {
+                bank,
+                word_addr,
+                words,
+                access_password,
+            } => {
+                let mut v = access_password.to_be_bytes().to_vec();
+                v.push(*bank as u8);
+                v.extend(word_addr.to_be_bytes());
+                v.extend((words.len() as u16).to_be_bytes());
+                for w in words {
+                    v.extend(w.to_be_bytes());
+                }
+                (vec![0x49], v)
+            }
+            Command::GetQueryParameters => (vec![0x0D], vec![]),
+            Command::SetQueryParameters(params) => {
+                (vec![0x0E], params.to_word().to_be_bytes().to_vec())
+            }
+            Command::GetSelect => (vec![0x0B], vec![]),
+            Command::SetSelect {
+                bank,
+                bit_pointer,
+                mask,
+                mask_bit_len,
+            } => {
+                // SelParam: Target (3 bits) and Action (3 bits) left to 0, MemBank (2 bits)
+                let mut v = vec![*bank as u8];
+                v.extend(bit_pointer.to_be_bytes());
+                v.push(*mask_bit_len);
+                v.push(0x00); // Truncate disabled
+                v.extend(&mask[..(*mask_bit_len as usize).div_ceil(8)]);
+                (vec![0x0C], v)
+            }
+            // 0x00 sends Select before every tag operation, 0x01 never sends it
+            Command::SetSelectMode(enabled) => {
+                (vec![0x12], vec![if *enabled { 0x00 } else { 0x01 }])
+            }
+            Command::SetAutoFrequencyHopping(enabled) => {
+                (vec![0xAD], vec![if *enabled { 0xFF } else { 0x00 }])
+            }
+            // Channel count, then the channel indexes
+            Command::InsertWorkingChannel(channels) => {
+                let mut params = vec![channels.len() as u8];
+                params.extend_from_slice(channels);
+                (vec![0xA9], params)
+            }
+            Command::KillTag(password) => (vec![0x65], password.to_be_bytes().to_vec()),
+            Command::ModuleSleep => (vec![0x17], vec![]),
+            // Enter, Reserved (fixed 0x01), IDLE time as in the protocol manual
+            Command::IdleMode(enter) => (vec![0x04], vec![u8::from(*enter), 0x01, 0x03]),
+            Command::SetRfCarrier(on) => (vec![0xB0], vec![if *on { 0xFF } else { 0x00 }]),
+            // Operation (0x00 direction, 0x01 level, 0x02 read), pin, value
+            Command::SetGpioDirection { pin, output } => {
+                (vec![0x1A], vec![0x00, *pin, u8::from(*output)])
+            }
+            Command::SetGpioLevel { pin, high } => (vec![0x1A], vec![0x01, *pin, u8::from(*high)]),
+            Command::ReadGpioLevel(pin) => (vec![0x1A], vec![0x02, *pin, 0x00]),
+            Command::SetBaudRate(baud) => {
+                (vec![0x11], ((baud / 100) as u16).to_be_bytes().to_vec())
+            }
+            Command::LockTag {
+                access_password,
+                lock_mask,
+            } => {
+                let mut v = access_password.to_be_bytes().to_vec();
+                // LD: 4 reserved bits then the 20 bit payload
+                v.extend(&lock_mask.payload().to_be_bytes()[1..]);
+                (vec![0x82], v)
+            }
+            Command::Raw { code, params } => (vec![*code], params.clone()),
         }
     }
 
     fn from_tuple(tuple: (Vec<u8>, Vec<u8>)) -> Result<Self, FrameError> {
-        match (tuple.0[0], tuple.1[0]) {
-            (INSTRUCTION_READER_WRITER_MODULE_INFO, READ_WRITE_INFO_HARDWARE_VERSION) => {
+        let Some(&code) = tuple.0.first() else {
+            return Err(FrameError::InvalidCommand("Missing command code".into()));
+        };
+        match (code, tuple.1.first().copied()) {
+            (INSTRUCTION_READER_WRITER_MODULE_INFO, Some(READ_WRITE_INFO_HARDWARE_VERSION)) => {
                 Ok(Command::HardwareVersion)
             }
-            (INSTRUCTION_READER_WRITER_MODULE_INFO, READ_WRITE_INFO_SOFTWARE_VERSION) => {
+            (INSTRUCTION_READER_WRITER_MODULE_INFO, Some(READ_WRITE_INFO_SOFTWARE_VERSION)) => {
                 Ok(Command::SoftwareVersion)
             }
-            (INSTRUCTION_READER_WRITER_MODULE_INFO, READ_WRITE_INFO_MANUFACTURER) => {
+            (INSTRUCTION_READER_WRITER_MODULE_INFO, Some(READ_WRITE_INFO_MANUFACTURER)) => {
                 Ok(Command::Manufacturer)
             }
-            (INSTRUCTION_READER_WRITER_MODULE_INFO, _) => Err(FrameError::InvalidCommand(format!(
-                "Invalid command code: {}",
-                tuple.1[0]
-            ))),
+            (INSTRUCTION_READER_WRITER_MODULE_INFO, param) => Err(FrameError::InvalidCommand(
+                format!("Invalid module information type: {:?}", param),
+            )),
             (0xAA, _) => Ok(Command::GetWorkingChannel),
             (0x08, _) => Ok(Command::GetWorkingArea),
             (0xB7, _) => Ok(Command::AcquireTransmitPower),
             (0x28, _) => Ok(Command::StopMultiplePollingInstruction),
+            (0x0B, _) => Ok(Command::GetSelect),
+            (0x0D, _) => Ok(Command::GetQueryParameters),
             _ => Err(FrameError::InvalidCommand(format!(
                 "Invalid command code: {}",
-                tuple.0[0]
+                code
             ))),
         }
     }
 }
 
-pub(crate) struct Frame {
+/// A command frame ready to be written to the reader: header, type 0x00, command code,
+/// parameter length, parameters, checksum and end byte
+pub struct Frame {
     payload: Vec<u8>,
 }
 
 impl Frame {
-    pub(crate) fn new(payload: &Command) -> Self {
-        let mut v = Vec::new();
-        // command
-        v.extend(payload.to_bytes().0);
-        let payload_size = payload.to_bytes().1.len() as u16;
+    pub fn new(payload: &impl SerializableCommand) -> Self {
+        let (command, params) = payload.to_bytes();
+        let mut v = command;
+        let payload_size = params.len() as u16;
         v.push((payload_size >> 8) as u8);
         v.push((payload_size & 0xFF) as u8);
-        v.extend(payload.to_bytes().1);
+        v.extend(params);
 
         Frame { payload: v }
     }
 
-    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+    pub fn to_bytes(&self) -> Vec<u8> {
         let mut v = Vec::new();
         v.push(R200_FRAME_HEADER);
         v.push(FRAME_TYPE_SEND_COMMAND);
 
         v.extend(&self.payload);
 
-        v.push(self.checksum(&v[2..]));
+        v.push(r200_checksum(&v[1..]));
         v.push(R200_FRAME_END);
         v
     }
+}
 
-    fn checksum(&self, bytes: &[u8]) -> u8 {
-        let sum: u16 = bytes.iter().map(|&b| b as u16).sum();
-        (sum & 0xFF) as u8
-    }
+/// Checksum of an R200 frame: the low byte of the sum of `bytes`.
+///
+/// Pass the frame from the type byte up to the last data byte, i.e. without the header,
+/// checksum and end bytes. The type of a command frame is 0x00, so starting from the
+/// command code gives the same value.
+///
+/// ```
+/// // Get working area: AA 00 08 00 00 08 DD
+/// assert_eq!(r200_uhf::r200_checksum(&[0x00, 0x08, 0x00, 0x00]), 0x08);
+/// ```
+pub fn r200_checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |sum, &b| sum.wrapping_add(b))
+}
+
+/// Transmit power parameter of the set and acquire power commands: the power in dBm
+/// times 100, big endian.
+///
+/// The value is rounded to the nearest 0.01 dBm, so 23.55 dBm is sent as 2355 even though
+/// `23.55 * 100.0` is slightly below it. Negative powers give 0, powers over 655.35 dBm
+/// saturate.
+///
+/// ```
+/// assert_eq!(r200_uhf::dbm_to_raw(23.55), [0x09, 0x33]);
+/// assert_eq!(r200_uhf::raw_to_dbm([0x09, 0x33]), 23.55);
+/// ```
+pub fn dbm_to_raw(dbm: f64) -> [u8; 2] {
+    (libm::round(dbm * 100.0) as u16).to_be_bytes()
+}
+
+/// Power in dBm of the 2-byte transmit power parameter, see [`dbm_to_raw`]
+pub fn raw_to_dbm(raw: [u8; 2]) -> f64 {
+    u16::from_be_bytes(raw) as f64 / 100.0
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::connector::{LockAction, Sel, Session, Target};
+
+    #[test]
+    fn r200_checksum_wraps_to_low_byte() {
+        // Manual: multiple polling of 10000 rounds, AA 00 27 00 03 22 27 10 83 DD
+        assert_eq!(
+            r200_checksum(&[0x00, 0x27, 0x00, 0x03, 0x22, 0x27, 0x10]),
+            0x83
+        );
+        assert_eq!(r200_checksum(&[0xFF; 300]), (0xFF * 300 % 256) as u8);
+    }
 
     fn frame_bytes(cmd: Command) -> Vec<u8> {
         Frame::new(&cmd).to_bytes()
@@ -207,6 +498,13 @@ mod tests {
         assert_eq!(bytes, expected);
     }
 
+    #[test]
+    fn set_working_channel_frame_bytes() {
+        let bytes = frame_bytes(Command::SetWorkingChannel(0x01));
+        let expected = vec![0xAA, 0x00, 0xAB, 0x00, 0x01, 0x01, 0xAD, 0xDD];
+        assert_eq!(bytes, expected);
+    }
+
     #[test]
     fn single_polling_instruction_frame_bytes() {
         let bytes = frame_bytes(Command::SinglePollingInstruction);
@@ -229,6 +527,245 @@ mod tests {
         assert_eq!(bytes, expected);
     }
 
+    #[test]
+    fn dbm_to_raw_rounds() {
+        // 23.55 * 100.0 is 2354.9999..., truncating it sent 23.54 dBm
+        assert_eq!(dbm_to_raw(23.55), 2355u16.to_be_bytes());
+        assert_eq!(dbm_to_raw(20.0), [0x07, 0xD0]);
+        assert_eq!(dbm_to_raw(-1.0), [0x00, 0x00]);
+        for dbm in [15.0, 18.5, 23.55, 26.0] {
+            assert_eq!(raw_to_dbm(dbm_to_raw(dbm)), dbm);
+        }
+    }
+
+    #[test]
+    fn set_working_area_frame_bytes() {
+        // Example from the protocol manual uses region 0x01, we check EU (0x03)
+        let bytes = frame_bytes(Command::SetWorkingArea(WorkingArea::EU));
+        let expected = vec![0xAA, 0x00, 0x07, 0x00, 0x01, 0x03, 0x0B, 0xDD];
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn read_tag_memory_frame_bytes() {
+        // Example from the protocol manual: read 2 words of the User bank with password 0x0000FFFF
+        let bytes = frame_bytes(Command::ReadTagMemory {
+            bank: MemoryBank::User,
+            word_addr: 0x0000,
+            word_count: 0x0002,
+            access_password: 0x0000FFFF,
+        });
+        let expected = vec![
+            0xAA, 0x00, 0x39, 0x00, 0x09, 0x00, 0x00, 0xFF, 0xFF, 0x03, 0x00, 0x00, 0x00, 0x02,
+            0x45, 0xDD,
+        ];
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn write_tag_memory_frame_bytes() {
+        // Example from the protocol manual: write 0x12345678 at the start of the User bank
+        let bytes = frame_bytes(Command::WriteTagMemory {
+            bank: MemoryBank::User,
+            word_addr: 0x0000,
+            words: vec![0x1234, 0x5678],
+            access_password: 0x0000FFFF,
+        });
+        let expected = vec![
+            0xAA, 0x00, 0x49, 0x00, 0x0D, 0x00, 0x00, 0xFF, 0xFF, 0x03, 0x00, 0x00, 0x00, 0x02,
+            0x12, 0x34, 0x56, 0x78, 0x6D, 0xDD,
+        ];
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn set_query_parameters_frame_bytes() {
+        // Example from the protocol manual: Sel=ALL, Session=S0, Target=A, Q=4
+        let bytes = frame_bytes(Command::SetQueryParameters(QueryParameters {
+            sel: Sel::All,
+            session: Session::S0,
+            target: Target::A,
+            q: 4,
+        }));
+        assert_eq!(
+            bytes,
+            vec![0xAA, 0x00, 0x0E, 0x00, 0x02, 0x10, 0x20, 0x40, 0xDD]
+        );
+    }
+
+    #[test]
+    fn set_select_frame_bytes() {
+        // Example from the protocol manual: 96 bit EPC mask starting at bit 0x20
+        let mask = vec![
+            0x30, 0x75, 0x1F, 0xEB, 0x70, 0x5C, 0x59, 0x04, 0xE3, 0xD5, 0x0D, 0x70,
+        ];
+        let bytes = frame_bytes(Command::SetSelect {
+            bank: MemoryBank::Epc,
+            bit_pointer: 0x20,
+            mask: mask.clone(),
+            mask_bit_len: 0x60,
+        });
+        let mut expected = vec![
+            0xAA, 0x00, 0x0C, 0x00, 0x13, 0x01, 0x00, 0x00, 0x00, 0x20, 0x60, 0x00,
+        ];
+        expected.extend(mask);
+        expected.extend([0xAD, 0xDD]);
+        assert_eq!(bytes, expected);
+
+        // Cancel the select from the manual: AA 00 12 00 01 01 14 DD
+        assert_eq!(
+            frame_bytes(Command::SetSelectMode(false)),
+            vec![0xAA, 0x00, 0x12, 0x00, 0x01, 0x01, 0x14, 0xDD]
+        );
+    }
+
+    #[test]
+    fn set_auto_frequency_hopping_frame_bytes() {
+        assert_eq!(
+            frame_bytes(Command::SetAutoFrequencyHopping(true)),
+            vec![0xAA, 0x00, 0xAD, 0x00, 0x01, 0xFF, 0xAD, 0xDD]
+        );
+        assert_eq!(
+            frame_bytes(Command::SetAutoFrequencyHopping(false)),
+            vec![0xAA, 0x00, 0xAD, 0x00, 0x01, 0x00, 0xAE, 0xDD]
+        );
+    }
+
+    #[test]
+    fn insert_working_channel_frame_bytes() {
+        // Example from the protocol manual
+        assert_eq!(
+            frame_bytes(Command::InsertWorkingChannel(vec![1, 2, 3, 4, 5])),
+            vec![
+                0xAA, 0x00, 0xA9, 0x00, 0x06, 0x05, 0x01, 0x02, 0x03, 0x04, 0x05, 0xC3, 0xDD
+            ]
+        );
+        assert_eq!(
+            frame_bytes(Command::InsertWorkingChannel(vec![])),
+            vec![0xAA, 0x00, 0xA9, 0x00, 0x01, 0x00, 0xAA, 0xDD]
+        );
+    }
+
+    #[test]
+    fn kill_tag_frame_bytes() {
+        // Example from the protocol manual: kill password 0x0000FFFF
+        assert_eq!(
+            frame_bytes(Command::KillTag(0x0000FFFF)),
+            vec![
+                0xAA, 0x00, 0x65, 0x00, 0x04, 0x00, 0x00, 0xFF, 0xFF, 0x67, 0xDD
+            ]
+        );
+    }
+
+    #[test]
+    fn set_baud_rate_frame_bytes() {
+        // Example from the protocol manual: 19200 baud
+        assert_eq!(
+            frame_bytes(Command::SetBaudRate(19200)),
+            vec![0xAA, 0x00, 0x11, 0x00, 0x02, 0x00, 0xC0, 0xD3, 0xDD]
+        );
+    }
+
+    #[test]
+    fn module_sleep_frame_bytes() {
+        assert_eq!(
+            frame_bytes(Command::ModuleSleep),
+            vec![0xAA, 0x00, 0x17, 0x00, 0x00, 0x17, 0xDD]
+        );
+    }
+
+    #[test]
+    fn idle_mode_frame_bytes() {
+        // Example from the protocol manual
+        assert_eq!(
+            frame_bytes(Command::IdleMode(true)),
+            vec![0xAA, 0x00, 0x04, 0x00, 0x03, 0x01, 0x01, 0x03, 0x0C, 0xDD]
+        );
+    }
+
+    #[test]
+    fn set_rf_carrier_frame_bytes() {
+        assert_eq!(
+            frame_bytes(Command::SetRfCarrier(true)),
+            vec![0xAA, 0x00, 0xB0, 0x00, 0x01, 0xFF, 0xB0, 0xDD]
+        );
+        assert_eq!(
+            frame_bytes(Command::SetRfCarrier(false)),
+            vec![0xAA, 0x00, 0xB0, 0x00, 0x01, 0x00, 0xB1, 0xDD]
+        );
+    }
+
+    #[test]
+    fn gpio_frame_bytes() {
+        // Example from the protocol manual: IO4 as output
+        assert_eq!(
+            frame_bytes(Command::SetGpioDirection {
+                pin: 4,
+                output: true
+            }),
+            vec![0xAA, 0x00, 0x1A, 0x00, 0x03, 0x00, 0x04, 0x01, 0x22, 0xDD]
+        );
+        assert_eq!(
+            frame_bytes(Command::ReadGpioLevel(1)),
+            vec![0xAA, 0x00, 0x1A, 0x00, 0x03, 0x02, 0x01, 0x00, 0x20, 0xDD]
+        );
+    }
+
+    #[test]
+    fn lock_tag_frame_bytes() {
+        let bytes = frame_bytes(Command::LockTag {
+            access_password: 0x0000FFFF,
+            lock_mask: LockMask::new().access_password(LockAction::Lock),
+        });
+        assert_eq!(
+            bytes,
+            vec![
+                0xAA, 0x00, 0x82, 0x00, 0x07, 0x00, 0x00, 0xFF, 0xFF, 0x03, 0x00, 0x80, 0x0A, 0xDD
+            ]
+        );
+    }
+
+    #[test]
+    fn lock_mask_payload_round_trip() {
+        let mask = LockMask::new()
+            .kill_password(LockAction::PermaLock)
+            .tid(LockAction::PermaUnlock)
+            .user(LockAction::Unlock);
+        assert_eq!(mask.payload(), 0xC3F04);
+        assert_eq!(LockMask::from_payload(0xC3F04).unwrap(), mask);
+        // Action bit set for the EPC area while its mask is clear
+        assert!(LockMask::from_payload(0x00020).is_err());
+        assert!(LockMask::from_payload(0x100000).is_err());
+    }
+
+    #[test]
+    fn raw_frame_bytes() {
+        // Same bytes as the manual's get query parameters frame
+        let bytes = frame_bytes(Command::Raw {
+            code: 0x0D,
+            params: vec![],
+        });
+        assert_eq!(bytes, vec![0xAA, 0x00, 0x0D, 0x00, 0x00, 0x0D, 0xDD]);
+        let bytes = frame_bytes(Command::Raw {
+            code: 0xB6,
+            params: vec![0x07, 0xD0],
+        });
+        assert_eq!(bytes, frame_bytes(Command::SetTransmissionPower(20.0)));
+    }
+
+    #[test]
+    fn query_parameters_word_round_trip() {
+        let params = QueryParameters {
+            sel: Sel::Sl,
+            session: Session::S2,
+            target: Target::B,
+            q: 15,
+        };
+        assert_eq!(params.to_word(), 0x1EF8);
+        assert_eq!(QueryParameters::from_word(0x1EF8), params);
+        assert_eq!(QueryParameters::from_word(0x1020).q, 4);
+    }
+
     #[test]
     fn serializable_command_to_bytes_and_from_tuple() {
         // to_bytes
@@ -282,17 +819,38 @@ mod tests {
     #[test]
     fn from_tuple_invalid_command_errors() {
         // Unknown subcode for module info
-        let err = Command::from_tuple((vec![0x03], vec![0xFF]))
-            .err()
-            .expect("expected error");
+        let err = Command::from_tuple((vec![0x03], vec![0xFF])).expect_err("expected error");
         let msg = format!("{}", err);
         assert!(msg.contains("Invalid command"));
 
         // Unknown main code
-        let err = Command::from_tuple((vec![0x99], vec![0x00]))
-            .err()
-            .expect("expected error");
+        let err = Command::from_tuple((vec![0x99], vec![0x00])).expect_err("expected error");
         let msg = format!("{}", err);
         assert!(msg.contains("Invalid command"));
     }
+
+    #[test]
+    fn from_tuple_missing_bytes_errors() {
+        assert!(Command::from_tuple((vec![], vec![])).is_err());
+        assert!(Command::from_tuple((vec![0x03], vec![])).is_err());
+        assert!(matches!(
+            Command::from_tuple((vec![0xAA], vec![])),
+            Ok(Command::GetWorkingChannel)
+        ));
+    }
+
+    #[test]
+    fn custom_command_only_needs_to_bytes() {
+        struct Beep;
+        impl SerializableCommand for Beep {
+            fn to_bytes(&self) -> (Vec<u8>, Vec<u8>) {
+                (vec![0x1A], vec![0x01])
+            }
+        }
+        assert_eq!(
+            Frame::new(&Beep).to_bytes(),
+            [0xAA, 0x00, 0x1A, 0x00, 0x01, 0x01, 0x1C, 0xDD]
+        );
+        assert!(Beep::from_tuple((vec![0x1A], vec![0x01])).is_err());
+    }
 }
@@ -1,6 +1,16 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 pub mod connector;
 mod frame;
+mod logging;
 mod packet;
+pub mod protocol;
 mod rfid;
 
-pub use rfid::Rfid;
+pub use frame::{FrameType, dbm_to_raw, r200_checksum, raw_to_dbm};
+pub use packet::{FrameDecoder, Packet};
+pub use rfid::{ByEpc, ParseError, PcInfo, Rfid, TagObservation};
+#[cfg(feature = "std")]
+pub use rfid::{TagSet, TimedRfid};
@@ -0,0 +1,38 @@
+//! Logging shim: messages go to the `log` crate, or to `defmt` with the `defmt` feature on
+//! bare metal targets, where `log` usually has no logger to print to.
+//!
+//! defmt formats on the host, so the message is formatted with `core::fmt` on the target and
+//! sent as a single string.
+
+#[cfg(all(feature = "defmt", target_os = "none"))]
+macro_rules! emit {
+    ($level:ident, $($arg:tt)*) => {
+        defmt::$level!("{=str}", alloc::format!($($arg)*).as_str())
+    };
+}
+
+#[cfg(not(all(feature = "defmt", target_os = "none")))]
+macro_rules! emit {
+    ($level:ident, $($arg:tt)*) => {
+        log::$level!($($arg)*)
+    };
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => { $crate::logging::emit!(debug, $($arg)*) };
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => { $crate::logging::emit!(info, $($arg)*) };
+}
+
+// Named apart and renamed on export, `warn` alone clashes with the built-in attribute
+macro_rules! warning {
+    ($($arg:tt)*) => { $crate::logging::emit!(warn, $($arg)*) };
+}
+
+macro_rules! error {
+    ($($arg:tt)*) => { $crate::logging::emit!(error, $($arg)*) };
+}
+
+pub(crate) use {debug, emit, error, info, warning as warn};
@@ -1,8 +1,18 @@
+use crate::connector::RESPONSE_COMMAND_FAILURE;
 use crate::frame::SerializableCommand;
-use crate::frame::{Command, FrameError};
-use std::fmt::Display;
+use crate::frame::{
+    Command, FrameError, FrameType, R200_FRAME_END, R200_FRAME_HEADER, r200_checksum,
+};
+use crate::logging::warn;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Display;
 
+/// A frame received from the device
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug)]
 pub struct Packet {
     raw_data: Vec<u8>,
 }
@@ -11,34 +21,112 @@ impl Packet {
     pub(crate) fn new(raw_data: Vec<u8>) -> Packet {
         Packet { raw_data }
     }
-    fn frame_type(&self) -> u8 {
-        self.raw_data[1]
+    /// Frame type: 0x01 for a response, 0x02 for a notification, 0 for a frame too short
+    /// to carry one
+    pub fn frame_type(&self) -> u8 {
+        self.raw_data.get(1).copied().unwrap_or(0)
     }
-    fn command_code(&self) -> u8 {
-        self.raw_data[2]
+    /// Decoded frame type
+    pub fn kind(&self) -> FrameType {
+        FrameType::from_code(self.frame_type())
     }
+    /// True for a frame the device sent on its own rather than in answer to a command
+    pub fn is_notification(&self) -> bool {
+        self.kind() == FrameType::Notification
+    }
+    /// Command code of the frame, 0xFF for an execution failure, 0 for a frame too short
+    /// to carry one
+    pub fn command_code(&self) -> u8 {
+        self.raw_data.get(2).copied().unwrap_or(0)
+    }
+    /// Length declared by the frame, 0 for a frame too short to carry one
     fn data_len(&self) -> u16 {
-        ((self.raw_data[3] as u16) << 8) | (self.raw_data[4] as u16)
+        match self.raw_data.get(3..5) {
+            Some(len) => ((len[0] as u16) << 8) | (len[1] as u16),
+            None => 0,
+        }
     }
 
-    pub(crate) fn get_data(&self) -> Vec<u8> {
-        let data = &self.raw_data[5..(5 + self.data_len() as usize)];
-        data.to_vec()
+    /// The whole frame as received, from the 0xAA header to the 0xDD end byte
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.raw_data
+    }
+
+    /// Parameters carried by the frame.
+    ///
+    /// A corrupt length field larger than the frame (a deserialized or hand-built Packet,
+    /// frames read from the port are checked) is clamped to the bytes before the checksum.
+    pub fn get_data(&self) -> Vec<u8> {
+        let start = self.raw_data.len().min(5);
+        // Checksum and end byte follow the data
+        let available = self.raw_data.len().saturating_sub(2).max(start);
+        let end = start + self.data_len() as usize;
+        if end > available {
+            warn!(
+                "Frame declares {} data bytes, only {} received",
+                self.data_len(),
+                available - start
+            );
+        }
+        self.raw_data[start..end.min(available)].to_vec()
     }
 
-    /// Check if packet is valid
+    /// Check if packet is valid: header and end markers, declared length and checksum
     pub fn is_valid(&self) -> bool {
+        // Header, type, command, length (2), checksum and end at least
+        if self.raw_data.len() < 7 {
+            return false;
+        }
+        if self.raw_data[0] != R200_FRAME_HEADER || self.raw_data.last() != Some(&R200_FRAME_END) {
+            return false;
+        }
         // If length is incorrect with what is expected
         if 5 + 2 + self.data_len() as usize != self.raw_data.len() {
             return false;
         }
-        // Validate checksum: sum of bytes from index 1 (type) to second-to-last byte
-        let cs_pos = self.raw_data.len() - 2;
-        let sum: u16 = self.raw_data[1..cs_pos].iter().map(|&b| b as u16).sum();
-        if (sum & 0xFF) as u8 != self.raw_data[cs_pos] {
+        self.verify_checksum()
+    }
+
+    /// Check the checksum byte that precedes the frame end.
+    ///
+    /// The checksum is the low byte of the sum of every byte from the frame type
+    /// (index 1) up to the last data byte, as described by the R200 protocol.
+    pub fn verify_checksum(&self) -> bool {
+        if self.raw_data.len() < 7 {
             return false;
         }
-        true
+        let cs_pos = self.raw_data.len() - 2;
+        r200_checksum(&self.raw_data[1..cs_pos]) == self.raw_data[cs_pos]
+    }
+
+    /// Error code carried by an execution failure frame (command 0xFF), None for any other frame
+    pub fn as_error(&self) -> Option<u8> {
+        if self.command_code() != RESPONSE_COMMAND_FAILURE {
+            return None;
+        }
+        self.get_data().first().copied()
+    }
+
+    /// Decode every valid frame found in `buf`, in order.
+    ///
+    /// Bytes outside of a frame and frames with a wrong length, end marker or checksum are
+    /// skipped, as is a frame cut short by the end of the buffer. Useful to replay captured
+    /// serial traffic without a device.
+    pub fn parse_all(buf: &[u8]) -> Vec<Packet> {
+        let mut decoder = FrameDecoder::new();
+        decoder.push(buf);
+        let mut packets = Vec::new();
+        loop {
+            while let Some(p) = decoder.next_packet() {
+                packets.push(p);
+            }
+            // What is left is a header whose frame runs past the end, or no header at all.
+            // With no more bytes to come the header is noise: skip it and keep looking.
+            if decoder.pending.first() != Some(&R200_FRAME_HEADER) {
+                return packets;
+            }
+            decoder.pending.remove(0);
+        }
     }
 
     pub(crate) fn debug(&self) -> String {
@@ -52,20 +140,104 @@ impl Packet {
     }
 
     pub(crate) fn command(&self) -> Result<Command, FrameError> {
-        Command::from_tuple((vec![self.command_code()], vec![self.raw_data[5]]))
+        let param = self.get_data().first().copied().unwrap_or_default();
+        Command::from_tuple((vec![self.command_code()], vec![param]))
     }
 }
 
-impl Display for Packet {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let out = {
-            if let Ok(text) = std::str::from_utf8(&*self.get_data()) {
-                text.to_string()
-            } else {
-                "Invalid UTF-8".to_string()
+/// Longest data field accepted in a frame. The longest R200 frame is the answer to a tag
+/// memory read, and `read_tag_memory` rejects reads of more than `MAX_READ_WORDS` words so
+/// that it fits: a larger length comes from a noise byte taken for a header.
+pub(crate) const MAX_FRAME_DATA_LEN: usize = 512;
+
+/// Decoder of the frames in a byte stream, independent of the serial port.
+///
+/// Bytes are added with `push` as they arrive and complete frames come out of `next_packet`
+/// in order, the way the connector reads them: anything before a header and frames with a
+/// wrong length, end marker or checksum are skipped, a partial frame waits for the rest.
+/// A header declaring more than 512 data bytes is noise and is skipped too.
+#[derive(Clone, Debug, Default)]
+pub struct FrameDecoder {
+    pending: Vec<u8>,
+    cap: Option<usize>,
+}
+
+impl FrameDecoder {
+    pub fn new() -> FrameDecoder {
+        FrameDecoder::default()
+    }
+
+    /// Decoder that drops the oldest pending bytes once more than twice `cap` are waiting,
+    /// keeping the last `cap`. Bounds the memory used by a stream that never completes a frame.
+    pub fn with_cap(cap: usize) -> FrameDecoder {
+        FrameDecoder {
+            pending: Vec::with_capacity(cap),
+            cap: Some(cap),
+        }
+    }
+
+    /// Append bytes received from the stream
+    pub fn push(&mut self, bytes: &[u8]) {
+        if let Some(cap) = self.cap
+            && self.pending.len() > 2 * cap
+        {
+            self.pending.drain(..self.pending.len() - cap);
+        }
+        self.pending.extend_from_slice(bytes);
+    }
+
+    /// Take the next complete frame, None until more bytes are pushed.
+    ///
+    /// The frame length comes from its length field, so an end byte inside the data does not
+    /// cut the frame short.
+    pub fn next_packet(&mut self) -> Option<Packet> {
+        self.next_packet_with(|_| {})
+    }
+
+    /// Same as `next_packet`, calling `on_invalid` with every frame dropped on the way
+    pub(crate) fn next_packet_with(&mut self, mut on_invalid: impl FnMut(&[u8])) -> Option<Packet> {
+        let rolling = &mut self.pending;
+        loop {
+            let start = rolling.iter().position(|&b| b == R200_FRAME_HEADER)?;
+            rolling.drain(..start);
+            if rolling.len() < 5 {
+                return None;
+            }
+            let data_len = ((rolling[3] as usize) << 8) | rolling[4] as usize;
+            if data_len > MAX_FRAME_DATA_LEN {
+                warn!("Skipping header of a {} byte frame", data_len);
+                rolling.remove(0);
+                continue;
             }
-        };
-        write!(f, "{out}")
+            let frame_len = 7 + data_len;
+            if rolling.len() < frame_len {
+                return None;
+            }
+            let p = Packet::new(rolling[..frame_len].to_vec());
+            if p.is_valid() {
+                rolling.drain(..frame_len);
+                return Some(p);
+            }
+            warn!("Dropping invalid frame: {:?}", &rolling[..frame_len]);
+            on_invalid(&rolling[..frame_len]);
+            // Not a real frame start, look for the next header
+            rolling.remove(0);
+        }
+    }
+
+    /// Bytes pushed and not decoded yet: a partial frame or noise
+    pub fn pending(&self) -> &[u8] {
+        &self.pending
+    }
+}
+
+/// Data of the frame as text. Invalid UTF-8 sequences show as U+FFFD and the NUL padding
+/// at the end of version strings is trimmed.
+impl Display for Packet {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let data = self.get_data();
+        let text = String::from_utf8_lossy(&data);
+        write!(f, "{}", text.trim_end_matches('\0'))
     }
 }
 
@@ -76,12 +248,13 @@ mod tests {
     // Helper to build a raw packet vector: [HEADER, TYPE, CMD, LEN_HI, LEN_LO, DATA..., CHECKSUM, END]
     fn build_packet(frame_type: u8, cmd: u8, data: &[u8]) -> Vec<u8> {
         let len = data.len() as u16;
-        let mut v = Vec::new();
-        v.push(crate::frame::R200_FRAME_HEADER);
-        v.push(frame_type);
-        v.push(cmd);
-        v.push((len >> 8) as u8);
-        v.push((len & 0xFF) as u8);
+        let mut v = vec![
+            crate::frame::R200_FRAME_HEADER,
+            frame_type,
+            cmd,
+            (len >> 8) as u8,
+            (len & 0xFF) as u8,
+        ];
         v.extend_from_slice(data);
         // checksum is sum of bytes from index 1 (type) to last data byte, low 8 bits
         let sum: u16 = v[1..].iter().map(|&b| b as u16).sum();
@@ -105,6 +278,40 @@ mod tests {
         assert!(dbg.contains("Lunghezza: 1"));
     }
 
+    #[test]
+    fn get_data_clamps_oversized_length() {
+        // Declares 0x20 data bytes, carries 2
+        let mut raw = build_packet(0x01, 0xB7, &[0x07, 0xD0]);
+        raw[4] = 0x20;
+        let p = Packet::new(raw);
+        assert!(!p.is_valid());
+        assert_eq!(p.get_data(), vec![0x07, 0xD0]);
+        // Cut inside the header
+        assert!(
+            Packet::new(vec![0xAA, 0x01, 0xB7, 0x00])
+                .get_data()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn packet_kind() {
+        let response = Packet::new(build_packet(0x01, 0xB7, &[0x07, 0xD0]));
+        assert_eq!(response.kind(), FrameType::Response);
+        assert!(!response.is_notification());
+        let tag = Packet::new(build_packet(0x02, 0x22, &[0xC9]));
+        assert!(tag.is_notification());
+        let other = Packet::new(build_packet(0x05, 0x22, &[]));
+        assert_eq!(other.kind(), FrameType::Unknown(0x05));
+        // A deserialized Packet may be cut anywhere
+        let short = Packet::new(vec![0xAA]);
+        assert_eq!(short.frame_type(), 0);
+        assert_eq!(short.command_code(), 0);
+        assert_eq!(short.kind(), FrameType::Command);
+        assert!(!short.is_notification());
+        assert_eq!(short.as_error(), None);
+    }
+
     #[test]
     fn display_outputs_utf8_data() {
         let raw = build_packet(0x00, 0x22, b"OK");
@@ -116,7 +323,11 @@ mod tests {
     fn display_handles_invalid_utf8() {
         let raw = build_packet(0x00, 0x22, &[0xFF]);
         let p = Packet::new(raw);
-        assert_eq!(format!("{}", p), "Invalid UTF-8");
+        assert_eq!(format!("{}", p), "\u{FFFD}");
+
+        let raw = build_packet(0x01, 0x03, b"M100\xFF V1.0\0\0");
+        let p = Packet::new(raw);
+        assert_eq!(format!("{}", p), "M100\u{FFFD} V1.0");
     }
 
     #[test]
@@ -154,4 +365,122 @@ mod tests {
         let p = Packet::new(incorrect_bytes);
         assert!(!p.is_valid());
     }
+
+    #[test]
+    fn packet_validity_checks_checksum_and_markers() {
+        let correct_bytes = build_packet(0x01, 0xB7, &[0x07, 0xD0]);
+
+        // Right length, wrong checksum
+        let mut wrong_checksum = correct_bytes.clone();
+        let cs_pos = wrong_checksum.len() - 2;
+        wrong_checksum[cs_pos] ^= 0xFF;
+        assert!(!Packet::new(wrong_checksum).is_valid());
+
+        let mut wrong_end = correct_bytes.clone();
+        *wrong_end.last_mut().unwrap() = 0x00;
+        assert!(!Packet::new(wrong_end).is_valid());
+
+        let mut wrong_header = correct_bytes;
+        wrong_header[0] = 0x00;
+        assert!(!Packet::new(wrong_header).is_valid());
+
+        // Too short to hold even an empty frame
+        assert!(!Packet::new(vec![0xAA, 0x01, 0xB7, 0x00, 0xDD]).is_valid());
+        assert!(!Packet::new(vec![]).is_valid());
+    }
+
+    #[test]
+    fn packet_as_error() {
+        // Inventory fail from the manual: AA 01 FF 00 01 15 16 DD
+        let p = Packet::new(build_packet(0x01, 0xFF, &[0x15]));
+        assert_eq!(p.as_error(), Some(0x15));
+        let p = Packet::new(build_packet(0x01, 0xB7, &[0x07, 0xD0]));
+        assert_eq!(p.as_error(), None);
+    }
+
+    #[test]
+    fn parse_all_skips_noise() {
+        let first = build_packet(0x01, 0x28, &[0x00]);
+        // End marker inside the data
+        let second = build_packet(0x02, 0x22, &[0xC9, 0xDD, 0xAA]);
+        let mut corrupted = build_packet(0x01, 0xB7, &[0x07, 0xD0]);
+        corrupted[5] ^= 0x01;
+
+        let mut buf = vec![0x00, 0x13, 0xDD];
+        buf.extend(&first);
+        // Stray header announcing a frame longer than the rest of the buffer
+        buf.extend([0xAA, 0x01, 0x22, 0x01, 0x00]);
+        buf.extend(&corrupted);
+        buf.extend(&second);
+        // Frame cut short by the end of the capture
+        buf.extend(&first[..4]);
+
+        let packets = Packet::parse_all(&buf);
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].command_code(), 0x28);
+        assert_eq!(packets[1].frame_type(), 0x02);
+        assert_eq!(packets[1].get_data(), vec![0xC9, 0xDD, 0xAA]);
+
+        assert!(Packet::parse_all(&[]).is_empty());
+        assert!(Packet::parse_all(&[0xAA, 0xAA, 0xDD]).is_empty());
+    }
+
+    #[test]
+    fn frame_decoder_across_pushes() {
+        let first = build_packet(0x01, 0x03, &[0x00, 0x41]);
+        let second = build_packet(0x02, 0x22, &[0xC9, 0x30]);
+        let mut stream = vec![0x13, 0x37];
+        stream.extend(&first);
+        stream.extend(&second);
+
+        let mut decoder = FrameDecoder::new();
+        let (head, tail) = stream.split_at(stream.len() - 3);
+        decoder.push(head);
+        assert_eq!(decoder.next_packet().unwrap().raw_data, first);
+        assert!(decoder.next_packet().is_none());
+        assert_eq!(decoder.pending(), &second[..second.len() - 3]);
+        decoder.push(tail);
+        assert_eq!(decoder.next_packet().unwrap().raw_data, second);
+        assert!(decoder.pending().is_empty());
+    }
+
+    #[test]
+    fn frame_decoder_skips_oversized_header() {
+        let frame = build_packet(0x01, 0xB7, &[0x07, 0xD0]);
+        // A noise 0xAA followed by bytes read as a 0xFFFF length
+        let mut stream = vec![0xAA, 0x00, 0x13, 0xFF, 0xFF];
+        stream.extend(&frame);
+
+        let mut decoder = FrameDecoder::new();
+        decoder.push(&stream);
+        assert_eq!(decoder.next_packet().unwrap().raw_data, frame);
+        assert!(decoder.pending().is_empty());
+    }
+
+    #[test]
+    fn frame_decoder_with_cap_drops_oldest() {
+        let mut decoder = FrameDecoder::with_cap(4);
+        decoder.push(&[0xAA, 0x01, 0x03, 0x00, 0x10, 1, 2, 3, 4]);
+        decoder.push(&[5]);
+        assert_eq!(decoder.pending(), &[1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn packet_checksum_verification() {
+        let correct_bytes = build_packet(0x01, 0x28, &[0x00]);
+        // Response to stop multiple polling from the manual: AA 01 28 00 01 00 2A DD
+        assert_eq!(
+            correct_bytes,
+            vec![0xAA, 0x01, 0x28, 0x00, 0x01, 0x00, 0x2A, 0xDD]
+        );
+        assert!(Packet::new(correct_bytes.clone()).verify_checksum());
+
+        let mut corrupted = correct_bytes.clone();
+        corrupted[5] = 0x01;
+        let p = Packet::new(corrupted);
+        assert!(!p.verify_checksum());
+        assert!(!p.is_valid());
+
+        assert!(!Packet::new(vec![0xAA, 0x01, 0xDD]).verify_checksum());
+    }
 }
@@ -0,0 +1,19 @@
+//! Low-level building blocks of the R200 serial protocol.
+//!
+//! `Connector` covers the instructions of the protocol manual; these types are what it is
+//! built on, to encode frames in your own code or send a command the library does not wrap
+//! yet. Implement [`SerializableCommand`] for a type of your own and encode it with
+//! [`Frame`], or send the bytes through `SyncIO::send_raw`.
+//!
+//! ```
+//! use r200_uhf::protocol::{Command, Frame};
+//!
+//! // Get working area: AA 00 08 00 00 08 DD
+//! let frame = Frame::new(&Command::GetWorkingArea).to_bytes();
+//! assert_eq!(frame, [0xAA, 0x00, 0x08, 0x00, 0x00, 0x08, 0xDD]);
+//! ```
+
+pub use crate::frame::{
+    Command, Frame, FrameError, FrameType, R200_FRAME_END, R200_FRAME_HEADER, SerializableCommand,
+    dbm_to_raw, r200_checksum, raw_to_dbm,
+};
@@ -1,5 +1,9 @@
-use std::fmt::Display;
-use std::hash::Hash;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Display;
+use core::hash::Hash;
 
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Debug)]
@@ -8,38 +12,169 @@ pub struct Rfid {
     pub pc: String,
     pub epc: String, // also known as the tag UID
     pub crc: String,
+    /// TID backscattered with the EPC by tags with FastID enabled, see `inventory_with_tid`
+    pub tid: Option<String>,
+    /// Antenna port that saw the tag, only reported by multi-antenna readers,
+    /// see `Connector::set_multi_antenna`
+    pub antenna: Option<u8>,
+    #[cfg_attr(feature = "serde", serde(with = "hex_bytes"))]
     pub(crate) raw: Vec<u8>,
 }
 
 impl Rfid {
     pub(crate) fn from_raw(raw: Vec<u8>) -> Rfid {
         let rssi = raw[0];
+        let pc = ((raw[1] as u16) << 8) | (raw[2] as u16);
+        let epc_end = 3 + epc_len(pc);
+        let tid_end = crc_start(&raw);
 
         Self {
-            pc: bytes_to_hex_upper(&raw[1..3].to_vec()),
-            epc: bytes_to_hex_upper(&raw[3..15]),
-            crc: bytes_to_hex_upper(&raw[15..17].to_vec()),
+            pc: bytes_to_hex_upper(&raw[1..3]),
+            epc: bytes_to_hex_upper(&raw[3..epc_end]),
+            crc: bytes_to_hex_upper(&raw[tid_end..tid_end + 2]),
+            tid: (tid_end > epc_end).then(|| bytes_to_hex_upper(&raw[epc_end..tid_end])),
+            antenna: None,
             rssi,
             raw,
         }
     }
+
+    /// Decode a tag payload given as a hex string (RSSI, PC, EPC, CRC, as `raw_bytes`), e.g.
+    /// received from another system. Surrounding whitespace is ignored.
+    ///
+    /// Returns
+    /// - Ok(Rfid) with the same fields as a tag read from the device.
+    /// - Err(ParseError::InvalidHex) for an odd number of digits or a non hex character.
+    /// - Err(ParseError::InvalidLength) if the payload is shorter than the EPC length in its PC.
+    pub fn from_hex(s: &str) -> Result<Rfid, ParseError> {
+        let raw = hex_to_bytes(s.trim()).ok_or(ParseError::InvalidHex)?;
+        let len = raw.len();
+        Self::parse(raw).ok_or(ParseError::InvalidLength(len))
+    }
+
+    /// Parse a tag payload, None if it is shorter than the EPC length in its PC.
+    ///
+    /// A payload carrying an embedded TID after the EPC is accepted as well. Bytes
+    /// trailing the CRC, appended by some firmwares, are kept in `raw_bytes`.
+    pub(crate) fn parse(raw: Vec<u8>) -> Option<Rfid> {
+        if raw.len() < MIN_PAYLOAD_LEN || raw.len() < Self::payload_len(pc_word(&raw)) {
+            return None;
+        }
+        Some(Self::from_raw(raw))
+    }
+
+    /// Parse a tag payload from a multi-antenna reader, which starts with the antenna port.
+    ///
+    /// A payload with the standard layout, without the antenna byte, is parsed as usual.
+    pub(crate) fn parse_with_antenna(raw: Vec<u8>) -> Option<Rfid> {
+        // Trailing bytes are not accepted after the antenna byte, they would hide
+        // a standard payload whose first EPC bytes look like a short PC
+        if let Some((&antenna, rest)) = raw.split_first()
+            && rest.len() >= MIN_PAYLOAD_LEN
+            && (rest.len() == Self::payload_len(pc_word(rest))
+                || rest.len() == Self::payload_len(pc_word(rest)) + EMBEDDED_TID_LEN)
+            && let Some(mut rfid) = Self::parse(rest.to_vec())
+        {
+            rfid.antenna = Some(antenna);
+            return Some(rfid);
+        }
+        Self::parse(raw)
+    }
+
+    /// Expected size of a tag payload (RSSI + PC + EPC + CRC) given its PC word
+    pub(crate) fn payload_len(pc: u16) -> usize {
+        1 + 2 + epc_len(pc) + 2
+    }
+}
+
+/// Why a tag payload could not be decoded, see `Rfid::from_hex`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// Odd number of digits, or a character that is not a hex digit
+    InvalidHex,
+    /// Payload of this many bytes, too short for the EPC length in its PC
+    InvalidLength(usize),
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ParseError::InvalidHex => write!(f, "Invalid hex string"),
+            ParseError::InvalidLength(len) => write!(f, "Invalid tag payload length {len}"),
+        }
+    }
+}
+
+impl core::error::Error for ParseError {}
+
+/// RSSI + PC + CRC, a tag with an empty EPC
+const MIN_PAYLOAD_LEN: usize = 1 + 2 + 2;
+
+/// 96 bit TID backscattered after the EPC by Impinj FastID
+const EMBEDDED_TID_LEN: usize = 12;
+
+/// Weakest RSSI the reader can detect, from the protocol manual (0xBA)
+const RSSI_MIN_DBM: i16 = -70;
+/// Reply of a tag held on the antenna, the manual gives no upper bound
+const RSSI_MAX_DBM: i16 = -20;
+
+/// PC word of a tag payload of at least `MIN_PAYLOAD_LEN` bytes
+fn pc_word(raw: &[u8]) -> u16 {
+    ((raw[1] as u16) << 8) | raw[2] as u16
+}
+
+/// Offset of the CRC in a tag payload. The embedded TID sits between the EPC and the CRC,
+/// only present when the length says so; other extra bytes follow the CRC.
+fn crc_start(raw: &[u8]) -> usize {
+    let pc = pc_word(raw);
+    if raw.len() == Rfid::payload_len(pc) + EMBEDDED_TID_LEN {
+        3 + epc_len(pc) + EMBEDDED_TID_LEN
+    } else {
+        3 + epc_len(pc)
+    }
+}
+
+/// The upper 5 bits of the PC word contain the EPC length in words
+fn epc_len(pc: u16) -> usize {
+    (((pc >> 11) & 0x1F) * 2) as usize
 }
 
+/// Two reads are the same tag when both EPC and PC match, RSSI and the other fields are
+/// ignored. Tags sharing an EPC under different numbering systems stay apart; wrap the reads
+/// in `ByEpc` to group them by EPC alone.
 impl Hash for Rfid {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
         self.epc.hash(state);
+        self.pc.hash(state);
     }
 }
 
 impl PartialEq<Self> for Rfid {
     fn eq(&self, other: &Self) -> bool {
-        self.epc == other.epc
+        self.epc == other.epc && self.pc == other.pc
     }
 }
 impl Eq for Rfid {}
 
+/// A read compared and hashed by its EPC only, e.g. `HashSet<ByEpc>` keeps one read per EPC
+#[derive(Clone, Debug)]
+pub struct ByEpc(pub Rfid);
+
+impl Hash for ByEpc {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.0.epc.hash(state);
+    }
+}
+
+impl PartialEq<Self> for ByEpc {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.epc == other.0.epc
+    }
+}
+impl Eq for ByEpc {}
+
 impl Display for Rfid {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(
             f,
             "RSSI: {}, PC: {}, EPC(UID): {:?}, CRC: {}, RAW: {}",
@@ -48,14 +183,313 @@ impl Display for Rfid {
             self.epc,
             self.crc,
             bytes_to_hex_upper(&self.raw)
-        )
+        )?;
+        if let Some(tid) = &self.tid {
+            write!(f, ", TID: {tid}")?;
+        }
+        if let Some(antenna) = self.antenna {
+            write!(f, ", Antenna: {antenna}")?;
+        }
+        Ok(())
     }
 }
 
 impl Rfid {
+    /// EPC of the tag as an uppercase hex string, the same value stored in `epc`
     pub fn uid(&self) -> String {
         self.epc.clone()
     }
+
+    /// EPC of the tag as bytes, the same value as `epc` without the hex round trip
+    pub fn epc_bytes(&self) -> Vec<u8> {
+        self.raw
+            .get(1..3)
+            .map(|pc| 3 + epc_len(((pc[0] as u16) << 8) | pc[1] as u16))
+            .and_then(|end| self.raw.get(3..end))
+            .unwrap_or_default()
+            .to_vec()
+    }
+
+    /// EPC of the tag as a big endian number, None if it is longer than 16 bytes.
+    ///
+    /// Standard 96 bit EPCs (e.g. GS1 SGTIN-96) fit in the low 96 bits.
+    pub fn epc_u128(&self) -> Option<u128> {
+        let epc = self.epc_bytes();
+        if epc.len() > 16 {
+            return None;
+        }
+        Some(epc.iter().fold(0, |acc, &b| (acc << 8) | b as u128))
+    }
+
+    /// Payload of the tag notification as received: RSSI, PC, EPC, the TID when present,
+    /// the CRC and any bytes trailing it. The antenna byte of multi-antenna readers is not
+    /// included.
+    pub fn raw_bytes(&self) -> &[u8] {
+        &self.raw
+    }
+
+    /// Signal strength of the tag reply in dBm.
+    ///
+    /// The reader reports RSSI as a signed byte already expressed in dBm
+    /// (e.g. 0xBA is -70 dBm, the weakest signal it can detect).
+    pub fn rssi_dbm(&self) -> i16 {
+        self.rssi as i8 as i16
+    }
+
+    /// Signal strength as 0 to 100 %, for a signal bar.
+    ///
+    /// Linear between -70 dBm (0 %, the weakest signal the reader detects) and -20 dBm
+    /// (100 %, a tag close to the antenna), clamped outside of that range.
+    pub fn signal_percent(&self) -> u8 {
+        let dbm = self.rssi_dbm().clamp(RSSI_MIN_DBM, RSSI_MAX_DBM);
+        ((dbm - RSSI_MIN_DBM) * 100 / (RSSI_MAX_DBM - RSSI_MIN_DBM)) as u8
+    }
+
+    /// Check the CRC backscattered by the tag against the CRC-16 of its PC + EPC.
+    ///
+    /// The R200 forwards the tag CRC without checking it, false means the EPC or PC
+    /// was corrupted on the air and the read should be discarded.
+    pub fn crc_valid(&self) -> bool {
+        if self.raw.len() < MIN_PAYLOAD_LEN {
+            return false;
+        }
+        let crc_start = crc_start(&self.raw);
+        if self.raw.len() < crc_start + 2 {
+            return false;
+        }
+        let reported = ((self.raw[crc_start] as u16) << 8) | self.raw[crc_start + 1] as u16;
+        crc16_gen2(&self.raw[1..crc_start]) == reported
+    }
+
+    /// Decode the fields of the PC word, e.g. to check the UMI bit before reading the User bank
+    pub fn pc_info(&self) -> PcInfo {
+        PcInfo::from_pc(u16::from_str_radix(&self.pc, 16).unwrap_or_default())
+    }
+}
+
+/// Fields of the Gen2 Protocol Control word backscattered before the EPC
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PcInfo {
+    /// Length of the EPC in 16-bit words (bits 15-11)
+    pub epc_word_len: u8,
+    /// User memory indicator, set when the User bank holds data (bit 10)
+    pub umi: bool,
+    /// XPC indicator, set when an extended PC word follows (bit 9)
+    pub xi: bool,
+    /// Numbering system toggle: false for an EPCglobal EPC, true for an ISO AFI (bit 8)
+    pub toggle: bool,
+    /// EPCglobal attribute bits or ISO AFI, depending on `toggle` (bits 7-0)
+    pub numbering_system: u8,
+}
+
+impl PcInfo {
+    /// Split a raw PC word, as found in `Rfid::pc`
+    pub fn from_pc(pc: u16) -> PcInfo {
+        PcInfo {
+            epc_word_len: ((pc >> 11) & 0x1F) as u8,
+            umi: pc & 0x0400 != 0,
+            xi: pc & 0x0200 != 0,
+            toggle: pc & 0x0100 != 0,
+            numbering_system: (pc & 0xFF) as u8,
+        }
+    }
+}
+
+/// CRC-16 of EPC Gen2 (ISO/IEC 13239): polynomial 0x1021, preset 0xFFFF, output inverted
+fn crc16_gen2(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0xFFFF;
+    for &b in data {
+        crc ^= (b as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Repeated sightings of the same EPC during an inventory.
+///
+/// RSSI values are the raw bytes reported by the reader, as in `Rfid::rssi`.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct TagObservation {
+    pub epc: String,
+    pub reads: u32,
+    pub rssi_min: u8,
+    pub rssi_max: u8,
+    pub rssi_mean: f32,
+}
+
+impl TagObservation {
+    /// Group tags by EPC, in the order each EPC was first seen
+    pub fn aggregate<'a>(tags: impl IntoIterator<Item = &'a Rfid>) -> Vec<TagObservation> {
+        let mut observations: Vec<TagObservation> = Vec::new();
+        let mut index: BTreeMap<&str, usize> = BTreeMap::new();
+        for tag in tags {
+            match index.get(tag.epc.as_str()) {
+                Some(&i) => observations[i].add(tag),
+                None => {
+                    index.insert(tag.epc.as_str(), observations.len());
+                    observations.push(TagObservation::first(tag));
+                }
+            }
+        }
+        observations
+    }
+
+    fn first(tag: &Rfid) -> TagObservation {
+        TagObservation {
+            epc: tag.epc.clone(),
+            reads: 1,
+            rssi_min: tag.rssi,
+            rssi_max: tag.rssi,
+            rssi_mean: tag.rssi as f32,
+        }
+    }
+
+    fn add(&mut self, tag: &Rfid) {
+        self.reads += 1;
+        self.rssi_min = self.rssi_min.min(tag.rssi);
+        self.rssi_max = self.rssi_max.max(tag.rssi);
+        self.rssi_mean += (tag.rssi as f32 - self.rssi_mean) / self.reads as f32;
+    }
+}
+
+/// Tags grouped by EPC as they are read, with the time each EPC was first and last seen.
+///
+/// Inventory results can be folded into it with `extend`:
+///
+/// ```no_run
+/// # use r200_uhf::connector::{Connector, sync::SyncIO};
+/// # use r200_uhf::TagSet;
+/// # fn run(connector: &mut Connector<Box<dyn serialport::SerialPort>>) -> Result<(), Box<dyn std::error::Error>> {
+/// let mut tags = TagSet::new();
+/// for _ in 0..10 {
+///     tags.extend(connector.multi_polling_instruction()?);
+/// }
+/// for o in tags.iter() {
+///     println!("{} read {} times", o.epc, o.reads);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default)]
+pub struct TagSet {
+    observations: Vec<TagObservation>,
+    seen: Vec<(std::time::Instant, std::time::Instant)>,
+    index: BTreeMap<String, usize>,
+}
+
+#[cfg(feature = "std")]
+impl TagSet {
+    pub fn new() -> TagSet {
+        TagSet::default()
+    }
+
+    /// Count a read of `tag`, seen now
+    pub fn insert(&mut self, tag: &Rfid) {
+        let now = std::time::Instant::now();
+        match self.index.get(tag.epc.as_str()) {
+            Some(&i) => {
+                self.observations[i].add(tag);
+                self.seen[i].1 = now;
+            }
+            None => {
+                self.index.insert(tag.epc.clone(), self.observations.len());
+                self.observations.push(TagObservation::first(tag));
+                self.seen.push((now, now));
+            }
+        }
+    }
+
+    /// Number of distinct EPCs
+    pub fn len(&self) -> usize {
+        self.observations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.observations.is_empty()
+    }
+
+    /// Observations in the order each EPC was first seen
+    pub fn iter(&self) -> impl Iterator<Item = &TagObservation> {
+        self.observations.iter()
+    }
+
+    pub fn get(&self, epc: &str) -> Option<&TagObservation> {
+        self.index.get(epc).map(|&i| &self.observations[i])
+    }
+
+    /// When the first read of `epc` was inserted
+    pub fn first_seen(&self, epc: &str) -> Option<std::time::Instant> {
+        self.index.get(epc).map(|&i| self.seen[i].0)
+    }
+
+    /// When the last read of `epc` was inserted
+    pub fn last_seen(&self, epc: &str) -> Option<std::time::Instant> {
+        self.index.get(epc).map(|&i| self.seen[i].1)
+    }
+}
+
+/// A tag with the moment its frame came back from the serial port.
+///
+/// The timestamp is read from the connector clock (see `Connector::set_clock`) once per read
+/// of the port: tags received in the same read share it, tags spread over several reads,
+/// e.g. a gate passage across polls, can be ordered and timed by it.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimedRfid {
+    pub rfid: Rfid,
+    /// Time of the connector clock when the read returning the frame completed
+    pub timestamp: core::time::Duration,
+}
+
+#[cfg(feature = "std")]
+impl Extend<Rfid> for TagSet {
+    fn extend<T: IntoIterator<Item = Rfid>>(&mut self, iter: T) {
+        for tag in iter {
+            self.insert(&tag);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> Extend<&'a Rfid> for TagSet {
+    fn extend<T: IntoIterator<Item = &'a Rfid>>(&mut self, iter: T) {
+        for tag in iter {
+            self.insert(tag);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl FromIterator<Rfid> for TagSet {
+    fn from_iter<T: IntoIterator<Item = Rfid>>(iter: T) -> TagSet {
+        let mut set = TagSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+/// Bytes of a hex string, either case, None if a digit is missing or invalid
+fn hex_to_bytes(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    s.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let hi = (pair[0] as char).to_digit(16)?;
+            let lo = (pair[1] as char).to_digit(16)?;
+            Some((hi << 4 | lo) as u8)
+        })
+        .collect()
 }
 
 fn bytes_to_hex_upper(bytes: &[u8]) -> String {
@@ -67,10 +501,44 @@ fn bytes_to_hex_upper(bytes: &[u8]) -> String {
     s
 }
 
+/// Serialize the raw payload as the same uppercase hex string shown by Display
+#[cfg(feature = "serde")]
+mod hex_bytes {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&super::bytes_to_hex_upper(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        super::hex_to_bytes(&s).ok_or_else(|| D::Error::custom("invalid hex string"))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    // Helper: tag with a 96 bit EPC of 0xE2 bytes ending with `epc_last`
+    fn make_tag(rssi: u8, epc_last: u8) -> Rfid {
+        let mut bytes = vec![rssi, 0x30, 0x00];
+        bytes.extend([0xE2; 11]);
+        bytes.extend([epc_last, 0x12, 0x34]);
+        Rfid::from_raw(bytes)
+    }
+
+    // Helper: payload of the sample tag, RSSI 0xBC and a 96 bit EPC
+    fn sample_bytes() -> Vec<u8> {
+        Rfid::from_hex("BC3000E28069150000501D63E2784FB0B7")
+            .unwrap()
+            .raw_bytes()
+            .to_vec()
+    }
+
     #[test]
     fn test_parsing_rfid() {
         let intake = "BC3000E28069150000501D63E2784FB0B7";
@@ -87,4 +555,276 @@ mod tests {
         assert_eq!(packet.epc, "E28069150000501D63E2784F");
         assert_eq!(packet.crc, "B0B7");
     }
+
+    #[test]
+    fn test_parsing_rfid_128bit_epc() {
+        // PC 0x4000 -> 8 words -> 16 bytes of EPC
+        let mut bytes = vec![0xC9, 0x40, 0x00];
+        bytes.extend(0x10..0x20);
+        bytes.extend([0x3A, 0x76]);
+
+        assert_eq!(Rfid::payload_len(0x4000), bytes.len());
+        let packet = Rfid::from_raw(bytes);
+
+        assert_eq!(packet.pc, "4000");
+        assert_eq!(packet.epc, "101112131415161718191A1B1C1D1E1F");
+        assert_eq!(packet.crc, "3A76");
+    }
+
+    #[test]
+    fn test_pc_info() {
+        let info = PcInfo::from_pc(0x3000);
+        assert_eq!(info.epc_word_len, 6);
+        assert!(!info.umi);
+        assert!(!info.xi);
+        assert!(!info.toggle);
+        assert_eq!(info.numbering_system, 0);
+
+        let info = PcInfo::from_pc(0x3705);
+        assert_eq!(info.epc_word_len, 6);
+        assert!(info.umi);
+        assert!(info.xi);
+        assert!(info.toggle);
+        assert_eq!(info.numbering_system, 0x05);
+
+        let mut bytes = vec![0xC9, 0x44, 0x00];
+        bytes.extend(0x10..0x20);
+        bytes.extend([0x3A, 0x76]);
+        let tag = Rfid::from_raw(bytes);
+        assert_eq!(tag.pc_info().epc_word_len, 8);
+        assert!(tag.pc_info().umi);
+    }
+
+    #[test]
+    fn test_signal_percent() {
+        let tag = |rssi| make_tag(rssi, 0xE2);
+        // -70 dBm and weaker
+        assert_eq!(tag(0xBA).signal_percent(), 0);
+        assert_eq!(tag(0x80).signal_percent(), 0);
+        // -45 dBm
+        assert_eq!(tag(0xD3).signal_percent(), 50);
+        // -20 dBm and stronger
+        assert_eq!(tag(0xEC).signal_percent(), 100);
+        assert_eq!(tag(0x05).signal_percent(), 100);
+    }
+
+    #[test]
+    fn test_raw_bytes_untouched() {
+        let mut bytes = vec![0xC9, 0x30, 0x00];
+        bytes.extend(0x01..=0x0C);
+        bytes.extend([0xAB, 0xCD]);
+        let tag = Rfid::parse_with_antenna([&[0x02][..], &bytes].concat()).unwrap();
+        assert_eq!(tag.antenna, Some(2));
+        assert_eq!(tag.raw_bytes(), bytes.as_slice());
+    }
+
+    #[test]
+    fn test_epc_bytes_and_u128() {
+        let mut bytes = vec![0xC9, 0x30, 0x00];
+        bytes.extend(0x01..=0x0C);
+        bytes.extend([0xAB, 0xCD]);
+        let tag = Rfid::from_raw(bytes);
+        assert_eq!(tag.epc_bytes(), (0x01..=0x0C).collect::<Vec<u8>>());
+        assert_eq!(tag.epc_u128(), Some(0x0102_0304_0506_0708_090A_0B0C));
+
+        // 256 bit EPC, PC length field 16 words
+        let mut bytes = vec![0xC9, 0x80, 0x00];
+        bytes.extend([0xE2; 32]);
+        bytes.extend([0xAB, 0xCD]);
+        let tag = Rfid::from_raw(bytes);
+        assert_eq!(tag.epc_bytes().len(), 32);
+        assert_eq!(tag.epc_u128(), None);
+    }
+
+    #[test]
+    fn test_eq_uses_epc_and_pc() {
+        let mut bytes = vec![0xC9, 0x30, 0x00];
+        bytes.extend([0xE2; 12]);
+        bytes.extend([0x12, 0x34]);
+        let a = Rfid::from_raw(bytes.clone());
+        bytes[0] = 0xB0;
+        let same = Rfid::from_raw(bytes.clone());
+        // Same EPC, toggle bit set: another numbering system
+        bytes[1] = 0x31;
+        let other_pc = Rfid::from_raw(bytes);
+
+        assert_eq!(a, same);
+        assert_ne!(a, other_pc);
+        assert_eq!(ByEpc(a.clone()), ByEpc(other_pc.clone()));
+
+        let set: std::collections::HashSet<_> = [a.clone(), same.clone(), other_pc.clone()].into();
+        assert_eq!(set.len(), 2);
+        let set: std::collections::HashSet<_> = [a, same, other_pc].map(ByEpc).into();
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_rejects_short_payload() {
+        assert!(Rfid::parse(vec![0xC9, 0x30, 0x00, 0xE2, 0x80, 0x68]).is_none());
+        assert!(Rfid::parse(vec![0xC9, 0x30]).is_none());
+        let mut bytes = vec![0xC9, 0x30, 0x00];
+        bytes.extend([0u8; 14]);
+        assert!(Rfid::parse(bytes).is_some());
+    }
+
+    #[test]
+    fn test_from_hex_errors() {
+        assert_eq!(
+            Rfid::from_hex(" bc3000e28069150000501d63e2784fb0b7\n")
+                .unwrap()
+                .uid(),
+            "E28069150000501D63E2784F"
+        );
+        assert_eq!(Rfid::from_hex("BC300"), Err(ParseError::InvalidHex));
+        assert_eq!(Rfid::from_hex("BC30ZZ"), Err(ParseError::InvalidHex));
+        assert_eq!(Rfid::from_hex("+C3000"), Err(ParseError::InvalidHex));
+        assert_eq!(
+            Rfid::from_hex("BC3000E280"),
+            Err(ParseError::InvalidLength(5))
+        );
+    }
+
+    #[test]
+    fn test_parse_embedded_tid() {
+        let bytes = sample_bytes();
+        assert_eq!(Rfid::parse(bytes.clone()).unwrap().tid, None);
+
+        let mut extended = bytes[..15].to_vec();
+        extended.extend([
+            0xE2, 0x80, 0x11, 0x05, 0x20, 0x00, 0x71, 0x2A, 0x9B, 0x3C, 0x08, 0x01,
+        ]);
+        extended.extend([0x12, 0x34]);
+        let tag = Rfid::parse(extended).unwrap();
+        assert_eq!(tag.epc, "E28069150000501D63E2784F");
+        assert_eq!(tag.tid.as_deref(), Some("E28011052000712A9B3C0801"));
+        assert_eq!(tag.crc, "1234");
+
+        // Shorter than the EPC length in the PC
+        assert!(Rfid::parse(bytes[..16].to_vec()).is_none());
+    }
+
+    #[test]
+    fn test_parse_keeps_trailing_bytes() {
+        let mut bytes = sample_bytes();
+        bytes.extend([0x01, 0x02, 0x03, 0x04]);
+        let tag = Rfid::parse(bytes.clone()).unwrap();
+        assert_eq!(tag.epc, "E28069150000501D63E2784F");
+        assert_eq!(tag.crc, "B0B7");
+        assert_eq!(tag.tid, None);
+        assert!(tag.crc_valid());
+        assert_eq!(tag.raw_bytes(), bytes.as_slice());
+    }
+
+    #[test]
+    fn test_parse_with_antenna() {
+        let bytes = sample_bytes();
+
+        let mut with_port = vec![0x03];
+        with_port.extend(&bytes);
+        let tag = Rfid::parse_with_antenna(with_port).unwrap();
+        assert_eq!(tag.antenna, Some(3));
+        assert_eq!(tag.rssi, 0xBC);
+        assert_eq!(tag.epc, "E28069150000501D63E2784F");
+        assert!(tag.crc_valid());
+
+        // Standard layout: no antenna byte
+        let tag = Rfid::parse_with_antenna(bytes.clone()).unwrap();
+        assert_eq!(tag.antenna, None);
+        assert_eq!(tag.epc, "E28069150000501D63E2784F");
+        // Without the flag the extra byte makes the payload malformed
+        let mut with_port = vec![0x03];
+        with_port.extend(&bytes);
+        assert!(Rfid::parse(with_port).is_none());
+    }
+
+    #[test]
+    fn test_crc_valid() {
+        let mut bytes = sample_bytes();
+        assert!(Rfid::from_raw(bytes.clone()).crc_valid());
+
+        // Single bit error in the EPC
+        bytes[5] ^= 0x01;
+        assert!(!Rfid::from_raw(bytes).crc_valid());
+
+        // Tag from the protocol manual: PC 3400, CRC 3A76
+        let mut bytes = vec![0xC9, 0x34, 0x00];
+        bytes.extend([
+            0x30, 0x75, 0x1F, 0xEB, 0x70, 0x5C, 0x59, 0x04, 0xE3, 0xD5, 0x0D, 0x70, 0x3A, 0x76,
+        ]);
+        assert!(Rfid::from_raw(bytes).crc_valid());
+    }
+
+    #[test]
+    fn test_tag_observation_aggregate() {
+        let tags = [
+            make_tag(0xC0, 1),
+            make_tag(0xBA, 2),
+            make_tag(0xC4, 1),
+            make_tag(0xC2, 1),
+        ];
+        let observations = TagObservation::aggregate(&tags);
+        assert_eq!(observations.len(), 2);
+        assert_eq!(observations[0].epc, tags[0].epc);
+        assert_eq!(observations[0].reads, 3);
+        assert_eq!(observations[0].rssi_min, 0xC0);
+        assert_eq!(observations[0].rssi_max, 0xC4);
+        assert!((observations[0].rssi_mean - 0xC2 as f32).abs() < 1e-3);
+        assert_eq!(observations[1].reads, 1);
+        assert_eq!(observations[1].rssi_mean, 0xBA as f32);
+    }
+
+    #[test]
+    fn test_tag_set() {
+        let mut set: TagSet = [make_tag(0xC0, 1), make_tag(0xBA, 2)].into_iter().collect();
+        let epc = make_tag(0, 1).epc;
+        let first = set.first_seen(&epc).unwrap();
+        set.extend([make_tag(0xC4, 1), make_tag(0xC2, 1)]);
+
+        assert_eq!(set.len(), 2);
+        let observations: Vec<_> = set.iter().cloned().collect();
+        assert_eq!(
+            observations,
+            TagObservation::aggregate(&[
+                make_tag(0xC0, 1),
+                make_tag(0xBA, 2),
+                make_tag(0xC4, 1),
+                make_tag(0xC2, 1)
+            ])
+        );
+        assert_eq!(set.get(&epc).unwrap().reads, 3);
+        assert_eq!(set.first_seen(&epc), Some(first));
+        assert!(set.last_seen(&epc).unwrap() >= first);
+        assert!(set.get("00").is_none());
+    }
+
+    #[test]
+    fn test_rssi_dbm() {
+        for (raw, dbm) in [
+            (0xBA, -70),
+            (0xBC, -68),
+            (0xC9, -55),
+            (0xFF, -1),
+            (0x80, -128),
+        ] {
+            assert_eq!(make_tag(raw, 0x01).rssi_dbm(), dbm, "raw 0x{raw:02X}");
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_json_round_trip() {
+        let mut bytes = vec![0xBC, 0x30, 0x00];
+        bytes.extend([
+            0xE2, 0x80, 0x69, 0x15, 0x00, 0x00, 0x50, 0x1D, 0x63, 0xE2, 0x78, 0x4F, 0xB0, 0xB7,
+        ]);
+        let tag = Rfid::from_raw(bytes);
+
+        let json = serde_json::to_string(&tag).unwrap();
+        assert!(json.contains("\"raw\":\"BC3000E28069150000501D63E2784FB0B7\""));
+
+        let back: Rfid = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.uid(), tag.uid());
+        assert_eq!(back.rssi, tag.rssi);
+        assert_eq!(back.raw, tag.raw);
+    }
 }